@@ -0,0 +1,138 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::json;
+use crate::view::View;
+use crate::{ diagnostics, embed };
+
+// screen pixels per frame the arrow keys move the cursor, shift moves it faster
+pub const CURSOR_SPEED: f32 = 3.0;
+pub const CURSOR_FAST: f32 = 12.0;
+// how long the last announcement stays on screen, in seconds
+const ANNOUNCEMENT_SECONDS: f64 = 4.0;
+
+// stands in for the mouse when working from the keyboard, kept in screen space so the
+// arrow keys move it the same way whatever the view is doing
+pub struct KeyboardCursor {
+    pub position: Vec2,
+    // only drawn once the arrow keys have been used
+    pub visible: bool,
+}
+
+impl Default for KeyboardCursor {
+    fn default() -> Self {
+        KeyboardCursor {
+            position: vec2(screen_width(), screen_height()) / 2.0,
+            visible: false,
+        }
+    }
+}
+
+impl KeyboardCursor {
+    pub fn nudge(&mut self, amount: Vec2) {
+        let bounds = vec2(screen_width(), screen_height());
+        self.position = (self.position + amount).clamp(Vec2::ZERO, bounds);
+        self.visible = true;
+    }
+
+    pub fn world(&self, view: &View) -> Vec2 {
+        view.screen_to_world(self.position)
+    }
+
+    pub fn draw(&self) {
+        if !self.visible {
+            return;
+        }
+        let Vec2 { x, y } = self.position;
+        draw_line(x - 8.0, y, x + 8.0, y, 1.0, GREEN);
+        draw_line(x, y - 8.0, x, y + 8.0, 1.0, GREEN);
+    }
+}
+
+// state changes as plain sentences: printed to stdout, where a terminal screen reader picks
+// them up, and shown on a status line at the bottom of the window
+#[derive(Default)]
+pub struct Announcer {
+    // keeps describing the selected body on the status line
+    pub verbose: bool,
+    last: Option<(String, f64)>,
+}
+
+impl Announcer {
+    pub fn say(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        println!("{}", message);
+        embed::emit("message", &[("text", json::quote(&message))]);
+        self.last = Some((message, get_time()));
+    }
+
+    pub fn draw(&self, bodies: &Bodies) {
+        let mut y = screen_height() - 12.0;
+        if self.verbose {
+            let status = match bodies.selected.iter().position(|&selected| selected) {
+                Some(index) => describe(bodies, index),
+                None => "nothing selected, tab selects the next body".to_string(),
+            };
+            draw_text(&status, 10.0, y, 18.0, LIGHTGRAY);
+            y -= 20.0;
+        }
+        if let Some((message, said_at)) = &self.last {
+            if get_time() - said_at < ANNOUNCEMENT_SECONDS {
+                draw_text(message, 10.0, y, 18.0, WHITE);
+            }
+        }
+    }
+}
+
+// selects only the next (or previous) body after the current selection and returns it
+pub fn cycle_selection(bodies: &mut Bodies, forward: bool) -> Option<usize> {
+    let count = bodies.len();
+    if count == 0 {
+        return None;
+    }
+
+    let next = match bodies.selected.iter().position(|&selected| selected) {
+        Some(current) if forward => (current + 1) % count,
+        Some(current) => (current + count - 1) % count,
+        None if forward => 0,
+        None => count - 1,
+    };
+    bodies.selected = vec![false; count];
+    bodies.selected[next] = true;
+    Some(next)
+}
+
+// a sentence about where a body is and how it moves, for the status line and announcements
+pub fn describe(bodies: &Bodies, index: usize) -> String {
+    let body = bodies.body(index);
+    let speed = body.velocity.length();
+    // counterclockwise from the right like on paper, screen y grows downwards
+    let heading = (-body.velocity.y).atan2(body.velocity.x).to_degrees().rem_euclid(360.0);
+    let nearest = bodies.positions
+        .iter()
+        .enumerate()
+        .filter(|&(other, _)| other != index)
+        .map(|(_, position)| position.distance(body.position))
+        .min_by(f32::total_cmp);
+    let from_center = body.position.distance(diagnostics::center_of_mass(bodies));
+
+    let kind = if body.black_hole { "black hole" } else { "body" };
+    let mut description = format!(
+        "{} {} at ({:.0}, {:.0}), mass {:.0}, ",
+        kind,
+        body.id,
+        body.position.x,
+        body.position.y,
+        body.mass
+    );
+    if body.freezed {
+        description.push_str("frozen");
+    } else {
+        description.push_str(&format!("speed {:.2} heading {:.0} degrees", speed, heading));
+    }
+    if let Some(nearest) = nearest {
+        description.push_str(&format!(", nearest body {:.0} away", nearest));
+    }
+    description.push_str(&format!(", {:.0} from the center of mass", from_center));
+    description
+}
@@ -0,0 +1,209 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::ewald;
+use crate::metadata::Metadata;
+use crate::prediction;
+use crate::settings::Settings;
+use crate::view::View;
+
+// launch directions tried either side of straight at the target, and how far either side
+const DIRECTIONS: usize = 33;
+const SPREAD: f32 = PI / 4.0;
+// launch speeds tried, as multiples of the speed of a circular orbit around the target at the
+// distance the body starts from
+const SPEEDS: usize = 9;
+const MIN_SPEED: f32 = 0.5;
+const MAX_SPEED: f32 = 2.0;
+// the flyby is over once the body is this many times further than at its closest
+const DEPARTURE: f32 = 2.0;
+// trajectories kept for the overlay, and every how many steps they have a point
+const CANDIDATES: usize = 5;
+const PATH_STRIDE: usize = 4;
+
+// one headless trial that swung past the target
+pub struct Candidate {
+    pub launch: Vec2,
+    // where it left the flyby for, counterclockwise from the right in degrees
+    pub heading: f32,
+    // off the heading asked for, in degrees
+    pub error: f32,
+    pub closest: f32,
+    // what the flyby gave or took, the speed relative to the target stays the same
+    pub departure_speed: f32,
+    path: Vec<Vec2>,
+}
+
+// launch velocities for `body` that swing it past `target` and off in the direction asked
+// for, found by trying a fan of them ahead of time on the bodies pulling hardest on it
+pub struct Plan {
+    pub body: u64,
+    pub target: u64,
+    pub heading: f32,
+    pub trials: usize,
+    // best first
+    pub candidates: Vec<Candidate>,
+}
+
+impl Plan {
+    // for the first selected body, `heading` in degrees counterclockwise from the right
+    pub fn search(
+        bodies: &Bodies,
+        target: u64,
+        heading: f32,
+        settings: &Settings
+    ) -> Result<Self, String> {
+        let index = bodies.selected
+            .iter()
+            .position(|&selected| selected)
+            .ok_or("select the body to send past the target first")?;
+        let target_index = bodies.ids
+            .iter()
+            .position(|&id| id == target)
+            .ok_or_else(|| format!("no body with id {}", target))?;
+        if target_index == index {
+            return Err("a body can't fly past itself".to_string());
+        }
+
+        // `index` first and the target along whether or not it pulls hard
+        let mut system = prediction::neighbourhood(bodies, index, settings.gravity);
+        let flyby = match system.ids.iter().position(|&id| id == target) {
+            Some(flyby) => flyby,
+            None => {
+                system.push(bodies.body(target_index));
+                system.len() - 1
+            }
+        };
+        let offset = bodies.positions[target_index] - bodies.positions[index];
+        let mu = settings.gravity * bodies.masses[target_index];
+        let circular = (mu / offset.length().max(1.0)).sqrt();
+
+        let mut trials = 0;
+        let mut candidates = Vec::new();
+        for i in 0..DIRECTIONS {
+            let turn = ((i as f32) / ((DIRECTIONS - 1) as f32) - 0.5) * 2.0 * SPREAD;
+            let direction = Vec2::from_angle(turn).rotate(offset.normalize_or_zero());
+            for j in 0..SPEEDS {
+                let fraction = (j as f32) / ((SPEEDS - 1) as f32);
+                let speed = circular * (MIN_SPEED + (MAX_SPEED - MIN_SPEED) * fraction);
+                let launch = bodies.velocities[target_index] + direction * speed;
+                trials += 1;
+                if let Some(mut candidate) = trial(&system, flyby, launch, settings) {
+                    candidate.error = angle_between(candidate.heading, heading);
+                    candidates.push(candidate);
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.error.total_cmp(&b.error));
+        candidates.truncate(CANDIDATES);
+        Ok(Plan { body: bodies.ids[index], target, heading, trials, candidates })
+    }
+
+    pub fn summary(&self, metadata: &Metadata) -> String {
+        let Some(best) = self.candidates.first() else {
+            return format!(
+                "none of {} trials swung past {} within the horizon",
+                self.trials,
+                metadata.label(self.target)
+            );
+        };
+        format!(
+            "best of {} trials: launch {} heading {:.0} at speed {:.3}, passes {} at {:.1} and \
+             leaves heading {:.0} ({:.0} off {:.0}) at speed {:.3}. `assist apply` to launch",
+            self.trials,
+            metadata.label(self.body),
+            heading(best.launch),
+            best.launch.length(),
+            metadata.label(self.target),
+            best.closest,
+            best.heading,
+            best.error,
+            self.heading,
+            best.departure_speed
+        )
+    }
+
+    pub fn apply(&self, bodies: &mut Bodies) -> Result<Vec2, String> {
+        let best = self.candidates.first().ok_or("no trajectory to launch on")?;
+        let index = bodies.ids
+            .iter()
+            .position(|&id| id == self.body)
+            .ok_or("the body is gone")?;
+        if bodies.freezed[index] {
+            return Err("the body is frozen".to_string());
+        }
+        bodies.velocities[index] = best.launch;
+        bodies.still_steps[index] = 0;
+        Ok(best.launch)
+    }
+
+    // the candidate trajectories from where the body was when planned, the best one brightest
+    pub fn draw(&self, view: &View) {
+        for (rank, candidate) in self.candidates.iter().enumerate().rev() {
+            let color = if rank == 0 { GOLD } else { Color::new(0.6, 0.6, 0.6, 0.35) };
+            for pair in candidate.path.windows(2) {
+                // jumps across the seam of a periodic world aren't part of the path
+                if ewald::crosses_seam(pair[0], pair[1]) {
+                    continue;
+                }
+                let (from, to) = (view.world_to_screen(pair[0]), view.world_to_screen(pair[1]));
+                draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+            }
+        }
+    }
+}
+
+// steps a copy of the system with body 0 launched at `launch`, the candidate once it is past
+// its closest approach to `flyby` and on its way out. None if it hits something or hasn't
+// left by the horizon
+fn trial(system: &Bodies, flyby: usize, launch: Vec2, settings: &Settings) -> Option<Candidate> {
+    let mut system = system.clone();
+    system.velocities[0] = launch;
+    let contact = system.body(0).contact_distance(&system.body(flyby), &settings.hacks);
+    let mut path = vec![system.positions[0]];
+    let mut closest = f32::INFINITY;
+    let mut outcome = None;
+
+    prediction::advance(&mut system, settings, |step, system| {
+        if step % PATH_STRIDE == 0 {
+            path.push(system.positions[0]);
+        }
+        for j in 1..system.len() {
+            let reach = system.body(0).contact_distance(&system.body(j), &settings.hacks);
+            if system.positions[0].distance(system.positions[j]) < reach {
+                return false;
+            }
+        }
+        let distance = system.positions[0].distance(system.positions[flyby]);
+        if distance < closest {
+            closest = distance;
+        } else if distance > DEPARTURE * closest.max(contact) {
+            outcome = Some(system.velocities[0]);
+            return false;
+        }
+        true
+    });
+
+    let departure = outcome?;
+    path.push(system.positions[0]);
+    Some(Candidate {
+        launch,
+        heading: heading(departure),
+        error: 0.0,
+        closest,
+        departure_speed: departure.length(),
+        path,
+    })
+}
+
+// counterclockwise from the right like on paper, screen y grows downwards
+fn heading(velocity: Vec2) -> f32 {
+    (-velocity.y).atan2(velocity.x).to_degrees().rem_euclid(360.0)
+}
+
+fn angle_between(a: f32, b: f32) -> f32 {
+    let difference = (a - b).rem_euclid(360.0);
+    difference.min(360.0 - difference)
+}
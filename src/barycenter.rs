@@ -0,0 +1,59 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::diagnostics;
+use crate::view::View;
+
+// the pairs grow with the square of the selection, past this many bodies only the center
+// of mass is marked
+const MAX_PAIRED: usize = 8;
+const CENTER_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.8);
+const PAIR_COLOR: Color = Color::new(1.0, 0.8, 0.3, 0.8);
+// between the two bodies of a pair, their barycenter is on it
+const LINK_COLOR: Color = Color::new(1.0, 0.8, 0.3, 0.2);
+
+// where the whole system balances, and where every pair of selected bodies does: the point
+// a binary's members circle around
+#[derive(Default)]
+pub struct Barycenters {
+    pub center_of_mass: bool,
+    pub pairs: bool,
+}
+
+impl Barycenters {
+    pub fn draw(&self, bodies: &Bodies, view: &View) {
+        if self.center_of_mass && !bodies.is_empty() {
+            let Vec2 { x, y } = view.world_to_screen(diagnostics::center_of_mass(bodies));
+            draw_circle_lines(x, y, 6.0, 1.0, CENTER_COLOR);
+            draw_line(x - 9.0, y, x + 9.0, y, 1.0, CENTER_COLOR);
+            draw_line(x, y - 9.0, x, y + 9.0, 1.0, CENTER_COLOR);
+        }
+        if !self.pairs {
+            return;
+        }
+        let selected: Vec<usize> = (0..bodies.len()).filter(|&i| bodies.selected[i]).collect();
+        if selected.len() > MAX_PAIRED {
+            return;
+        }
+        for (n, &i) in selected.iter().enumerate() {
+            for &j in &selected[n + 1..] {
+                let (a, b) = (bodies.positions[i], bodies.positions[j]);
+                let mass = bodies.masses[i] + bodies.masses[j];
+                if mass <= 0.0 {
+                    continue;
+                }
+                let center = (a * bodies.masses[i] + b * bodies.masses[j]) / mass;
+                let (a, b) = (view.world_to_screen(a), view.world_to_screen(b));
+                let Vec2 { x, y } = view.world_to_screen(center);
+                draw_line(a.x, a.y, b.x, b.y, 1.0, LINK_COLOR);
+                // a small diamond
+                let corners = [(0.0, -5.0), (5.0, 0.0), (0.0, 5.0), (-5.0, 0.0)]
+                    .map(|(dx, dy)| vec2(x + dx, y + dy));
+                for k in 0..corners.len() {
+                    let (from, to) = (corners[k], corners[(k + 1) % corners.len()]);
+                    draw_line(from.x, from.y, to.x, to.y, 1.0, PAIR_COLOR);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use macroquad::miniquad::window::dpi_scale;
+use macroquad::models::{ draw_mesh, Mesh, Vertex };
+use macroquad::prelude::*;
+
+use crate::style::{ self, BodyStyle };
+
+// texels across one cell of the atlas, and the transparent border around what's in it so
+// linear filtering doesn't bleed between cells
+const CELL: u16 = 64;
+const PADDING: f32 = 2.0;
+// ring widths as a share of the radius, thickest first. Each body takes the nearest
+const RING_WIDTHS: [f32; 8] = [0.5, 0.35, 0.25, 0.18, 0.125, 0.09, 0.0625, 0.045];
+const DISC_CELL: usize = 0;
+const SPRITE_CELL: usize = 1;
+const FIRST_RING_CELL: usize = 2;
+// bodies bigger than a cell in real pixels would come out blurry, they're drawn one at a time
+const MAX_RADIUS: f32 = (CELL as f32) / 2.0 - PADDING;
+// macroquad splits draw calls at 5000 indices, this many quads just fit in one
+const MAX_QUADS: usize = 800;
+
+// every body a textured quad out of one atlas of discs, shaded sprites and rings of a few
+// widths, in as few draw calls as fit, instead of dozens of little triangles and lines each.
+// Bodies are drawn in the order they're added, `flush` before drawing anything else in
+// between
+pub struct BodyBatch {
+    atlas: Texture2D,
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+}
+
+impl BodyBatch {
+    pub fn new() -> Self {
+        let atlas = Texture2D::from_image(&atlas());
+        atlas.set_filter(FilterMode::Linear);
+        BodyBatch {
+            atlas,
+            vertices: Vec::with_capacity(4 * MAX_QUADS),
+            indices: Vec::with_capacity(6 * MAX_QUADS),
+        }
+    }
+
+    // `radius` on screen, and `scale` how much the view magnifies the body
+    pub fn add(&mut self, style: BodyStyle, position: Vec2, radius: f32, scale: f32, color: Color) {
+        // positions and sizes are in points, a high DPI display has more pixels to each
+        if radius * dpi_scale() > MAX_RADIUS {
+            self.flush();
+            self.draw_one(style, position, radius, scale, color);
+            return;
+        }
+        let cell = match style {
+            BodyStyle::Filled => DISC_CELL,
+            BodyStyle::Sprite => SPRITE_CELL,
+            BodyStyle::Outline => {
+                // rings thinner than a pixel would fade away in the texture, the lines they
+                // stand in for don't
+                let width = style::outline_width(radius, scale).max(1.0) / radius.max(1.0);
+                let nearest = (0..RING_WIDTHS.len())
+                    .min_by(|&a, &b| {
+                        (RING_WIDTHS[a] - width).abs().total_cmp(&(RING_WIDTHS[b] - width).abs())
+                    })
+                    .unwrap();
+                FIRST_RING_CELL + nearest
+            }
+        };
+        // the quad covers the padding too
+        let half = (radius * (CELL as f32)) / 2.0 / MAX_RADIUS;
+        let cells = (FIRST_RING_CELL + RING_WIDTHS.len()) as f32;
+        let (left, right) = ((cell as f32) / cells, ((cell + 1) as f32) / cells);
+        self.quad(position, half, [vec2(left, 0.0), vec2(right, 1.0)], color);
+    }
+
+    // a body too small on screen for its shape to show, as a square dot the size of the
+    // body and at least a pixel across, whatever its style
+    pub fn add_point(&mut self, position: Vec2, radius: f32, color: Color) {
+        // the middle of the disc, solid all over
+        let cells = (FIRST_RING_CELL + RING_WIDTHS.len()) as f32;
+        let middle = vec2(((DISC_CELL as f32) + 0.5) / cells, 0.5);
+        self.quad(position, radius.max(0.5), [middle, middle], color);
+    }
+
+    // a square `half` wide either side of `position`, `uv` the texture's top left and bottom
+    // right corners on it
+    fn quad(&mut self, position: Vec2, half: f32, [from, to]: [Vec2; 2], color: Color) {
+        if self.vertices.len() == 4 * MAX_QUADS {
+            self.flush();
+        }
+        let first = self.vertices.len() as u16;
+        for (corner, uv) in [
+            (vec2(-half, -half), from),
+            (vec2(half, -half), vec2(to.x, from.y)),
+            (vec2(half, half), to),
+            (vec2(-half, half), vec2(from.x, to.y)),
+        ] {
+            let at = position + corner;
+            self.vertices.push(Vertex::new2(vec3(at.x, at.y, 0.0), uv, color));
+        }
+        self.indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+
+    // draws what's been added so far
+    pub fn flush(&mut self) {
+        if self.indices.is_empty() {
+            return;
+        }
+        let mesh = Mesh {
+            vertices: std::mem::take(&mut self.vertices),
+            indices: std::mem::take(&mut self.indices),
+            texture: Some(self.atlas.clone()),
+        };
+        draw_mesh(&mesh);
+        self.vertices = mesh.vertices;
+        self.indices = mesh.indices;
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    fn draw_one(&self, style: BodyStyle, position: Vec2, radius: f32, scale: f32, color: Color) {
+        match style {
+            BodyStyle::Outline => {
+                let width = style::outline_width(radius, scale);
+                draw_circle_lines(position.x, position.y, radius, width, color);
+            }
+            BodyStyle::Filled => draw_circle(position.x, position.y, radius, color),
+            BodyStyle::Sprite => {
+                let half = (radius * (CELL as f32)) / 2.0 / MAX_RADIUS;
+                let cell = CELL as f32;
+                let params = DrawTextureParams {
+                    dest_size: Some(Vec2::splat(2.0 * half)),
+                    source: Some(Rect::new((SPRITE_CELL as f32) * cell, 0.0, cell, cell)),
+                    ..Default::default()
+                };
+                draw_texture_ex(&self.atlas, position.x - half, position.y - half, color, params);
+            }
+        }
+    }
+}
+
+// one row of cells: a disc, the shaded sprite and then the rings, white so they take the
+// body's color
+fn atlas() -> Image {
+    let cells = FIRST_RING_CELL + RING_WIDTHS.len();
+    let mut image = Image::gen_image_color(CELL * (cells as u16), CELL, BLANK);
+    let middle = (CELL as f32) / 2.0;
+    let content = middle - PADDING;
+    for cell in 0..cells {
+        for y in 0..CELL as u32 {
+            for x in 0..CELL as u32 {
+                let r = (vec2(x as f32, y as f32) + 0.5 - middle).length() / content;
+                // a texel of smoothing at the rim, on both edges of a ring
+                let outside = ((1.0 - r) * content + 0.5).clamp(0.0, 1.0);
+                let color = match cell {
+                    DISC_CELL => Color::new(1.0, 1.0, 1.0, outside),
+                    // limb darkening
+                    SPRITE_CELL => {
+                        let brightness = 0.4 + 0.6 * (1.0 - r * r).max(0.0).sqrt();
+                        Color::new(brightness, brightness, brightness, outside)
+                    }
+                    _ => {
+                        let inner = 1.0 - RING_WIDTHS[cell - FIRST_RING_CELL];
+                        let inside = ((r - inner) * content + 0.5).clamp(0.0, 1.0);
+                        Color::new(1.0, 1.0, 1.0, outside.min(inside))
+                    }
+                };
+                image.set_pixel((cell as u32) * (CELL as u32) + x, y, color);
+            }
+        }
+    }
+    image
+}
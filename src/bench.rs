@@ -0,0 +1,63 @@
+use std::time::Instant;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+use crate::solver::{ self, Solver };
+use crate::{ rng, Body };
+
+// exact reference forces are only computed for a sample so huge runs stay feasible
+const REFERENCE_SAMPLE: usize = 256;
+
+// `threebody bench [bodies] [seed]`: times every solver on the same random system and
+// reports the force error relative to brute force. The seed defaults to 0 so runs on
+// different machines or commits time the very same system
+pub fn run(count: usize, seed: u64) {
+    rng::seed(seed);
+    let bodies: Bodies = (0..count).map(|_| Body::random(None)).collect();
+    let settings = Settings::default();
+
+    let step = (count / REFERENCE_SAMPLE).max(1);
+    let sample: Vec<usize> = (0..count).step_by(step).collect();
+    let reference: Vec<Vec2> = sample
+        .iter()
+        .map(|&i| solver::brute_force_on(&bodies, i, settings.gravity))
+        .collect();
+
+    println!(
+        "{} bodies (seed {}), theta {}, leaf capacity {}",
+        count,
+        seed,
+        settings.theta,
+        settings.leaf_capacity
+    );
+    println!("{:<22} {:>12} {:>16}", "solver", "time (ms)", "rms rel. error");
+
+    // brute force runs twice so the scalar and vectorized kernels can be compared
+    let runs = [(Solver::BruteForce, false)]
+        .into_iter()
+        .chain(Solver::CPU.into_iter().map(|solver| (solver, true)));
+    for (solver, simd) in runs {
+        let mut bodies = bodies.clone();
+        let settings = Settings { solver, simd, ..Settings::default() };
+
+        let start = Instant::now();
+        solver::compute_forces(&mut bodies, &settings);
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+        let mut error = 0.0;
+        for (&i, exact) in sample.iter().zip(&reference) {
+            let magnitude = exact.length().max(f32::EPSILON);
+            error += ((bodies.forces[i] - *exact).length() / magnitude).powi(2) as f64;
+        }
+        let rms = (error / (sample.len().max(1) as f64)).sqrt();
+
+        let name = match (solver, simd) {
+            (Solver::BruteForce, true) => "brute force (simd)",
+            (Solver::BruteForce, false) => "brute force (scalar)",
+            _ => solver.name(),
+        };
+        println!("{:<22} {:>12.2} {:>16.3e}", name, elapsed, rms);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+use crate::Body;
+
+// a tenth of what a full random system weighs, enough to dominate its neighbourhood
+const BLACK_HOLE_MASS: f32 = 100_000.0;
+
+// the Schwarzschild radius 2 G M / c^2, so it follows the mass as the hole feeds and the
+// speed of light set in the console
+pub fn horizon(mass: f32, settings: &Settings) -> f32 {
+    (2.0 * settings.gravity * mass) / settings.light_speed.powi(2)
+}
+
+pub fn new(position: Vec2, settings: &Settings) -> Body {
+    let mut body = Body::new(position);
+    body.mass = BLACK_HOLE_MASS;
+    body.radius = horizon(body.mass, settings);
+    body.black_hole = true;
+    body
+}
+
+// every body whose center is inside a horizon falls in, its mass and momentum going to the
+// hole. Heavier holes eat first, so of two holes that meet the lighter one is swallowed.
+// Returns (hole, swallowed body) ids
+pub fn absorb(bodies: &mut Bodies, settings: &Settings) -> Vec<(u64, u64)> {
+    let mut holes: Vec<usize> = (0..bodies.len()).filter(|&i| bodies.black_holes[i]).collect();
+    if holes.is_empty() {
+        return Vec::new();
+    }
+    holes.sort_by(|&a, &b| bodies.masses[b].total_cmp(&bodies.masses[a]));
+    // gravity or the speed of light may have changed since the last step
+    for &i in &holes {
+        bodies.radii[i] = horizon(bodies.masses[i], settings);
+    }
+
+    let mut swallowed = Vec::new();
+    let mut gone = HashSet::new();
+    for i in holes {
+        if gone.contains(&i) {
+            continue;
+        }
+        let mut hole = bodies.body(i);
+        for j in 0..bodies.len() {
+            if j == i || gone.contains(&j) {
+                continue;
+            }
+            let other = bodies.body(j);
+            if hole.position.distance(other.position) >= hole.radius {
+                continue;
+            }
+            // the center of mass and total momentum stay where they were
+            let mass = hole.mass + other.mass;
+            hole.position = (hole.position * hole.mass + other.position * other.mass) / mass;
+            hole.velocity = (hole.velocity * hole.mass + other.velocity * other.mass) / mass;
+            hole.mass = mass;
+            hole.radius = horizon(mass, settings);
+            gone.insert(j);
+            swallowed.push((hole.id, other.id));
+        }
+        bodies.set(i, hole);
+    }
+
+    if !swallowed.is_empty() {
+        let ids: HashSet<u64> = swallowed.iter().map(|&(_, id)| id).collect();
+        bodies.retain(|body| !ids.contains(&body.id));
+    }
+    swallowed
+}
+
+// a black disk inside a glowing ring, with a fainter halo around it
+pub fn draw(screen_position: Vec2, radius: f32, selected: bool) {
+    let Vec2 { x, y } = screen_position;
+    let ring = if selected { YELLOW } else { Color::new(0.7, 0.4, 1.0, 1.0) };
+    draw_circle(x, y, radius * 1.6, Color::new(0.7, 0.4, 1.0, 0.15));
+    draw_circle(x, y, radius, BLACK);
+    draw_circle_lines(x, y, radius, 2.0, ring);
+}
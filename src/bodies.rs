@@ -0,0 +1,176 @@
+use macroquad::prelude::*;
+
+use crate::Body;
+use crate::sleep;
+
+// structure of arrays storage: every field of `Body` lives in its own contiguous array so
+// the solvers only stream through the data they actually read. All arrays have the same
+// length, `Body` is the row type used to push, read and write whole bodies.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Bodies {
+    pub ids: Vec<u64>,
+    pub positions: Vec<Vec2>,
+    pub velocities: Vec<Vec2>,
+    pub accelerations: Vec<Vec2>,
+    pub forces: Vec<Vec2>,
+    pub masses: Vec<f32>,
+    pub radii: Vec<f32>,
+    pub freezed: Vec<bool>,
+    pub selected: Vec<bool>,
+    pub freeze_groups: Vec<Option<usize>>,
+    pub depths: Vec<f32>,
+    pub black_holes: Vec<bool>,
+    pub external_forces: Vec<Vec2>,
+    pub still_steps: Vec<u32>,
+}
+
+impl Bodies {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Bodies {
+            ids: Vec::with_capacity(capacity),
+            positions: Vec::with_capacity(capacity),
+            velocities: Vec::with_capacity(capacity),
+            accelerations: Vec::with_capacity(capacity),
+            forces: Vec::with_capacity(capacity),
+            masses: Vec::with_capacity(capacity),
+            radii: Vec::with_capacity(capacity),
+            freezed: Vec::with_capacity(capacity),
+            selected: Vec::with_capacity(capacity),
+            freeze_groups: Vec::with_capacity(capacity),
+            depths: Vec::with_capacity(capacity),
+            black_holes: Vec::with_capacity(capacity),
+            external_forces: Vec::with_capacity(capacity),
+            still_steps: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn push(&mut self, body: Body) {
+        self.ids.push(body.id);
+        self.positions.push(body.position);
+        self.velocities.push(body.velocity);
+        self.accelerations.push(body.acceleration);
+        self.forces.push(body.force);
+        self.masses.push(body.mass);
+        self.radii.push(body.radius);
+        self.freezed.push(body.freezed);
+        self.selected.push(body.selected);
+        self.freeze_groups.push(body.freeze_group);
+        self.depths.push(body.depth);
+        self.black_holes.push(body.black_hole);
+        self.external_forces.push(body.external_force);
+        self.still_steps.push(body.still_steps);
+    }
+
+    // gathers one body out of the arrays, panics when out of bounds like slice indexing
+    pub fn body(&self, index: usize) -> Body {
+        Body {
+            id: self.ids[index],
+            position: self.positions[index],
+            velocity: self.velocities[index],
+            acceleration: self.accelerations[index],
+            force: self.forces[index],
+            mass: self.masses[index],
+            radius: self.radii[index],
+            freezed: self.freezed[index],
+            selected: self.selected[index],
+            freeze_group: self.freeze_groups[index],
+            depth: self.depths[index],
+            black_hole: self.black_holes[index],
+            external_force: self.external_forces[index],
+            still_steps: self.still_steps[index],
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<Body> {
+        (index < self.len()).then(|| self.body(index))
+    }
+
+    pub fn set(&mut self, index: usize, body: Body) {
+        self.ids[index] = body.id;
+        self.positions[index] = body.position;
+        self.velocities[index] = body.velocity;
+        self.accelerations[index] = body.acceleration;
+        self.forces[index] = body.force;
+        self.masses[index] = body.mass;
+        self.radii[index] = body.radius;
+        self.freezed[index] = body.freezed;
+        self.selected[index] = body.selected;
+        self.freeze_groups[index] = body.freeze_group;
+        self.depths[index] = body.depth;
+        self.black_holes[index] = body.black_hole;
+        self.external_forces[index] = body.external_force;
+        self.still_steps[index] = body.still_steps;
+    }
+
+    // bodies are handed out by value, write changes back with `set` or `for_each_mut`
+    pub fn iter(&self) -> impl Iterator<Item = Body> + '_ {
+        (0..self.len()).map(|index| self.body(index))
+    }
+
+    // convenience for code that works on whole bodies, hot loops should use the arrays
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut Body)) {
+        for index in 0..self.len() {
+            let mut body = self.body(index);
+            f(&mut body);
+            self.set(index, body);
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&Body) -> bool) {
+        let kept: Bodies = self.iter().filter(|body| keep(body)).collect();
+        *self = kept;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Bodies::default();
+    }
+
+    // neither frozen nor asleep, so it gets integrated, same as `Body::is_active`
+    pub fn is_active(&self, index: usize) -> bool {
+        !self.freezed[index] && self.still_steps[index] < sleep::SLEEP_STEPS
+    }
+
+    pub fn force_between(&self, i: usize, j: usize, gravity: f32) -> Vec2 {
+        self.force_towards(i, self.positions[j], self.masses[j], gravity)
+    }
+
+    // attraction towards a point mass, also used for the aggregated cells of the tree solvers
+    pub fn force_towards(&self, i: usize, position: Vec2, mass: f32, gravity: f32) -> Vec2 {
+        let distance = self.positions[i].distance(position);
+        if distance < 2.0 * self.radii[i] {
+            // Adjust to avoid division by zero in force calculation
+            return Vec2::ZERO; // Collision detected, no force applied
+        }
+
+        let numer = self.masses[i] * mass;
+        let denom = distance.powi(2);
+        let magnitude = gravity * (numer / denom);
+
+        // separate into directions
+        magnitude * ((position - self.positions[i]) / distance)
+    }
+}
+
+impl FromIterator<Body> for Bodies {
+    fn from_iter<I: IntoIterator<Item = Body>>(iter: I) -> Self {
+        let mut bodies = Bodies::default();
+        bodies.extend(iter);
+        bodies
+    }
+}
+
+impl Extend<Body> for Bodies {
+    fn extend<I: IntoIterator<Item = Body>>(&mut self, iter: I) {
+        for body in iter {
+            self.push(body);
+        }
+    }
+}
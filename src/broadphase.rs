@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Broadphase {
+    SpatialHash,
+    SweepAndPrune,
+}
+
+impl Broadphase {
+    pub fn next(self) -> Self {
+        match self {
+            Broadphase::SpatialHash => Broadphase::SweepAndPrune,
+            Broadphase::SweepAndPrune => Broadphase::SpatialHash,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Broadphase::SpatialHash => "spatial hash",
+            Broadphase::SweepAndPrune => "sweep and prune",
+        }
+    }
+}
+
+// pairs of bodies that may be overlapping, each listed once as (lower, higher) index
+pub fn candidate_pairs(broadphase: Broadphase, bodies: &Bodies) -> Vec<(usize, usize)> {
+    match broadphase {
+        Broadphase::SpatialHash => spatial_hash_pairs(bodies, 0.0),
+        Broadphase::SweepAndPrune => sweep_and_prune_pairs(bodies),
+    }
+}
+
+// pairs whose surfaces may be closer than `margin`, found the same way as overlapping ones
+pub fn pairs_within(bodies: &Bodies, margin: f32) -> Vec<(usize, usize)> {
+    spatial_hash_pairs(bodies, margin.max(0.0))
+}
+
+// neighbouring cells that still have to be checked, each pair of cells is visited once
+const NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (1, 1), (0, 1), (-1, 1)];
+
+// uniform grid sized so overlapping bodies, or ones within `margin` of touching, always share
+// a cell or sit in adjacent ones
+fn spatial_hash_pairs(bodies: &Bodies, margin: f32) -> Vec<(usize, usize)> {
+    let max_radius = bodies.radii.iter().fold(0.0f32, |max, &radius| max.max(radius));
+    let cell_size = (2.0 * max_radius + margin).max(1.0);
+
+    let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (index, &position) in bodies.positions.iter().enumerate() {
+        cells.entry(cell_of(position, cell_size)).or_default().push(index);
+    }
+
+    let mut pairs = Vec::new();
+    for (&(x, y), members) in &cells {
+        for (a, &i) in members.iter().enumerate() {
+            for &j in &members[a + 1..] {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+
+        for (dx, dy) in NEIGHBOURS {
+            if let Some(neighbours) = cells.get(&(x + dx, y + dy)) {
+                for &i in members {
+                    for &j in neighbours {
+                        pairs.push((i.min(j), i.max(j)));
+                    }
+                }
+            }
+        }
+    }
+
+    // keep the resolution order independent of the hash map iteration order
+    pairs.sort_unstable();
+    pairs
+}
+
+fn cell_of(position: Vec2, cell_size: f32) -> (i32, i32) {
+    ((position.x / cell_size).floor() as i32, (position.y / cell_size).floor() as i32)
+}
+
+// bodies collide closer than twice the larger radius, so each one reaches out 2r
+fn extent(bodies: &Bodies, index: usize) -> f32 {
+    2.0 * bodies.radii[index]
+}
+
+// sorts along x and only tests bodies whose x intervals overlap, which copes with
+// clustered scenes where a single grid cell size fits nobody
+fn sweep_and_prune_pairs(bodies: &Bodies) -> Vec<(usize, usize)> {
+    let boxes: Vec<(Vec2, Vec2)> = (0..bodies.len())
+        .map(|i| {
+            let reach = Vec2::splat(extent(bodies, i));
+            (bodies.positions[i] - reach, bodies.positions[i] + reach)
+        })
+        .collect();
+    overlapping_boxes(&boxes)
+}
+
+// pairs that may have touched at some point while moving in a straight line from `start` to
+// where they are now: their boxes around the whole path overlap. Sorted along x like sweep
+// and prune, a fast body's box can be far bigger than any grid cell
+pub fn swept_pairs(bodies: &Bodies, start: &[Vec2]) -> Vec<(usize, usize)> {
+    let boxes: Vec<(Vec2, Vec2)> = (0..bodies.len())
+        .map(|i| {
+            let reach = Vec2::splat(extent(bodies, i));
+            let (from, to) = (start[i], bodies.positions[i]);
+            (from.min(to) - reach, from.max(to) + reach)
+        })
+        .collect();
+    overlapping_boxes(&boxes)
+}
+
+// every pair of (min, max) boxes that overlap
+fn overlapping_boxes(boxes: &[(Vec2, Vec2)]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..boxes.len()).collect();
+    order.sort_unstable_by(|&a, &b| boxes[a].0.x.total_cmp(&boxes[b].0.x));
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let (min, max) = boxes[i];
+        active.retain(|&j| boxes[j].1.x >= min.x);
+
+        for &j in &active {
+            if boxes[j].0.y <= max.y && min.y <= boxes[j].1.y {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+        active.push(i);
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
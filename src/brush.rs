@@ -0,0 +1,98 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+// what one notch of the wheel multiplies the strength by, and how far it goes either way
+const STRENGTH_STEP: f32 = 1.25;
+pub const MIN_STRENGTH: f32 = 0.05;
+pub const MAX_STRENGTH: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BrushMode {
+    Attract,
+    Repel,
+    Swirl,
+    Damp,
+}
+
+impl BrushMode {
+    pub fn next(self) -> Self {
+        match self {
+            BrushMode::Attract => BrushMode::Repel,
+            BrushMode::Repel => BrushMode::Swirl,
+            BrushMode::Swirl => BrushMode::Damp,
+            BrushMode::Damp => BrushMode::Attract,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BrushMode::Attract => "attract",
+            BrushMode::Repel => "repel",
+            BrushMode::Swirl => "swirl",
+            BrushMode::Damp => "damp",
+        }
+    }
+}
+
+// the strength is `Settings::brush_strength`, so `set` and scenarios reach it
+pub struct Brush {
+    pub mode: BrushMode,
+    pub radius: f32,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush {
+            mode: BrushMode::Attract,
+            radius: 80.0,
+        }
+    }
+}
+
+impl Brush {
+    // nudges the velocity of every body inside the brush by up to `strength` per unit of
+    // time, fading out towards the edge
+    pub fn apply(&self, center: Vec2, bodies: &mut Bodies, strength: f32, dt: f32) {
+        for i in 0..bodies.len() {
+            if bodies.freezed[i] {
+                continue;
+            }
+
+            let offset = center - bodies.positions[i];
+            let distance = offset.length();
+            if distance > self.radius || distance == 0.0 {
+                continue;
+            }
+
+            let falloff = 1.0 - distance / self.radius;
+            let direction = offset / distance;
+            let impulse = strength * falloff * dt;
+
+            let velocity = &mut bodies.velocities[i];
+            match self.mode {
+                BrushMode::Attract => *velocity += direction * impulse,
+                BrushMode::Repel => *velocity -= direction * impulse,
+                BrushMode::Swirl => *velocity += direction.perp() * impulse,
+                BrushMode::Damp => *velocity *= (1.0 - impulse).max(0.0),
+            }
+        }
+    }
+
+    pub fn resize(&mut self, amount: f32) {
+        self.radius = (self.radius + amount).clamp(10.0, 400.0);
+    }
+
+    // `notches` of the wheel, stronger for positive ones
+    pub fn stronger(strength: f32, notches: f32) -> f32 {
+        (strength * STRENGTH_STEP.powf(notches)).clamp(MIN_STRENGTH, MAX_STRENGTH)
+    }
+
+    // `scale` is the view's pixels per world unit, the radius is in world units
+    pub fn draw(&self, center: Vec2, scale: f32, strength: f32) {
+        let radius = self.radius * scale;
+        draw_circle_lines(center.x, center.y, radius, 1.0, SKYBLUE);
+        let label = format!("{} {:.2}", self.mode.name(), strength);
+        draw_text(&label, center.x - radius, center.y - radius - 4.0, 18.0, SKYBLUE);
+    }
+}
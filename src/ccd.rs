@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::broadphase;
+use crate::contacts::Impact;
+use crate::settings::Settings;
+
+// bodies are placed this far inside contact distance at the time of impact, so the usual
+// collision response sees them touching
+const CONTACT_OVERLAP: f32 = 1e-3;
+
+// the fraction of the step at which two circles moving in straight lines first come within
+// `contact` of each other. `offset` is where the second starts relative to the first and
+// `displacement` how far it moves relative to the first over the whole step
+pub fn time_of_impact(offset: Vec2, displacement: Vec2, contact: f32) -> Option<f32> {
+    let a = displacement.length_squared();
+    let b = offset.dot(displacement);
+    let c = offset.length_squared() - contact.powi(2);
+    // already touching, or not closing in: the discrete test handles both
+    if c <= 0.0 || b >= 0.0 || a == 0.0 {
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / a;
+    (time <= 1.0).then_some(time)
+}
+
+// catches what the discrete test before the step misses: a body moving further than its own
+// radius in one step can pass straight through another. Every such body is swept against the
+// others whose paths come near its own (see `broadphase::swept_pairs`) from `start` to where
+// it ended up, and the earliest hit is moved back to the contact point, resolved there and
+// carried on with its new velocity for the rest of the step. One hit per body per step.
+// Returns the collisions
+pub fn resolve(bodies: &mut Bodies, start: &[Vec2], settings: &Settings) -> Vec<Impact> {
+    let (dt, hacks) = (settings.dt, settings.hacks);
+    let mut handled = vec![false; bodies.len()];
+    let mut collisions = Vec::new();
+
+    let fast: Vec<bool> = (0..bodies.len())
+        .map(|i| (bodies.positions[i] - start[i]).length() > bodies.radii[i])
+        .collect();
+    if !fast.contains(&true) {
+        return collisions;
+    }
+    // the pairs come sorted, so each list is in index order like a scan over all bodies
+    let mut candidates: Vec<Vec<usize>> = vec![Vec::new(); bodies.len()];
+    for (i, j) in broadphase::swept_pairs(bodies, start) {
+        if fast[i] {
+            candidates[i].push(j);
+        }
+        if fast[j] {
+            candidates[j].push(i);
+        }
+    }
+
+    for i in 0..bodies.len() {
+        if handled[i] || bodies.black_holes[i] || !fast[i] {
+            continue;
+        }
+        let moved = bodies.positions[i] - start[i];
+        let body = bodies.body(i);
+        let mut earliest: Option<(usize, f32)> = None;
+        for &j in &candidates[i] {
+            if handled[j] || bodies.black_holes[j] {
+                continue;
+            }
+            let contact = body.contact_distance(&bodies.body(j), &hacks) * (1.0 - CONTACT_OVERLAP);
+            let offset = start[j] - start[i];
+            let displacement = bodies.positions[j] - start[j] - moved;
+            if let Some(time) = time_of_impact(offset, displacement, contact) {
+                if earliest.is_none_or(|(_, earliest)| time < earliest) {
+                    earliest = Some((j, time));
+                }
+            }
+        }
+        let Some((j, time)) = earliest else {
+            continue;
+        };
+
+        let (mut body, mut other_body) = (bodies.body(i), bodies.body(j));
+        body.position = start[i].lerp(body.position, time);
+        other_body.position = start[j].lerp(other_body.position, time);
+        let before = (body, other_body);
+        if body.check_and_resolve_collision(&mut other_body, settings) {
+            collisions.push(Impact::between((i, j), (&before.0, &before.1), (&body, &other_body)));
+        }
+        // frozen and sleeping bodies stay where they are, they never moved in the first place
+        for moving in [&mut body, &mut other_body] {
+            if moving.is_active() {
+                moving.update_position((1.0 - time) * dt);
+            }
+        }
+        bodies.set(i, body);
+        bodies.set(j, other_body);
+        handled[i] = true;
+        handled[j] = true;
+    }
+    collisions
+}
@@ -0,0 +1,245 @@
+use std::collections::{ HashMap, HashSet };
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::freeze::FreezeGroups;
+use crate::metadata::Metadata;
+use crate::provenance::Provenance;
+use crate::settings::Settings;
+
+// bodies named in each line of a diff summary, the CSV has the rest
+const SHOWN_BODIES: usize = 5;
+// changes smaller than this are float noise, not a difference
+const EPSILON: f32 = 1e-4;
+
+// the whole simulation under a name, to come back to or to compare a later state against
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub name: String,
+    pub time: f32,
+    pub bodies: Bodies,
+    pub metadata: Metadata,
+    pub settings: Settings,
+    // the bodies' `freeze_groups` indices point into these
+    pub freeze_groups: FreezeGroups,
+}
+
+// named checkpoints in the order they were saved, kept for the session
+#[derive(Default)]
+pub struct Checkpoints {
+    list: Vec<Checkpoint>,
+}
+
+impl Checkpoints {
+    // a name that is taken is overwritten, returns whether it was
+    pub fn save(&mut self, checkpoint: Checkpoint) -> bool {
+        match self.list.iter_mut().find(|saved| saved.name == checkpoint.name) {
+            Some(saved) => {
+                *saved = checkpoint;
+                true
+            }
+            None => {
+                self.list.push(checkpoint);
+                false
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Checkpoint, String> {
+        self.list
+            .iter()
+            .find(|saved| saved.name == name)
+            .ok_or_else(|| format!("no checkpoint named `{}`", name))
+    }
+
+    pub fn report(&self) -> String {
+        if self.list.is_empty() {
+            return "no checkpoints, `checkpoint <name>` saves one".to_string();
+        }
+        let width = self.list.iter().map(|saved| saved.name.len()).max().unwrap_or(0);
+        self.list
+            .iter()
+            .map(|saved| {
+                format!(
+                    "{:width$}  t = {:.2}, {} bodies",
+                    saved.name,
+                    saved.time,
+                    saved.bodies.len(),
+                    width = width
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+// how one body present in both checkpoints changed between them
+pub struct BodyDelta {
+    pub id: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+}
+
+// what happened between two checkpoints, `from` the earlier one as far as the reader cares
+pub struct Diff<'a> {
+    pub from: &'a Checkpoint,
+    pub to: &'a Checkpoint,
+    // provenance entries that differ, as (key, from, to)
+    pub settings: Vec<(String, String, String)>,
+    pub added: Vec<u64>,
+    pub removed: Vec<u64>,
+    // bodies in both, by id
+    pub deltas: Vec<BodyDelta>,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(from: &'a Checkpoint, to: &'a Checkpoint) -> Self {
+        let (before, after) = (Provenance::new(&from.settings), Provenance::new(&to.settings));
+        let settings = before.entries
+            .into_iter()
+            .zip(after.entries)
+            .filter(|((key, old), (_, new))| key != "created" && old != new)
+            .map(|((key, old), (_, new))| (key, old, new))
+            .collect();
+
+        let indices: HashMap<u64, usize> = from.bodies.ids
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| (id, index))
+            .collect();
+        let mut added = Vec::new();
+        let mut deltas = Vec::new();
+        for (j, &id) in to.bodies.ids.iter().enumerate() {
+            let Some(&i) = indices.get(&id) else {
+                added.push(id);
+                continue;
+            };
+            deltas.push(BodyDelta {
+                id,
+                position: to.bodies.positions[j] - from.bodies.positions[i],
+                velocity: to.bodies.velocities[j] - from.bodies.velocities[i],
+                mass: to.bodies.masses[j] - from.bodies.masses[i],
+            });
+        }
+        let remaining: HashSet<u64> = to.bodies.ids.iter().copied().collect();
+        let mut removed: Vec<u64> = from.bodies.ids
+            .iter()
+            .copied()
+            .filter(|id| !remaining.contains(id))
+            .collect();
+        added.sort_unstable();
+        removed.sort_unstable();
+        deltas.sort_unstable_by_key(|delta| delta.id);
+        Diff { from, to, settings, added, removed, deltas }
+    }
+
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "{} (t = {:.2}, {} bodies) -> {} (t = {:.2}, {} bodies)",
+                self.from.name,
+                self.from.time,
+                self.from.bodies.len(),
+                self.to.name,
+                self.to.time,
+                self.to.bodies.len()
+            )
+        ];
+        if !self.settings.is_empty() {
+            let changes: Vec<String> = self.settings
+                .iter()
+                .map(|(key, old, new)| format!("{} {} -> {}", key, old, new))
+                .collect();
+            lines.push(format!("settings: {}", changes.join(", ")));
+        }
+        if !self.added.is_empty() {
+            let names = self.names(&self.added, &self.to.metadata);
+            lines.push(format!("added {}: {}", self.added.len(), names));
+        }
+        if !self.removed.is_empty() {
+            let names = self.names(&self.removed, &self.from.metadata);
+            lines.push(format!("removed {}: {}", self.removed.len(), names));
+        }
+        lines.push(self.change_line("moved", |delta| delta.position.length()));
+        lines.push(self.change_line("changed velocity", |delta| delta.velocity.length()));
+        lines.push(self.change_line("changed mass", |delta| delta.mass.abs()));
+        lines.join("\n")
+    }
+
+    // "N of M moved, X on average, most: body a by x, ..." for one per-body quantity
+    fn change_line(&self, verb: &str, size: impl Fn(&BodyDelta) -> f32) -> String {
+        let mut changed: Vec<(u64, f32)> = self.deltas
+            .iter()
+            .map(|delta| (delta.id, size(delta)))
+            .filter(|&(_, size)| size > EPSILON)
+            .collect();
+        if changed.is_empty() {
+            return format!("none of {} {}", self.deltas.len(), verb);
+        }
+        changed.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mean = changed.iter().map(|&(_, size)| size).sum::<f32>() / (changed.len() as f32);
+        let most: Vec<String> = changed
+            .iter()
+            .take(SHOWN_BODIES)
+            .map(|&(id, size)| format!("{} by {:.2}", self.to.metadata.label(id), size))
+            .collect();
+        format!(
+            "{} of {} {}, {:.2} on average, most: {}",
+            changed.len(),
+            self.deltas.len(),
+            verb,
+            mean,
+            most.join(", ")
+        )
+    }
+
+    fn names(&self, ids: &[u64], metadata: &Metadata) -> String {
+        let mut names: Vec<String> = ids
+            .iter()
+            .take(SHOWN_BODIES)
+            .map(|&id| metadata.label(id))
+            .collect();
+        if ids.len() > SHOWN_BODIES {
+            names.push(format!("and {} more", ids.len() - SHOWN_BODIES));
+        }
+        names.join(", ")
+    }
+
+    // every body of either checkpoint, under the provenance of the later one plus the names
+    // and times of both
+    pub fn write_csv(&self, path: &str) -> io::Result<()> {
+        let mut provenance = Provenance::new(&self.to.settings);
+        for (key, checkpoint) in [("from", self.from), ("to", self.to)] {
+            provenance.entries.push((
+                key.to_string(),
+                format!("{} at t = {}", checkpoint.name, checkpoint.time),
+            ));
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        provenance.write_header(&mut file)?;
+        writeln!(file, "id,status,dx,dy,dvx,dvy,dmass")?;
+        for &id in &self.added {
+            writeln!(file, "{},added,,,,,", id)?;
+        }
+        for &id in &self.removed {
+            writeln!(file, "{},removed,,,,,", id)?;
+        }
+        for delta in &self.deltas {
+            writeln!(
+                file,
+                "{},common,{},{},{},{},{}",
+                delta.id,
+                delta.position.x,
+                delta.position.y,
+                delta.velocity.x,
+                delta.velocity.y,
+                delta.mass
+            )?;
+        }
+        file.flush()
+    }
+}
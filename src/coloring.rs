@@ -0,0 +1,105 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::Body;
+
+const SLOW: Color = Color::new(0.2, 0.4, 1.0, 1.0);
+const FAST: Color = Color::new(1.0, 0.15, 0.1, 1.0);
+const LIGHT: Color = Color::new(0.35, 0.4, 0.5, 1.0);
+const HEAVY: Color = Color::new(1.0, 0.85, 0.3, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorMode {
+    // red, dark when asleep
+    Plain,
+    // blue for the slowest bodies to red for the fastest
+    Speed,
+    // dull gray for the lightest to bright gold for the heaviest, on a log scale
+    Mass,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Plain => ColorMode::Speed,
+            ColorMode::Speed => ColorMode::Mass,
+            ColorMode::Mass => ColorMode::Plain,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Plain => "plain",
+            ColorMode::Speed => "speed",
+            ColorMode::Mass => "mass",
+        }
+    }
+}
+
+// maps the quantity a mode colors by onto the gradient, refitted every frame
+pub struct Palette {
+    pub mode: ColorMode,
+    // the values at either end of the gradient
+    pub low: f32,
+    pub high: f32,
+}
+
+impl Palette {
+    pub fn fit(mode: ColorMode, bodies: &Bodies) -> Self {
+        let (low, high) = match mode {
+            ColorMode::Plain => (0.0, 1.0),
+            // a couple of standard deviations over the mean, so a single runaway body
+            // doesn't leave everything else blue
+            ColorMode::Speed => {
+                let count = bodies.len().max(1) as f32;
+                let speeds = || bodies.velocities.iter().map(|velocity| velocity.length());
+                let mean = speeds().sum::<f32>() / count;
+                let variance = speeds().map(|speed| (speed - mean).powi(2)).sum::<f32>() / count;
+                (0.0, mean + 2.0 * variance.sqrt())
+            }
+            // masses span orders of magnitude between a swarm and its attractors
+            ColorMode::Mass => {
+                let masses = bodies.masses.iter().map(|mass| mass.max(f32::MIN_POSITIVE).ln());
+                masses.fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), mass| {
+                    (low.min(mass), high.max(mass))
+                })
+            }
+        };
+        Palette { mode, low, high }
+    }
+
+    // the gradient color of `body`, none in plain mode
+    pub fn color(&self, body: &Body) -> Option<Color> {
+        let value = match self.mode {
+            ColorMode::Plain => {
+                return None;
+            }
+            ColorMode::Speed => body.velocity.length(),
+            ColorMode::Mass => body.mass.max(f32::MIN_POSITIVE).ln(),
+        };
+        // with nothing to tell apart, equal masses read as heavy and a system at rest as slow
+        let range = self.high - self.low;
+        let t = if range > 0.0 {
+            ((value - self.low) / range).clamp(0.0, 1.0)
+        } else if self.mode == ColorMode::Mass {
+            1.0
+        } else {
+            0.0
+        };
+        Some(self.gradient(t))
+    }
+
+    // `t` from 0 at the low end to 1 at the high end
+    pub fn gradient(&self, t: f32) -> Color {
+        let (from, to) = match self.mode {
+            ColorMode::Mass => (LIGHT, HEAVY),
+            ColorMode::Plain | ColorMode::Speed => (SLOW, FAST),
+        };
+        Color::new(
+            from.r + (to.r - from.r) * t,
+            from.g + (to.g - from.g) * t,
+            from.b + (to.b - from.b) * t,
+            1.0
+        )
+    }
+}
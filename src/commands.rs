@@ -0,0 +1,1065 @@
+use std::f32::consts::TAU;
+use std::fs;
+
+use macroquad::prelude::*;
+
+use crate::assist::Plan;
+use crate::bodies::Bodies;
+use crate::brush;
+use crate::checkpoints::{ Checkpoint, Checkpoints, Diff };
+use crate::contacts::ContactStats;
+use crate::diagnostics::DiagnosticsLog;
+use crate::field::{ self, Grid };
+use crate::freeze::FreezeGroups;
+use crate::gallery::{ self, Gallery };
+use crate::generators;
+use crate::insertion::Insertion;
+use crate::integrator::Integrator;
+use crate::metadata::{ Metadata, Value };
+use crate::noise::Noise;
+use crate::numbers::{ Notation, Separator };
+use crate::presets::Preset;
+use crate::provenance::Provenance;
+use crate::query::{ self, Expr };
+use crate::quiz::Quiz;
+use crate::recording::Recorder;
+use crate::scenario;
+use crate::schedule::{ self, Kind, Schedules };
+use crate::settings::{
+    Hacks,
+    Settings,
+    MAX_DT,
+    MAX_SUBSTEPS,
+    MAX_THETA,
+    MAX_TIME_SCALE,
+    MIN_DT,
+    MIN_THETA,
+    MIN_TIME_SCALE,
+};
+use crate::simd;
+use crate::style::BodyStyle;
+use crate::theme::Theme;
+use crate::tipsy::{ self, Plane };
+use crate::trails::MAX_TRAIL_LENGTH;
+use crate::video::Video;
+use crate::watchdog;
+use crate::{ export, fmm, rng, Body };
+
+// how deep `exec` and `gallery open` may nest, a script or scenario that runs itself stops
+// here
+const MAX_EXEC_DEPTH: usize = 8;
+
+// everything a command is allowed to touch, borrowed from the main loop
+pub struct Context<'a> {
+    pub bodies: &'a mut Bodies,
+    pub settings: &'a mut Settings,
+    pub metadata: &'a mut Metadata,
+    pub diagnostics: &'a DiagnosticsLog,
+    pub contacts: &'a ContactStats,
+    pub schedules: &'a mut Schedules,
+    pub recorder: &'a mut Option<Recorder>,
+    pub video: &'a mut Option<Video>,
+    pub checkpoints: &'a mut Checkpoints,
+    // the orbit insertion being previewed
+    pub insertion: &'a mut Option<Insertion>,
+    // the gravity assist whose trajectories are on screen
+    pub assist: &'a mut Option<Plan>,
+    // a restored checkpoint takes the clock back with it
+    pub time: &'a mut f32,
+    pub quiz: &'a mut Quiz,
+    // starts over with a preset, like the rest of the run
+    pub noise: &'a mut Noise,
+    pub gallery: &'a mut Gallery,
+    pub freeze_groups: &'a mut FreezeGroups,
+    // how many scripts deep the command is, see `MAX_EXEC_DEPTH`
+    pub exec_depth: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Random,
+    Disk {
+        center: Vec2,
+        radius: f32,
+    },
+    // `radius` is the scale radius, the cluster reaches out to five times that
+    Plummer {
+        center: Vec2,
+        radius: f32,
+    },
+    // every body on a circular orbit around the mass further in
+    Rotating {
+        center: Vec2,
+        radius: f32,
+    },
+}
+
+// operations applied to every selected body at once
+#[derive(Clone, PartialEq, Debug)]
+pub enum Bulk {
+    Freeze,
+    Unfreeze,
+    Delete,
+    Impulse(Vec2),
+    Mass(f32),
+    Tag(String, Value),
+}
+
+// managing the scenarios and recordings kept in the gallery, see `gallery::Gallery`
+#[derive(Clone, PartialEq, Debug)]
+pub enum GalleryAction {
+    Add {
+        path: String,
+        name: String,
+        tags: Vec<String>,
+        description: String,
+    },
+    Remove(String),
+    Tag(String, Vec<String>),
+    Untag(String, Vec<String>),
+    Describe(String, String),
+    Search(Vec<String>),
+    // runs a scenario, recordings are only pointed at
+    Open(String),
+    Export(String, Vec<String>),
+    Import(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Diagnostics,
+    // a snapshot for pynbody, yt and other N-body tools, see `tipsy::write`
+    Tipsy,
+    // acceleration and potential sampled on a grid, for NumPy
+    Field(Grid),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Set {
+        name: String,
+        value: f32,
+    },
+    // its own command, an f32 only holds 24 bits of a seed. Takes effect with the next preset
+    SetSeed(u64),
+    Spawn {
+        count: usize,
+        shape: Shape,
+    },
+    Select(Expr),
+    Bulk(Bulk),
+    // the selected bodies frozen into a named group, released by `Release` or on its own once
+    // the clock reaches `release_at`
+    FreezeGroup {
+        name: String,
+        release_at: Option<f32>,
+    },
+    Release(String),
+    Export {
+        format: ExportFormat,
+        path: String,
+    },
+    Exec(String),
+    // a Tipsy snapshot in place of the bodies, see `tipsy::place`
+    Import {
+        path: String,
+        plane: Plane,
+    },
+    Preset(Preset),
+    Integrator(Integrator),
+    Watchdog(watchdog::Mode),
+    Notation(Notation),
+    Separator(Separator),
+    Style(BodyStyle),
+    // a built in theme's name or a theme file, see `theme::Theme::load`
+    Theme(String),
+    Gallery(GalleryAction),
+    // a CSV time series for the selected bodies, see `schedule::load`
+    Schedule(String),
+    // every `every`th step into a `.tbr` file, and with `track` every step of the selected
+    // bodies in between, see `recording::Recorder`
+    Record {
+        path: String,
+        every: usize,
+        track: bool,
+    },
+    StopRecording,
+    // the window every `every`th frame as a GIF or numbered PNGs, see `video::Video`
+    Video {
+        path: String,
+        every: usize,
+    },
+    StopVideo,
+    // suggests a restitution for the bounciness wanted, see `ContactStats::calibrate`
+    Calibrate(f32),
+    // the whole simulation under a name, see `checkpoints::Checkpoints`
+    Checkpoint(String),
+    Restore(String),
+    Checkpoints,
+    // `now` stands for the running simulation, the report also goes to `path` as a CSV
+    Diff {
+        from: String,
+        to: String,
+        path: Option<String>,
+    },
+    // previews the burn onto an orbit around `primary`, see `insertion::Insertion`
+    Orbit {
+        eccentricity: f32,
+        primary: Option<u64>,
+    },
+    ApplyOrbit,
+    CancelOrbit,
+    // searches launches past `target` leaving towards `heading`, see `assist::Plan`
+    Assist {
+        target: u64,
+        heading: f32,
+    },
+    ApplyAssist,
+    CancelAssist,
+    // guided questions for a lab, see `quiz::Quiz`
+    Question {
+        prompt: String,
+        answer: String,
+    },
+    // shows the current question after moving this many on, or back
+    Quiz(isize),
+    Reveal,
+    ClearQuiz,
+    Hacks,
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        ["set", "seed", seed] => {
+            let seed = seed.parse().map_err(|_| format!("invalid seed `{}`", seed))?;
+            Ok(Command::SetSeed(seed))
+        }
+        ["set", name, value] => {
+            Ok(Command::Set {
+                name: name.to_string(),
+                value: number(value)?,
+            })
+        }
+        ["spawn", count, rest @ ..] => {
+            let count = count.parse().map_err(|_| format!("invalid count `{}`", count))?;
+            let shape = match rest {
+                [] | ["random"] => Shape::Random,
+                ["disk", x, y, radius] => {
+                    Shape::Disk {
+                        center: vec2(number(x)?, number(y)?),
+                        radius: number(radius)?,
+                    }
+                }
+                ["plummer", x, y, radius] => {
+                    Shape::Plummer {
+                        center: vec2(number(x)?, number(y)?),
+                        radius: number(radius)?,
+                    }
+                }
+                ["rotating", x, y, radius] => {
+                    Shape::Rotating {
+                        center: vec2(number(x)?, number(y)?),
+                        radius: number(radius)?,
+                    }
+                }
+                _ => {
+                    return Err(
+                        "usage: spawn <count> [random | disk <x> <y> <radius> | \
+                         plummer <x> <y> <radius> | rotating <x> <y> <radius>]".to_string()
+                    );
+                }
+            };
+            Ok(Command::Spawn { count, shape })
+        }
+        ["select", rest @ ..] if !rest.is_empty() => {
+            Ok(Command::Select(selection(&rest.join(" "))?))
+        }
+        ["freeze"] => Ok(Command::Bulk(Bulk::Freeze)),
+        ["freeze", "group", rest @ ..] => {
+            let (name, release_at) = match rest {
+                [name @ .., "release_at", time] => (name, Some(number(time)?)),
+                _ => (rest, None),
+            };
+            if name.is_empty() {
+                return Err("usage: freeze group <name> [release_at <time>]".to_string());
+            }
+            Ok(Command::FreezeGroup { name: name.join(" "), release_at })
+        }
+        ["release", name @ ..] if !name.is_empty() => Ok(Command::Release(name.join(" "))),
+        ["unfreeze"] => Ok(Command::Bulk(Bulk::Unfreeze)),
+        ["delete"] => Ok(Command::Bulk(Bulk::Delete)),
+        ["impulse", x, y] => Ok(Command::Bulk(Bulk::Impulse(vec2(number(x)?, number(y)?)))),
+        ["mass", value] => {
+            let mass = number(value)?;
+            // a massless body has no acceleration to speak of, and a negative one pushes
+            if !mass.is_finite() || mass <= 0.0 {
+                return Err("the mass has to be positive".to_string());
+            }
+            Ok(Command::Bulk(Bulk::Mass(mass)))
+        }
+        ["tag", key, value @ ..] if !value.is_empty() => {
+            Ok(Command::Bulk(Bulk::Tag(key.to_string(), Value::parse(&value.join(" ")))))
+        }
+        // kept as text whatever it looks like, `tag name 42` would make it a number
+        ["name", name @ ..] if !name.is_empty() => {
+            Ok(Command::Bulk(Bulk::Tag("name".to_string(), Value::Text(name.join(" ")))))
+        }
+        ["export", "field", path, rest @ ..] => {
+            let size = |word: &str| -> Result<usize, String> {
+                match word.parse() {
+                    Ok(size) if size > 0 => Ok(size),
+                    _ => Err(format!("invalid grid size `{}`", word)),
+                }
+            };
+            let grid = match rest {
+                [] => Grid::default(),
+                [columns, rows] => {
+                    Grid { columns: size(columns)?, rows: size(rows)?, ..Grid::default() }
+                }
+                [columns, rows, x0, y0, x1, y1] => {
+                    Grid {
+                        columns: size(columns)?,
+                        rows: size(rows)?,
+                        min: vec2(number(x0)?, number(y0)?),
+                        max: vec2(number(x1)?, number(y1)?),
+                    }
+                }
+                _ => {
+                    return Err(
+                        "usage: export field <path> [<columns> <rows> [<x0> <y0> <x1> <y1>]]"
+                            .to_string()
+                    );
+                }
+            };
+            Ok(Command::Export {
+                format: ExportFormat::Field(grid),
+                path: path.to_string(),
+            })
+        }
+        ["export", format, path] => {
+            let format = match *format {
+                "csv" => ExportFormat::Csv,
+                "diagnostics" => ExportFormat::Diagnostics,
+                "tipsy" => ExportFormat::Tipsy,
+                _ => {
+                    return Err(format!("unknown export format `{}`", format));
+                }
+            };
+            Ok(Command::Export {
+                format,
+                path: path.to_string(),
+            })
+        }
+        ["exec", path] => Ok(Command::Exec(path.to_string())),
+        ["import", "tipsy", path, rest @ ..] if rest.len() <= 1 => {
+            let plane = match rest {
+                [name] => {
+                    Plane::parse(name).ok_or_else(|| {
+                        format!("unknown plane `{}`, xy, xz or yz", name)
+                    })?
+                }
+                _ => Plane::Xy,
+            };
+            Ok(Command::Import { path: path.to_string(), plane })
+        }
+        ["hacks"] => Ok(Command::Hacks),
+        ["preset", name] => {
+            Preset::parse(name)
+                .map(Command::Preset)
+                .ok_or_else(|| format!("unknown preset `{}`", name))
+        }
+        ["schedule", path] => Ok(Command::Schedule(path.to_string())),
+        ["calibrate", target] => Ok(Command::Calibrate(number(target)?)),
+        ["checkpoint", "restore", name] => Ok(Command::Restore(name.to_string())),
+        ["checkpoint", "now"] => {
+            Err("`now` is the running simulation, pick another name".to_string())
+        }
+        ["checkpoint", name] => Ok(Command::Checkpoint(name.to_string())),
+        ["checkpoints"] => Ok(Command::Checkpoints),
+        ["diff", from, to, rest @ ..] if rest.len() <= 1 => {
+            Ok(Command::Diff {
+                from: from.to_string(),
+                to: to.to_string(),
+                path: rest.first().map(|path| path.to_string()),
+            })
+        }
+        ["orbit", "apply"] => Ok(Command::ApplyOrbit),
+        ["orbit", "cancel"] => Ok(Command::CancelOrbit),
+        ["orbit", rest @ ..] if rest.len() <= 2 => {
+            let eccentricity = rest.first().map_or(Ok(0.0), |word| number(word))?;
+            let primary = match rest.get(1) {
+                Some(id) => Some(id.parse().map_err(|_| format!("invalid id `{}`", id))?),
+                None => None,
+            };
+            Ok(Command::Orbit { eccentricity, primary })
+        }
+        ["assist", "apply"] => Ok(Command::ApplyAssist),
+        ["assist", "cancel"] => Ok(Command::CancelAssist),
+        ["assist", target, heading] => {
+            Ok(Command::Assist {
+                target: target.parse().map_err(|_| format!("invalid id `{}`", target))?,
+                heading: number(heading)?,
+            })
+        }
+        ["quiz", "add", rest @ ..] => {
+            let text = rest.join(" ");
+            match text.split_once('|') {
+                Some((prompt, answer)) if !prompt.trim().is_empty() => {
+                    Ok(Command::Question {
+                        prompt: prompt.trim().to_string(),
+                        answer: answer.trim().to_string(),
+                    })
+                }
+                _ => Err("usage: quiz add <question> | <answer>".to_string()),
+            }
+        }
+        ["quiz"] => Ok(Command::Quiz(0)),
+        ["quiz", "next"] => Ok(Command::Quiz(1)),
+        ["quiz", "back"] => Ok(Command::Quiz(-1)),
+        ["quiz", "reveal"] => Ok(Command::Reveal),
+        ["quiz", "clear"] => Ok(Command::ClearQuiz),
+        ["record", "stop"] => Ok(Command::StopRecording),
+        ["video", "stop"] => Ok(Command::StopVideo),
+        ["video", path, rest @ ..] if rest.len() <= 1 => {
+            let every = match rest {
+                [every] => {
+                    every
+                        .parse()
+                        .ok()
+                        .filter(|&every| every > 0)
+                        .ok_or_else(|| format!("invalid frame count `{}`", every))?
+                }
+                _ => 1,
+            };
+            Ok(Command::Video { path: path.to_string(), every })
+        }
+        ["record", path] => {
+            Ok(Command::Record { path: path.to_string(), every: 1, track: false })
+        }
+        ["record", path, every, rest @ ..] if rest.is_empty() || rest == ["track"] => {
+            match every.parse() {
+                Ok(every) if every > 0 => {
+                    Ok(Command::Record { path: path.to_string(), every, track: !rest.is_empty() })
+                }
+                _ => Err(format!("invalid step count `{}`", every)),
+            }
+        }
+        ["integrator", name] => {
+            Integrator::parse(name)
+                .map(Command::Integrator)
+                .ok_or_else(|| format!("unknown integrator `{}`", name))
+        }
+        ["watchdog", mode] => {
+            watchdog::Mode::parse(mode)
+                .map(Command::Watchdog)
+                .ok_or_else(|| format!("unknown watchdog mode `{}`, off, tune or pause", mode))
+        }
+        ["numbers", name] => {
+            match (Notation::parse(name), Separator::parse(name)) {
+                (Some(notation), _) => Ok(Command::Notation(notation)),
+                (_, Some(separator)) => Ok(Command::Separator(separator)),
+                _ => {
+                    let formats = "auto, scientific, si, point or comma";
+                    Err(format!("unknown number format `{}`, {}", name, formats))
+                }
+            }
+        }
+        ["gallery", "add", path, name, rest @ ..] => {
+            // tags, then the description after a bar
+            let text = rest.join(" ");
+            let (tags, description) = text.split_once('|').unwrap_or((&text, ""));
+            Ok(Command::Gallery(GalleryAction::Add {
+                path: path.to_string(),
+                name: name.to_string(),
+                tags: tags.split_whitespace().map(str::to_string).collect(),
+                description: description.trim().to_string(),
+            }))
+        }
+        ["gallery", "remove", name] => {
+            Ok(Command::Gallery(GalleryAction::Remove(name.to_string())))
+        }
+        ["gallery", verb @ ("tag" | "untag"), name, tags @ ..] if !tags.is_empty() => {
+            let (name, tags) = (name.to_string(), owned(tags));
+            Ok(Command::Gallery(if *verb == "tag" {
+                GalleryAction::Tag(name, tags)
+            } else {
+                GalleryAction::Untag(name, tags)
+            }))
+        }
+        ["gallery", "describe", name, text @ ..] => {
+            Ok(Command::Gallery(GalleryAction::Describe(name.to_string(), text.join(" "))))
+        }
+        ["gallery"] => Ok(Command::Gallery(GalleryAction::Search(Vec::new()))),
+        ["gallery", "search", query @ ..] => {
+            Ok(Command::Gallery(GalleryAction::Search(owned(query))))
+        }
+        ["gallery", "open", name] => Ok(Command::Gallery(GalleryAction::Open(name.to_string()))),
+        ["gallery", "export", path, names @ ..] => {
+            Ok(Command::Gallery(GalleryAction::Export(path.to_string(), owned(names))))
+        }
+        ["gallery", "import", path] => {
+            Ok(Command::Gallery(GalleryAction::Import(path.to_string())))
+        }
+        ["style", name] => {
+            BodyStyle::parse(name)
+                .map(Command::Style)
+                .ok_or_else(|| format!("unknown style `{}`, outline, filled or sprite", name))
+        }
+        ["theme", name] => Ok(Command::Theme(name.to_string())),
+        ["theme"] => Err(format!("usage: theme <{} | file.json>", Theme::BUILT_IN.join(" | "))),
+        [] => Err("empty command".to_string()),
+        [name, ..] => Err(format!("unknown command `{}`", name)),
+    }
+}
+
+fn number(word: &str) -> Result<f32, String> {
+    word.parse().map_err(|_| format!("invalid number `{}`", word))
+}
+
+fn owned(list: &[&str]) -> Vec<String> {
+    list.iter().map(|word| word.to_string()).collect()
+}
+
+fn selection(expression: &str) -> Result<Expr, String> {
+    match expression {
+        "all" => query::parse("true"),
+        "none" => query::parse("false"),
+        _ => query::parse(expression),
+    }
+}
+
+pub fn execute(command: Command, context: &mut Context) -> Result<String, String> {
+    match command {
+        Command::Set { name, value } => {
+            // `number` takes "nan" and "inf", nothing here wants them
+            if !value.is_finite() {
+                return Err(format!("invalid number `{}`", value));
+            }
+            let settings = &mut context.settings;
+            // dt, substeps and theta answer with what they were clamped to
+            match name.as_str() {
+                "G" | "gravity" => {
+                    settings.gravity = value;
+                }
+                "dt" => {
+                    settings.dt = value.clamp(MIN_DT, MAX_DT);
+                    return Ok(format!("dt = {}", settings.dt));
+                }
+                "substeps" => {
+                    settings.substeps = (value.max(1.0) as usize).min(MAX_SUBSTEPS);
+                    return Ok(format!("substeps = {}", settings.substeps));
+                }
+                "time_scale" => {
+                    settings.time_scale = value.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+                }
+                "theta" => {
+                    settings.theta = value.clamp(MIN_THETA, MAX_THETA);
+                    return Ok(format!("theta = {}", settings.theta));
+                }
+                "expansion_order" => {
+                    settings.expansion_order = (value.max(0.0) as usize).min(fmm::MAX_ORDER);
+                }
+                "leaf_capacity" => {
+                    settings.leaf_capacity = value.max(1.0) as usize;
+                }
+                "restitution" => {
+                    settings.restitution = value.clamp(0.0, 1.0);
+                }
+                "wall_friction" => {
+                    settings.wall_friction = value.clamp(0.0, 1.0);
+                }
+                "max_velocity" => {
+                    settings.max_velocity = value.max(0.0);
+                }
+                "throw_strength" => {
+                    settings.throw_strength = value;
+                }
+                "simd" => {
+                    if value != 0.0 && !simd::KERNEL {
+                        return Err("there's no SIMD kernel for this platform".to_string());
+                    }
+                    settings.simd = value != 0.0;
+                }
+                "ccd" => {
+                    settings.ccd = value != 0.0;
+                }
+                // all the non-physical shortcuts off (or back on) at once
+                "ideal" => {
+                    settings.hacks = if value != 0.0 { Hacks::NONE } else { Hacks::ALL };
+                }
+                "relativity" => {
+                    settings.relativity = value != 0.0;
+                }
+                "periodic" => {
+                    settings.periodic = value != 0.0;
+                }
+                "separate" => {
+                    settings.separate = value != 0.0;
+                }
+                "separation" => {
+                    settings.separation = value.max(0.0);
+                }
+                "separation_iterations" => {
+                    settings.separation_iterations = value.max(1.0) as usize;
+                }
+                "trail_length" => {
+                    settings.trail_length = (value.max(1.0) as usize).min(MAX_TRAIL_LENGTH);
+                }
+                "trail_fade" => {
+                    settings.trail_fade = value.max(0.0);
+                }
+                "velocity_scale" => {
+                    settings.velocity_scale = value.max(0.0);
+                }
+                "predict_path" => {
+                    settings.predict_path = value != 0.0;
+                }
+                "brush_strength" => {
+                    settings.brush_strength = value.clamp(brush::MIN_STRENGTH, brush::MAX_STRENGTH);
+                }
+                "starfield" => {
+                    settings.starfield = value != 0.0;
+                }
+                "lod_radius" => {
+                    settings.lod_radius = value.max(0.0);
+                }
+                "energy_window" => {
+                    settings.energy_window = value.max(1.0);
+                }
+                "acceleration_scale" => {
+                    settings.acceleration_scale = value.max(0.0);
+                }
+                "noise_amplitude" => {
+                    settings.noise_amplitude = value.max(0.0);
+                }
+                "noise_correlation" => {
+                    if value <= 0.0 {
+                        return Err("the correlation time has to be positive".to_string());
+                    }
+                    settings.noise_correlation = value;
+                }
+                "tide_major" => {
+                    settings.tide_major = value;
+                }
+                "tide_minor" => {
+                    settings.tide_minor = value;
+                }
+                "tide_angle" => {
+                    settings.tide_angle = value;
+                }
+                "c" | "light_speed" => {
+                    if value <= 0.0 {
+                        return Err("the speed of light has to be positive".to_string());
+                    }
+                    settings.light_speed = value;
+                }
+                // takes effect with the next preset
+                "body_count" => {
+                    settings.body_count = value.max(0.0) as usize;
+                }
+                _ if settings.hacks.set(&name, value != 0.0) => {}
+                _ => {
+                    return Err(format!("unknown setting `{}`", name));
+                }
+            }
+            Ok(format!("{} = {}", name, value))
+        }
+        Command::SetSeed(seed) => {
+            context.settings.seed = seed;
+            Ok(format!("seed = {}", seed))
+        }
+        Command::Spawn { count, shape } => {
+            let spawned: Vec<Body> = match shape {
+                Shape::Random => (0..count).map(|_| Body::random(None)).collect(),
+                Shape::Disk { center, radius } => {
+                    (0..count)
+                        .map(|_| {
+                            // sqrt keeps the density uniform over the area
+                            let distance = radius * rng::gen_range(0.0f32, 1.0).sqrt();
+                            let direction = Vec2::from_angle(rng::gen_range(0.0, TAU));
+                            Body::random(Some(center + direction * distance))
+                        })
+                        .collect()
+                }
+                Shape::Plummer { center, radius } => {
+                    generators::plummer(count, center, radius, context.settings.gravity)
+                }
+                Shape::Rotating { center, radius } => {
+                    generators::rotating_disk(count, center, radius, context.settings.gravity)
+                }
+            };
+            for body in spawned {
+                context.metadata.set(body.id, "spawned_at", Value::Number(*context.time as f64));
+                context.bodies.push(body);
+            }
+            Ok(format!("spawned {} bodies", count))
+        }
+        Command::Select(expr) => {
+            // evaluate everything first so a bad query leaves the selection untouched
+            let matches = context.bodies
+                .iter()
+                .map(|body| expr.matches(&body, context.metadata))
+                .collect::<Result<Vec<bool>, String>>()?;
+            let count = matches.iter().filter(|&&selected| selected).count();
+            context.bodies.selected = matches;
+            Ok(format!("selected {} bodies", count))
+        }
+        Command::Bulk(operation) => {
+            let count = context.bodies.selected.iter().filter(|&&selected| selected).count();
+            if let Bulk::Delete = operation {
+                context.bodies.retain(|body| !body.selected);
+                context.metadata.retain(context.bodies);
+                return Ok(format!("deleted {} bodies", count));
+            }
+
+            let metadata = &mut context.metadata;
+            context.bodies.for_each_mut(|body| {
+                if !body.selected {
+                    return;
+                }
+                match &operation {
+                    Bulk::Freeze => {
+                        body.freezed = true;
+                    }
+                    Bulk::Unfreeze => {
+                        body.freezed = false;
+                        body.freeze_group = None;
+                        metadata.remove(body.id, "freeze_group");
+                    }
+                    Bulk::Impulse(impulse) => {
+                        body.velocity += *impulse;
+                    }
+                    Bulk::Mass(mass) => {
+                        body.mass = *mass;
+                    }
+                    Bulk::Tag(key, value) => {
+                        metadata.set(body.id, key, value.clone());
+                    }
+                    Bulk::Delete => unreachable!(),
+                }
+            });
+            Ok(format!("updated {} bodies", count))
+        }
+        Command::FreezeGroup { name, release_at } => {
+            if context.freeze_groups.find(&name).is_some() {
+                return Err(format!("there's already a group called `{}`", name));
+            }
+            let groups = &mut context.freeze_groups;
+            let index = groups
+                .freeze_selection(context.bodies, context.metadata, Some(&name), release_at)
+                .ok_or("nothing is selected")?;
+            let count = groups.count_members(index, context.bodies);
+            Ok(match release_at {
+                Some(time) => format!("froze {} bodies into {} until {}", count, name, time),
+                None => format!("froze {} bodies into {}", count, name),
+            })
+        }
+        Command::Release(name) => {
+            let index = context.freeze_groups
+                .find(&name)
+                .ok_or_else(|| format!("no group called `{}`", name))?;
+            let count = context.freeze_groups.count_members(index, context.bodies);
+            context.freeze_groups.release(index, context.bodies, context.metadata);
+            Ok(format!("released {} bodies of {}", count, name))
+        }
+        Command::Export { format, path } => {
+            let provenance = Provenance::new(context.settings);
+            let result = match format {
+                ExportFormat::Csv => export::bodies_csv(context.bodies, &path, &provenance),
+                ExportFormat::Diagnostics => context.diagnostics.export_csv(&path, &provenance),
+                ExportFormat::Tipsy => {
+                    let gravity = context.settings.gravity;
+                    tipsy::write(context.bodies, &path, *context.time, gravity)
+                }
+                ExportFormat::Field(grid) => {
+                    let sampled = field::sample(context.bodies, grid, context.settings.gravity);
+                    field::write(&sampled, &path, &provenance)
+                }
+            };
+            result.map_err(|err| format!("failed to write {}: {}", path, err))?;
+            Ok(format!("exported to {}", path))
+        }
+        Command::Exec(path) => {
+            if context.exec_depth >= MAX_EXEC_DEPTH {
+                return Err(format!("scripts nested more than {} deep", MAX_EXEC_DEPTH));
+            }
+            let script = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+            context.exec_depth += 1;
+            let output: Vec<String> = run_script(&script, context);
+            context.exec_depth -= 1;
+            Ok(output.join("\n"))
+        }
+        Command::Import { path, plane } => {
+            let snapshot = tipsy::read(&path)?;
+            let gravity = context.settings.gravity;
+            let (imported, length, time) = tipsy::place(&snapshot, plane, gravity)?;
+            context.bodies.clear();
+            context.metadata.clear();
+            context.schedules.clear();
+            context.freeze_groups.clear();
+            context.settings.scale = None;
+            context.noise.reset(context.settings.seed);
+            for (body, particle) in imported.into_iter().zip(&snapshot.particles) {
+                context.metadata.set(body.id, "family", Value::Text(particle.family.to_string()));
+                context.bodies.push(body);
+            }
+            let numbers = context.settings.numbers;
+            Ok(
+                format!(
+                    "imported {} bodies from time {} in the {} plane, a length unit is {} and \
+                     a time unit {}, Z shows the third axis as depth",
+                    context.bodies.len(),
+                    numbers.format(snapshot.time, 3),
+                    plane.name(),
+                    numbers.quantity(length, 2, "px"),
+                    numbers.format(time, 2)
+                )
+            )
+        }
+        Command::Preset(preset) => {
+            preset.load(context.bodies, context.settings, context.metadata, *context.time);
+            context.schedules.clear();
+            context.freeze_groups.clear();
+            context.noise.reset(context.settings.seed);
+            Ok(format!("loaded {} preset", preset.name()))
+        }
+        Command::Integrator(integrator) => {
+            context.settings.integrator = integrator;
+            Ok(format!("integrator {}", integrator.name()))
+        }
+        Command::Watchdog(mode) => {
+            context.settings.watchdog = mode;
+            Ok(format!("watchdog {}", mode.name()))
+        }
+        Command::Notation(notation) => {
+            context.settings.numbers.notation = notation;
+            Ok(format!("numbers in {} notation", notation.name()))
+        }
+        Command::Separator(separator) => {
+            context.settings.numbers.separator = separator;
+            Ok(format!("numbers with a decimal {}", separator.name()))
+        }
+        Command::Style(style) => {
+            context.settings.body_style = style;
+            Ok(format!("bodies drawn {}", style.name()))
+        }
+        Command::Theme(name) => {
+            context.settings.theme = Theme::find(&name)?;
+            Ok(format!("theme {}", context.settings.theme.name))
+        }
+        Command::Schedule(path) => {
+            let (kind, samples) = schedule::load(&path)?;
+            let mut count = 0;
+            for body in context.bodies.iter().filter(|body| body.selected) {
+                context.schedules.add(body.id, kind, samples.clone(), *context.time);
+                count += 1;
+            }
+            let what = match kind {
+                Kind::Acceleration => "accelerations",
+                Kind::Waypoints => "waypoints",
+            };
+            let duration = context.schedules.list.last().map_or(0.0, |last| last.duration());
+            Ok(format!("scheduled {} over {} time units for {} bodies", what, duration, count))
+        }
+        Command::Record { path, every, track } => {
+            let tracks: Vec<u64> = (0..context.bodies.len())
+                .filter(|&index| track && context.bodies.selected[index])
+                .map(|index| context.bodies.ids[index])
+                .collect();
+            if track && tracks.is_empty() {
+                return Err("select the bodies to track first".to_string());
+            }
+            if let Some(recorder) = context.recorder.take() {
+                recorder.stop().map_err(|err| format!("failed to write {}: {}", path, err))?;
+            }
+            let mut provenance = Provenance::new(context.settings);
+            if track {
+                let ids: Vec<String> = tracks.iter().map(u64::to_string).collect();
+                provenance.entries.push(("tracks".to_string(), ids.join(" ")));
+            }
+            let recorder = Recorder::start(&path, every, &tracks, &provenance).map_err(|err| {
+                format!("failed to write {}: {}", path, err)
+            })?;
+            *context.recorder = Some(recorder);
+            if track {
+                Ok(format!("recording to {}, tracking {} bodies", path, tracks.len()))
+            } else {
+                Ok(format!("recording to {}", path))
+            }
+        }
+        Command::StopRecording => {
+            let recorder = context.recorder.take().ok_or("not recording")?;
+            let path = recorder.path.clone();
+            let frames = recorder
+                .stop()
+                .map_err(|err| format!("failed to write {}: {}", path, err))?;
+            Ok(format!("recorded {} frames to {}", frames, path))
+        }
+        Command::Video { path, every } => {
+            if let Some(video) = context.video.take() {
+                video.stop()?;
+            }
+            *context.video = Some(Video::start(&path, every)?);
+            Ok(format!("filming to {}", path))
+        }
+        Command::StopVideo => {
+            let video = context.video.take().ok_or("not filming")?;
+            let path = video.path.clone();
+            let frames = video.stop()?;
+            Ok(format!("filmed {} frames to {}", frames, path))
+        }
+        Command::Calibrate(target) => context.contacts.calibrate(target, context.settings),
+        Command::Checkpoint(name) => {
+            let checkpoint = current(&name, context);
+            let replaced = context.checkpoints.save(checkpoint);
+            Ok(
+                format!(
+                    "{} checkpoint {} at t = {:.2}",
+                    if replaced { "replaced" } else { "saved" },
+                    name,
+                    *context.time
+                )
+            )
+        }
+        Command::Restore(name) => {
+            let checkpoint = context.checkpoints.get(&name)?.clone();
+            *context.bodies = checkpoint.bodies;
+            *context.metadata = checkpoint.metadata;
+            *context.settings = checkpoint.settings;
+            *context.time = checkpoint.time;
+            *context.freeze_groups = checkpoint.freeze_groups;
+            // schedules run against the clock of the branch they were made on, and planned
+            // orbits and assists are for bodies as they were on it
+            context.schedules.clear();
+            *context.insertion = None;
+            *context.assist = None;
+            Ok(format!("restored checkpoint {} at t = {:.2}", name, checkpoint.time))
+        }
+        Command::Checkpoints => Ok(context.checkpoints.report()),
+        Command::Diff { from, to, path } => {
+            let now = current("now", context);
+            let lookup = |name: &str| {
+                if name == "now" { Ok(&now) } else { context.checkpoints.get(name) }
+            };
+            let diff = Diff::new(lookup(&from)?, lookup(&to)?);
+            let mut report = diff.summary();
+            if let Some(path) = path {
+                diff.write_csv(&path).map_err(|err| format!("failed to write {}: {}", path, err))?;
+                report.push_str(&format!("\nwrote every body to {}", path));
+            }
+            Ok(report)
+        }
+        Command::Orbit { eccentricity, primary } => {
+            let insertion = Insertion::new(context.bodies, eccentricity, primary)?;
+            *context.insertion = Some(insertion);
+            Ok(insertion.describe(context.bodies, context.metadata, context.settings.gravity))
+        }
+        Command::ApplyOrbit => {
+            let insertion = context.insertion.take().ok_or("no orbit planned, `orbit` plans one")?;
+            let delta_v = insertion.apply(context.bodies, context.settings.gravity)?;
+            Ok(format!("burned {:.3} for {}", delta_v, context.metadata.label(insertion.body)))
+        }
+        Command::CancelOrbit => {
+            context.insertion.take().ok_or("no orbit planned")?;
+            Ok("orbit cancelled".to_string())
+        }
+        Command::Assist { target, heading } => {
+            let plan = Plan::search(context.bodies, target, heading, context.settings)?;
+            let summary = plan.summary(context.metadata);
+            *context.assist = Some(plan);
+            Ok(summary)
+        }
+        Command::ApplyAssist => {
+            let plan = context.assist.take().ok_or("no flyby planned, `assist` plans one")?;
+            let launch = plan.apply(context.bodies)?;
+            let name = context.metadata.label(plan.body);
+            Ok(format!("launched {} at speed {:.3}", name, launch.length()))
+        }
+        Command::CancelAssist => {
+            context.assist.take().ok_or("no flyby planned")?;
+            Ok("flyby cancelled".to_string())
+        }
+        Command::Question { prompt, answer } => {
+            context.quiz.add(&prompt, &answer);
+            Ok(format!("question {} added", context.quiz.len()))
+        }
+        Command::Quiz(by) => Ok(context.quiz.go(by)?.prompt.clone()),
+        Command::Reveal => Ok(context.quiz.reveal()?.answer.clone()),
+        Command::ClearQuiz => {
+            context.quiz.clear();
+            Ok("questions cleared".to_string())
+        }
+        Command::Hacks => Ok(context.settings.hacks.report()),
+        Command::Gallery(GalleryAction::Open(name)) => {
+            let (kind, path) = context.gallery.locate(&name)?;
+            if kind == gallery::Kind::Recording {
+                return Ok(format!("{} is a recording, `threebody analyze info {}`", name, path));
+            }
+            if context.exec_depth >= MAX_EXEC_DEPTH {
+                return Err(format!("scenarios nested more than {} deep", MAX_EXEC_DEPTH));
+            }
+            let text = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+            let lines = scenario::commands(&text)?;
+            context.exec_depth += 1;
+            let output: Vec<String> = lines
+                .iter()
+                .map(|line| {
+                    match run(line, context) {
+                        Ok(output) => output,
+                        Err(err) => format!("{}: {}", line, err),
+                    }
+                })
+                .collect();
+            context.exec_depth -= 1;
+            Ok(output.join("\n"))
+        }
+        Command::Gallery(action) => {
+            let gallery = &mut context.gallery;
+            match action {
+                GalleryAction::Add { path, name, tags, description } => {
+                    gallery.add(&path, &name, tags, description)
+                }
+                GalleryAction::Remove(name) => gallery.remove(&name),
+                GalleryAction::Tag(name, tags) => gallery.tag(&name, &tags, true),
+                GalleryAction::Untag(name, tags) => gallery.tag(&name, &tags, false),
+                GalleryAction::Describe(name, description) => gallery.describe(&name, description),
+                GalleryAction::Search(words) => gallery.search(&words),
+                GalleryAction::Open(_) => unreachable!(),
+                GalleryAction::Export(path, names) => gallery.export(&path, &names),
+                GalleryAction::Import(path) => gallery.import(&path),
+            }
+        }
+    }
+}
+
+fn current(name: &str, context: &Context) -> Checkpoint {
+    Checkpoint {
+        name: name.to_string(),
+        time: *context.time,
+        bodies: context.bodies.clone(),
+        metadata: context.metadata.clone(),
+        settings: context.settings.clone(),
+        freeze_groups: context.freeze_groups.clone(),
+    }
+}
+
+pub fn run(line: &str, context: &mut Context) -> Result<String, String> {
+    execute(parse(line)?, context)
+}
+
+// one command per line, `#` starts a comment
+pub fn run_script(script: &str, context: &mut Context) -> Vec<String> {
+    script
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            match run(line, context) {
+                Ok(output) => output,
+                Err(err) => format!("{}: {}", line, err),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+use crate::recording::Recording;
+
+// plot size in SVG user units
+const PLOT_WIDTH: f32 = 640.0;
+const PLOT_HEIGHT: f32 = 360.0;
+const PLOT_MARGIN: f32 = 48.0;
+
+// how far apart the two runs are at one time, over the bodies both have
+struct Divergence {
+    time: f32,
+    bodies: usize,
+    rms: f32,
+    max: f32,
+    max_id: u64,
+    rms_velocity: f32,
+}
+
+#[derive(Default)]
+struct BodyDivergence {
+    last: f32,
+    max: f32,
+    sum: f32,
+    frames: usize,
+}
+
+// `threebody analyze compare <a> <b> [prefix]`: lines the frames of `b` up with those of `a`
+// by time (interpolating when the runs recorded at different steps) and measures how far
+// every body has drifted. Writes `<prefix>.csv` over time, `<prefix>-bodies.csv` per body
+// and `<prefix>.svg`, a log plot of the drift
+pub fn run(a_path: &str, b_path: &str, prefix: &str) -> Result<String, String> {
+    let a = Recording::read(a_path)?;
+    let mut b = Recording::read(b_path)?;
+    match_ids(&a, &mut b);
+
+    let mut over_time = Vec::new();
+    let mut per_body: BTreeMap<u64, BodyDivergence> = BTreeMap::new();
+    for frame in &a.frames {
+        let (mut squared, mut squared_velocity) = (0.0, 0.0);
+        let mut divergence = Divergence {
+            time: frame.time,
+            bodies: 0,
+            rms: 0.0,
+            max: 0.0,
+            max_id: 0,
+            rms_velocity: 0.0,
+        };
+        for sample in &frame.samples {
+            let Some(other) = b.at(frame.time, sample.id) else {
+                continue;
+            };
+            let distance = sample.position.distance(other.position);
+            squared += distance.powi(2);
+            squared_velocity += sample.velocity.distance_squared(other.velocity);
+            if divergence.bodies == 0 || distance > divergence.max {
+                divergence.max = distance;
+                divergence.max_id = sample.id;
+            }
+            divergence.bodies += 1;
+
+            let body = per_body.entry(sample.id).or_default();
+            body.last = distance;
+            body.max = body.max.max(distance);
+            body.sum += distance;
+            body.frames += 1;
+        }
+        if divergence.bodies == 0 {
+            continue;
+        }
+        divergence.rms = (squared / divergence.bodies as f32).sqrt();
+        divergence.rms_velocity = (squared_velocity / divergence.bodies as f32).sqrt();
+        over_time.push(divergence);
+    }
+    if over_time.is_empty() {
+        return Err("the recordings share no bodies over the same time span".to_string());
+    }
+
+    let notes = header(a_path, &a, b_path, &b);
+    let written = write_csv(&format!("{}.csv", prefix), &notes, &over_time)
+        .and_then(|_| write_bodies(&format!("{}-bodies.csv", prefix), &notes, &per_body))
+        .and_then(|_| write_svg(&format!("{}.svg", prefix), &over_time));
+    written.map_err(|err| format!("failed to write {}: {}", prefix, err))?;
+
+    let last = over_time.last().unwrap();
+    let mut report = notes
+        .iter()
+        .filter_map(|line| line.strip_prefix("differs: "))
+        .map(|line| format!("{}\n", line))
+        .collect::<String>();
+    report.push_str(
+        &format!(
+            "{} frames, {} bodies compared from t = {} to {}\n\
+             at the end: rms drift {}, worst body {} off by {}",
+            over_time.len(),
+            per_body.len(),
+            over_time[0].time,
+            last.time,
+            last.rms,
+            last.max_id,
+            last.max
+        )
+    );
+    if let Some(rate) = growth_rate(&over_time) {
+        report.push_str(&format!("\ndrift grows by e every {} time units", 1.0 / rate));
+    }
+    report.push_str(&format!("\nwritten to {0}.csv, {0}-bodies.csv and {0}.svg", prefix));
+    Ok(report)
+}
+
+// ids count up over a session, so the same scenario run after something else gets different
+// ones. When the first keyframes share no ids but hold as many bodies, they're paired in order
+fn match_ids(a: &Recording, b: &mut Recording) {
+    let (first_a, first_b) = (a.first_keyframe(), b.first_keyframe());
+    let shared = first_a.samples.iter().any(|sample| first_b.get(sample.id).is_some());
+    if shared || first_a.samples.len() != first_b.samples.len() {
+        return;
+    }
+    let renamed: BTreeMap<u64, u64> = first_b.samples
+        .iter()
+        .zip(&first_a.samples)
+        .map(|(from, to)| (from.id, to.id))
+        .collect();
+    for frame in &mut b.frames {
+        // bodies that weren't there at the start have no partner
+        frame.samples.retain(|sample| renamed.contains_key(&sample.id));
+        for sample in &mut frame.samples {
+            sample.id = renamed[&sample.id];
+        }
+        frame.samples.sort_by_key(|sample| sample.id);
+    }
+}
+
+// where the two runs came from and which of their settings differ, as comment lines
+fn header(a_path: &str, a: &Recording, b_path: &str, b: &Recording) -> Vec<String> {
+    let mut notes = vec![format!("a: {}", a_path), format!("b: {}", b_path)];
+    for (key, value) in &a.provenance.entries {
+        if key == "created" {
+            continue;
+        }
+        let other = b.provenance.entries
+            .iter()
+            .find(|(other, _)| other == key)
+            .map_or("?", |(_, value)| value.as_str());
+        if other != value {
+            notes.push(format!("differs: {} {} -> {}", key, value, other));
+        }
+    }
+    notes
+}
+
+// least squares slope of ln(rms) over time, for the frames that drifted at all. Positive for
+// chaotic runs, where it approximates the largest Lyapunov exponent
+fn growth_rate(over_time: &[Divergence]) -> Option<f32> {
+    let points: Vec<(f64, f64)> = over_time
+        .iter()
+        .filter(|divergence| divergence.rms > 0.0)
+        .map(|divergence| (divergence.time as f64, (divergence.rms as f64).ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let count = points.len() as f64;
+    let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points.iter().map(|(t, y)| (t - mean_t) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+    let rate = covariance / variance;
+    (variance > 0.0 && rate > 0.0).then_some(rate as f32)
+}
+
+fn write_csv(path: &str, notes: &[String], over_time: &[Divergence]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for note in notes {
+        writeln!(file, "# {}", note)?;
+    }
+    writeln!(file, "time,bodies,rms,max,max_id,rms_velocity")?;
+    for divergence in over_time {
+        writeln!(
+            file,
+            "{},{},{},{},{},{}",
+            divergence.time,
+            divergence.bodies,
+            divergence.rms,
+            divergence.max,
+            divergence.max_id,
+            divergence.rms_velocity
+        )?;
+    }
+    file.flush()
+}
+
+fn write_bodies(
+    path: &str,
+    notes: &[String],
+    per_body: &BTreeMap<u64, BodyDivergence>
+) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for note in notes {
+        writeln!(file, "# {}", note)?;
+    }
+    writeln!(file, "id,last,max,mean")?;
+    for (id, body) in per_body {
+        writeln!(
+            file,
+            "{},{},{},{}",
+            id,
+            body.last,
+            body.max,
+            body.sum / body.frames as f32
+        )?;
+    }
+    file.flush()
+}
+
+// rms and max drift against time, log scale since chaotic drift grows exponentially
+fn write_svg(path: &str, over_time: &[Divergence]) -> io::Result<()> {
+    let positive = over_time
+        .iter()
+        .flat_map(|divergence| [divergence.rms, divergence.max])
+        .filter(|&value| value > 0.0);
+    let smallest = positive.clone().fold(f32::INFINITY, f32::min);
+    let largest = positive.fold(0.0, f32::max);
+    let floor = if largest > 0.0 { smallest.log10().floor() } else { 0.0 };
+    let ceiling = largest.log10().ceil().max(floor + 1.0);
+    let (start, end) = (over_time[0].time, over_time[over_time.len() - 1].time);
+    let span = (end - start).max(f32::EPSILON);
+
+    let (width, height) = (PLOT_WIDTH - 2.0 * PLOT_MARGIN, PLOT_HEIGHT - 2.0 * PLOT_MARGIN);
+    let x = |time: f32| PLOT_MARGIN + ((time - start) / span) * width;
+    let y = |value: f32| {
+        let exponent = value.max(10f32.powf(floor)).log10();
+        PLOT_MARGIN + height * (1.0 - (exponent - floor) / (ceiling - floor))
+    };
+    let line = |value: fn(&Divergence) -> f32| {
+        over_time
+            .iter()
+            .map(|divergence| format!("{:.1},{:.1}", x(divergence.time), y(value(divergence))))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"sans-serif\" font-size=\"12\">",
+        PLOT_WIDTH,
+        PLOT_HEIGHT
+    )?;
+    writeln!(file, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")?;
+    writeln!(
+        file,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>",
+        PLOT_MARGIN,
+        PLOT_MARGIN,
+        width,
+        height
+    )?;
+    for exponent in (floor as i32)..=(ceiling as i32) {
+        let tick = y(10f32.powi(exponent));
+        writeln!(
+            file,
+            "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">1e{}</text>",
+            PLOT_MARGIN - 4.0,
+            tick + 4.0,
+            exponent
+        )?;
+    }
+    writeln!(
+        file,
+        "<text x=\"{}\" y=\"{}\">t = {}</text>",
+        PLOT_MARGIN,
+        PLOT_HEIGHT - PLOT_MARGIN + 16.0,
+        start
+    )?;
+    writeln!(
+        file,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">t = {}</text>",
+        PLOT_MARGIN + width,
+        PLOT_HEIGHT - PLOT_MARGIN + 16.0,
+        end
+    )?;
+    writeln!(
+        file,
+        "<polyline fill=\"none\" stroke=\"lightgray\" points=\"{}\"/>",
+        line(|divergence| divergence.max)
+    )?;
+    writeln!(
+        file,
+        "<polyline fill=\"none\" stroke=\"steelblue\" points=\"{}\"/>",
+        line(|divergence| divergence.rms)
+    )?;
+    writeln!(
+        file,
+        "<text x=\"{}\" y=\"{}\">drift: rms (blue) and max (gray)</text>",
+        PLOT_MARGIN,
+        PLOT_MARGIN - 12.0
+    )?;
+    writeln!(file, "</svg>")?;
+    file.flush()
+}
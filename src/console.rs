@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+const MAX_OUTPUT_LINES: usize = 12;
+const LINE_HEIGHT: f32 = 18.0;
+
+#[derive(Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    history: Vec<String>,
+    // position while browsing the history with the arrow keys
+    history_index: Option<usize>,
+    output: VecDeque<String>,
+}
+
+impl Console {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn open_with(&mut self, input: &str) {
+        // swallow the key that opened the box so it doesn't end up in the input
+        while get_char_pressed().is_some() {}
+        self.open = true;
+        self.input = input.to_string();
+    }
+
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            if self.output.len() == MAX_OUTPUT_LINES {
+                self.output.pop_front();
+            }
+            self.output.push_back(line.to_string());
+        }
+    }
+
+    // reads typed characters and returns a line once enter is pressed
+    pub fn update(&mut self) -> Option<String> {
+        while let Some(character) = get_char_pressed() {
+            if self.open && !character.is_control() && character != '`' && character != '~' {
+                self.input.push(character);
+            }
+        }
+        if !self.open {
+            return None;
+        }
+
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Up) && !self.history.is_empty() {
+            let index = match self.history_index {
+                Some(index) => index.saturating_sub(1),
+                None => self.history.len() - 1,
+            };
+            self.history_index = Some(index);
+            self.input = self.history[index].clone();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            if let Some(index) = self.history_index {
+                let next = index + 1;
+                self.history_index = if next < self.history.len() { Some(next) } else { None };
+                self.input = match self.history_index {
+                    Some(index) => self.history[index].clone(),
+                    None => String::new(),
+                };
+            }
+        }
+
+        if is_key_pressed(KeyCode::Enter) && !self.input.trim().is_empty() {
+            let line = std::mem::take(&mut self.input);
+            self.print(&format!("> {}", line));
+            self.history.push(line.clone());
+            self.history_index = None;
+            return Some(line);
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        if !self.open {
+            return;
+        }
+
+        let height = ((MAX_OUTPUT_LINES + 1) as f32) * LINE_HEIGHT + 10.0;
+        draw_rectangle(0.0, 0.0, screen_width(), height, Color::new(0.0, 0.0, 0.0, 0.8));
+        for (i, line) in self.output.iter().enumerate() {
+            draw_text(line, 8.0, LINE_HEIGHT * ((i + 1) as f32), 18.0, LIGHTGRAY);
+        }
+        draw_text(format!("> {}_", self.input), 8.0, height - 8.0, 18.0, WHITE);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+use crate::Body;
+
+// frames the rolling averages on the panel cover, about two seconds at 60 fps
+const HISTORY_FRAMES: usize = 120;
+const PANEL_WIDTH: f32 = 330.0;
+const PANEL_LINES: usize = 5;
+
+// one resolved collision between two bodies
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Impact {
+    pub position: Vec2,
+    // how fast the bodies closed in along the line between their centers. Negative for
+    // overlapping bodies already moving apart, which `Hacks::velocity_swap` still swaps
+    pub speed: f32,
+    // and how fast they moved apart along it right after, or at the end of the step once
+    // `settle` has run
+    pub rebound: f32,
+    pub energy_lost: f32,
+    // neither body hit anything else in the same step, so the rebound is the pair's own
+    pub isolated: bool,
+    normal: Vec2,
+    // indices of the pair, valid until the bodies are next added or removed
+    pub pair: (usize, usize),
+}
+
+impl Impact {
+    // the same pair before and after the collision response
+    pub fn between(pair: (usize, usize), before: (&Body, &Body), after: (&Body, &Body)) -> Self {
+        let normal = (before.1.position - before.0.position).normalize_or_zero();
+        let kinetic = |a: &Body, b: &Body| {
+            0.5 * a.mass * a.velocity.length_squared() + 0.5 * b.mass * b.velocity.length_squared()
+        };
+        Impact {
+            position: (after.0.position + after.1.position) / 2.0,
+            speed: (before.0.velocity - before.1.velocity).dot(normal),
+            rebound: (after.1.velocity - after.0.velocity).dot(normal),
+            energy_lost: kinetic(before.0, before.1) - kinetic(after.0, after.1),
+            isolated: true,
+            normal,
+            pair,
+        }
+    }
+
+    // measures the rebound again after the rest of the step, so gravity pulling the pair back
+    // together counts towards how bouncy it felt. `hits` is how many collisions of the step
+    // every body was in
+    pub fn settle(&mut self, bodies: &Bodies, hits: &[u32]) {
+        let (i, j) = self.pair;
+        self.rebound = (bodies.velocities[j] - bodies.velocities[i]).dot(self.normal);
+        self.isolated = hits[i] == 1 && hits[j] == 1;
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct FrameStats {
+    count: usize,
+    // the speeds only count the isolated impacts that closed in
+    closing: usize,
+    speed: f32,
+    rebound: f32,
+    energy_lost: f32,
+}
+
+// collision counts, impact speeds and the kinetic energy collisions take out, per frame
+#[derive(Default)]
+pub struct ContactStats {
+    pub visible: bool,
+    frames: VecDeque<FrameStats>,
+    total_count: u64,
+    total_energy_lost: f64,
+}
+
+impl ContactStats {
+    pub fn record(&mut self, impacts: &[Impact]) {
+        let mut frame = FrameStats::default();
+        for impact in impacts {
+            frame.count += 1;
+            frame.energy_lost += impact.energy_lost;
+            // a body in a pile gets pushed around by all of its contacts at once
+            if impact.speed > 0.0 && impact.isolated {
+                frame.closing += 1;
+                frame.speed += impact.speed;
+                frame.rebound += impact.rebound;
+            }
+        }
+        if self.frames.len() == HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        self.total_count += frame.count as u64;
+        self.total_energy_lost += frame.energy_lost as f64;
+    }
+
+    pub fn reset(&mut self) {
+        *self = ContactStats { visible: self.visible, ..ContactStats::default() };
+    }
+
+    fn recent(&self) -> FrameStats {
+        self.frames.iter().fold(FrameStats::default(), |sum, frame| {
+            FrameStats {
+                count: sum.count + frame.count,
+                closing: sum.closing + frame.closing,
+                speed: sum.speed + frame.speed,
+                rebound: sum.rebound + frame.rebound,
+                energy_lost: sum.energy_lost + frame.energy_lost,
+            }
+        })
+    }
+
+    // rebound speed at the end of the step over impact speed, for the recent collisions: what
+    // restitution amounts to once gravity and overlapping contacts have had their say
+    pub fn bounciness(&self) -> Option<f32> {
+        let recent = self.recent();
+        (recent.closing > 0 && recent.speed > 0.0).then(|| recent.rebound / recent.speed)
+    }
+
+    // the restitution that should bring the measured bounciness to `target`, assuming it
+    // scales with restitution as it does for an isolated pair
+    pub fn calibrate(&self, target: f32, settings: &Settings) -> Result<String, String> {
+        if !(0.0..=1.0).contains(&target) {
+            return Err("bounciness goes from 0 (sticky) to 1 (elastic)".to_string());
+        }
+        if !settings.hacks.collision_friction {
+            return Err("restitution only applies with collision_friction on".to_string());
+        }
+        let measured = self
+            .bounciness()
+            .filter(|&measured| measured > 0.0)
+            .ok_or("no bouncing collisions recently, let some happen first")?;
+        let current = settings.restitution;
+        let suggested = current * (target / measured);
+        let mut report = format!(
+            "measured bounciness {:.2} at restitution {:.2} over {} impacts",
+            measured,
+            current,
+            self.recent().closing
+        );
+        if suggested > 1.0 {
+            report.push_str(
+                &format!(
+                    "\neven restitution 1 only reaches about {:.2}: gravity pulls the bodies \
+                     back together, `set force_cutoff 0` or a smaller G lets them part",
+                    measured / current
+                )
+            );
+        } else {
+            report.push_str(&format!("\nfor {:.2} try `set restitution {:.2}`", target, suggested));
+        }
+        Ok(report)
+    }
+
+    // where the panel ends at the top right, for whatever goes under it
+    pub fn bottom(&self) -> f32 {
+        if self.visible { 20.0 * (PANEL_LINES as f32) + 20.0 } else { 0.0 }
+    }
+
+    pub fn draw(&self, settings: &Settings) {
+        if !self.visible {
+            return;
+        }
+        let recent = self.recent();
+        let frames = self.frames.len().max(1) as f32;
+        let last = self.frames.back().copied().unwrap_or_default();
+        let average_speed = recent.speed / recent.closing.max(1) as f32;
+        let numbers = settings.numbers;
+        let bounciness = self.bounciness().map_or("-".to_string(), |value| {
+            numbers.format(value, 2)
+        });
+        let lines: [String; PANEL_LINES] = [
+            format!(
+                "collisions: {} this frame, {} per frame",
+                last.count,
+                numbers.format((recent.count as f32) / frames, 1)
+            ),
+            format!("impact speed: {} on average", numbers.format(average_speed, 2)),
+            format!(
+                "energy lost: {} per frame, {} in total",
+                numbers.format(recent.energy_lost / frames, 1),
+                numbers.format(self.total_energy_lost, 0)
+            ),
+            format!(
+                "bounciness: {}  restitution: {}",
+                bounciness,
+                numbers.format(settings.restitution, 2)
+            ),
+            format!("{} collisions since reset", self.total_count),
+        ];
+
+        let (width, height) = (PANEL_WIDTH, 20.0 * (lines.len() as f32) + 10.0);
+        let x = screen_width() - width;
+        let mut y = 30.0;
+        draw_rectangle(x - 10.0, y - 20.0, width, height, Color::new(0.0, 0.0, 0.0, 0.6));
+        for line in lines {
+            draw_text(&line, x, y, 18.0, LIGHTGRAY);
+            y += 20.0;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{ BufRead, BufReader, BufWriter, Read, Write };
+use std::net::TcpStream;
+use std::process::{ Child, Command, Stdio };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+// couples the simulation to an external model, a child process talking over stdin/stdout or
+// a server on a TCP socket, without linking it in. Before every step it is sent the state
+//
+//   step <step> <time> <dt> <count>
+//   <id> <x> <y> <vx> <vy> <mass>        (count lines)
+//
+// and answers with extra forces for any of the bodies, one per line, then `end`
+//
+//   <id> <fx> <fy>
+//   end
+//
+// which are added to gravity for that step. The simulation waits for the answer, so the
+// external model runs in lockstep
+pub struct CoSimulation {
+    reader: Box<dyn BufRead>,
+    writer: BufWriter<Box<dyn Write>>,
+    child: Option<Child>,
+    step: u64,
+}
+
+impl CoSimulation {
+    // runs `command` through the shell
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut child = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("failed to start `{}`: {}", command, err))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        Ok(CoSimulation::new(stdout, stdin, Some(child)))
+    }
+
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect(address)
+            .map_err(|err| format!("failed to connect to {}: {}", address, err))?;
+        // every step is a small request waiting on its answer
+        stream.set_nodelay(true).map_err(|err| err.to_string())?;
+        let reader = stream.try_clone().map_err(|err| err.to_string())?;
+        Ok(CoSimulation::new(reader, stream, None))
+    }
+
+    fn new(
+        reader: impl Read + 'static,
+        writer: impl Write + 'static,
+        child: Option<Child>
+    ) -> Self {
+        CoSimulation {
+            reader: Box::new(BufReader::new(reader)),
+            writer: BufWriter::new(Box::new(writer)),
+            child,
+            step: 0,
+        }
+    }
+
+    // sends the state and adds the forces that come back to `Bodies::external_forces`
+    pub fn exchange(&mut self, bodies: &mut Bodies, time: f32, dt: f32) -> Result<(), String> {
+        self.send(bodies, time, dt).map_err(|err| format!("co-simulation: {}", err))?;
+        self.step += 1;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self.reader
+                .read_line(&mut line)
+                .map_err(|err| format!("co-simulation: {}", err))?;
+            if read == 0 {
+                return Err("co-simulation closed the connection".to_string());
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let malformed = || {
+                format!("co-simulation: expected `<id> <fx> <fy>` or `end`, got `{}`", line.trim())
+            };
+            match words[..] {
+                ["end"] => {
+                    return Ok(());
+                }
+                [id, fx, fy] => {
+                    let parsed = (id.parse::<u64>(), fx.parse(), fy.parse());
+                    let (Ok(id), Ok(fx), Ok(fy)) = parsed else {
+                        return Err(malformed());
+                    };
+                    let index = bodies.ids
+                        .iter()
+                        .position(|&other| other == id)
+                        .ok_or_else(|| format!("co-simulation: no body with id {}", id))?;
+                    bodies.external_forces[index] += vec2(fx, fy);
+                }
+                [] => {}
+                _ => {
+                    return Err(malformed());
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, bodies: &Bodies, time: f32, dt: f32) -> std::io::Result<()> {
+        writeln!(self.writer, "step {} {} {} {}", self.step, time, dt, bodies.len())?;
+        for body in bodies.iter() {
+            writeln!(
+                self.writer,
+                "{} {} {} {} {} {}",
+                body.id,
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+                body.mass
+            )?;
+        }
+        self.writer.flush()
+    }
+}
+
+impl Drop for CoSimulation {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::screenshot;
+
+// what an unattended run leaves behind, told apart by the extension
+#[derive(Clone, PartialEq, Debug)]
+pub enum Artifact {
+    // every body, like `export csv`
+    Bodies(String),
+    Screenshot(String),
+}
+
+impl Artifact {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        match path.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("csv") => Ok(Artifact::Bodies(path.to_string())),
+            Some("png") => Ok(Artifact::Screenshot(path.to_string())),
+            _ => Err(format!("don't know what to write to `{}`, it takes .csv or .png", path)),
+        }
+    }
+}
+
+// `--run-for <duration> [--then <file>,<file>...]`: wall clock time after which the run
+// writes its artifacts and quits, for screensavers and lab machines nobody is watching
+pub struct Deadline {
+    seconds: f64,
+    started: f64,
+    pub artifacts: Vec<Artifact>,
+}
+
+impl Deadline {
+    // `90`, `90s`, `10m` or `2h`
+    pub fn parse(duration: &str) -> Result<Self, String> {
+        let (number, unit) = match duration.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(split) => duration.split_at(split),
+            None => (duration, "s"),
+        };
+        let scale = match unit {
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => {
+                return Err(format!("unknown unit in `{}`, use s, m or h", duration));
+            }
+        };
+        match number.parse::<f64>() {
+            Ok(number) if number > 0.0 => {
+                Ok(Deadline {
+                    seconds: number * scale,
+                    started: date::now(),
+                    artifacts: Vec::new(),
+                })
+            }
+            _ => Err(format!("invalid duration `{}`", duration)),
+        }
+    }
+
+    // comma separated
+    pub fn then(&mut self, paths: &str) -> Result<(), String> {
+        for path in paths.split(',').map(str::trim).filter(|path| !path.is_empty()) {
+            self.artifacts.push(Artifact::parse(path)?);
+        }
+        Ok(())
+    }
+
+    // the clock starts over with the first frame, the time to open the window doesn't count
+    pub fn start(&mut self) {
+        self.started = date::now();
+    }
+
+    pub fn is_due(&self) -> bool {
+        date::now() - self.started >= self.seconds
+    }
+
+    // console commands writing the files that don't need the screen
+    pub fn commands(&self) -> Vec<String> {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| {
+                match artifact {
+                    Artifact::Bodies(path) => Some(format!("export csv {}", path)),
+                    Artifact::Screenshot(_) => None,
+                }
+            })
+            .collect()
+    }
+
+    // once the last frame is drawn
+    pub fn take_screenshots(&self) -> Vec<String> {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| {
+                let Artifact::Screenshot(path) = artifact else {
+                    return None;
+                };
+                match screenshot::write(path) {
+                    Ok(()) => Some(format!("saved a screenshot to {}", path)),
+                    Err(err) => Some(err),
+                }
+            })
+            .collect()
+    }
+}
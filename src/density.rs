@@ -0,0 +1,127 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::view::View;
+
+// screen pixels per cell, the texture is stretched over the screen with linear filtering
+pub const CELL_SIZE: f32 = 8.0;
+// sparse cells through dense ones on a log scale, the sparsest still faintly visible
+const SPARSE: Color = Color::new(0.1, 0.05, 0.4, 0.35);
+const MIDDLE: Color = Color::new(0.85, 0.2, 0.5, 0.8);
+const DENSE: Color = Color::new(1.0, 0.95, 0.6, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderMode {
+    Bodies,
+    // the density map with the bodies on top
+    DensityUnder,
+    // only the density map, with selected bodies and black holes drawn so they can be found
+    Density,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Bodies => RenderMode::DensityUnder,
+            RenderMode::DensityUnder => RenderMode::Density,
+            RenderMode::Density => RenderMode::Bodies,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Bodies => "bodies",
+            RenderMode::DensityUnder => "density under bodies",
+            RenderMode::Density => "density",
+        }
+    }
+
+    pub fn shows_density(self) -> bool {
+        self != RenderMode::Bodies
+    }
+}
+
+// how many bodies are in each cell of a grid over the screen, drawn as one texture. With
+// thousands of bodies piled on each other the circles only tell where there are some
+#[derive(Default)]
+pub struct DensityMap {
+    counts: Vec<u32>,
+    // the most bodies in a cell last frame, the dense end of the scale
+    max: u32,
+    image: Option<Image>,
+    texture: Option<Texture2D>,
+}
+
+impl DensityMap {
+    pub fn draw(&mut self, bodies: &Bodies, view: &View) {
+        let columns = (screen_width() / CELL_SIZE).ceil().max(1.0) as usize;
+        let rows = (screen_height() / CELL_SIZE).ceil().max(1.0) as usize;
+        // binned where they are drawn, so the map follows the camera and the frame
+        self.counts.clear();
+        self.counts.resize(columns * rows, 0);
+        for index in 0..bodies.len() {
+            let (position, _) = view.project(&bodies.body(index));
+            let (column, row) = (position.x / CELL_SIZE, position.y / CELL_SIZE);
+            if column < 0.0 || row < 0.0 || column >= columns as f32 || row >= rows as f32 {
+                continue;
+            }
+            self.counts[(row as usize) * columns + (column as usize)] += 1;
+        }
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        self.max = max;
+        if max == 0 {
+            return;
+        }
+
+        // a new image and texture only when the window changes size
+        let resized = self.image
+            .as_ref()
+            .is_none_or(|image| (image.width(), image.height()) != (columns, rows));
+        if resized {
+            self.image = Some(Image::gen_image_color(columns as u16, rows as u16, BLANK));
+            self.texture = None;
+        }
+        let image = self.image.as_mut().unwrap();
+        for (index, &count) in self.counts.iter().enumerate() {
+            let color = if count == 0 {
+                BLANK
+            } else {
+                shade(((count as f32).ln_1p() / (max as f32).ln_1p()).clamp(0.0, 1.0))
+            };
+            image.set_pixel((index % columns) as u32, (index / columns) as u32, color);
+        }
+        let texture = self.texture.get_or_insert_with(|| {
+            let texture = Texture2D::from_image(image);
+            texture.set_filter(FilterMode::Linear);
+            texture
+        });
+        texture.update(image);
+
+        let params = DrawTextureParams {
+            dest_size: Some(vec2((columns as f32) * CELL_SIZE, (rows as f32) * CELL_SIZE)),
+            ..Default::default()
+        };
+        draw_texture_ex(texture, 0.0, 0.0, WHITE, params);
+    }
+
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+}
+
+// `density` from 0 for a single body to 1 for the densest cell, on the log scale
+pub fn shade(density: f32) -> Color {
+    let lerp = |a: Color, b: Color, t: f32| {
+        Color::new(
+            a.r + (b.r - a.r) * t,
+            a.g + (b.g - a.g) * t,
+            a.b + (b.b - a.b) * t,
+            a.a + (b.a - a.a) * t
+        )
+    };
+    if density < 0.5 {
+        lerp(SPARSE, MIDDLE, density * 2.0)
+    } else {
+        lerp(MIDDLE, DENSE, (density - 0.5) * 2.0)
+    }
+}
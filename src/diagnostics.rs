@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::numbers::exact;
+use crate::provenance::Provenance;
+
+const MAX_SAMPLES: usize = 100_000;
+
+pub fn kinetic_energy(bodies: &Bodies) -> f64 {
+    bodies.masses
+        .iter()
+        .zip(&bodies.velocities)
+        .map(|(&mass, velocity)| 0.5 * (mass as f64) * (velocity.length_squared() as f64))
+        .sum()
+}
+
+pub fn potential_energy(bodies: &Bodies, gravity: f32) -> f64 {
+    let mut energy = 0.0;
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            energy += pair_potential(bodies, i, j, gravity);
+        }
+    }
+    energy
+}
+
+// -G m1 m2 / r, continued linearly inside contact distance to match `solver::pairwise`
+// rather than diving towards minus infinity for overlapping bodies
+pub fn pair_potential(bodies: &Bodies, i: usize, j: usize, gravity: f32) -> f64 {
+    let (positions, masses, radii) = (&bodies.positions, &bodies.masses, &bodies.radii);
+    let distance = positions[i].distance(positions[j]) as f64;
+    let contact = (radii[i] + radii[j]) as f64;
+    let strength = (gravity as f64) * (masses[i] as f64) * (masses[j] as f64);
+    if distance >= contact {
+        -strength / distance
+    } else {
+        (strength * (distance - 2.0 * contact)) / contact.powi(2)
+    }
+}
+
+pub fn total_mass(bodies: &Bodies) -> f32 {
+    bodies.masses.iter().sum()
+}
+
+pub fn center_of_mass(bodies: &Bodies) -> Vec2 {
+    let mass = total_mass(bodies);
+    if mass == 0.0 {
+        return Vec2::ZERO;
+    }
+    bodies.positions
+        .iter()
+        .zip(&bodies.masses)
+        .fold(Vec2::ZERO, |sum, (&position, &mass)| sum + position * mass) / mass
+}
+
+pub fn linear_momentum(bodies: &Bodies) -> Vec2 {
+    bodies.velocities
+        .iter()
+        .zip(&bodies.masses)
+        .fold(Vec2::ZERO, |sum, (&velocity, &mass)| sum + velocity * mass)
+}
+
+// z component of the angular momentum about the center of mass
+pub fn angular_momentum(bodies: &Bodies) -> f32 {
+    let center = center_of_mass(bodies);
+    let mass = total_mass(bodies);
+    let center_velocity = if mass == 0.0 { Vec2::ZERO } else { linear_momentum(bodies) / mass };
+
+    bodies
+        .iter()
+        .map(|body| (body.position - center).perp_dot(body.velocity - center_velocity) * body.mass)
+        .sum()
+}
+
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub time: f32,
+    pub kinetic: f64,
+    pub potential: f64,
+    pub momentum: Vec2,
+    pub angular_momentum: f32,
+}
+
+#[derive(Default)]
+pub struct DiagnosticsLog {
+    pub samples: VecDeque<Sample>,
+}
+
+impl DiagnosticsLog {
+    pub fn record(&mut self, sample: Sample) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn export_csv(&self, path: &str, provenance: &Provenance) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        provenance.write_header(&mut file)?;
+        writeln!(file, "time,kinetic,potential,total,momentum_x,momentum_y,angular_momentum")?;
+        for sample in &self.samples {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{}",
+                exact(sample.time),
+                exact(sample.kinetic),
+                exact(sample.potential),
+                exact(sample.kinetic + sample.potential),
+                exact(sample.momentum.x),
+                exact(sample.momentum.y),
+                exact(sample.angular_momentum)
+            )?;
+        }
+        file.flush()
+    }
+}
+
+#[derive(Default)]
+pub struct EnergyTracker {
+    pub kinetic: f64,
+    pub potential: f64,
+    initial: Option<f64>,
+    body_count: usize,
+    gravity: f32,
+}
+
+impl EnergyTracker {
+    pub fn update(&mut self, bodies: &Bodies, gravity: f32) {
+        self.kinetic = kinetic_energy(bodies);
+        self.potential = potential_energy(bodies, gravity);
+
+        // spawning or clearing bodies or changing G changes the system, so measure drift again
+        if self.body_count != bodies.len() || self.gravity != gravity {
+            self.body_count = bodies.len();
+            self.gravity = gravity;
+            self.initial = None;
+        }
+        if self.initial.is_none() {
+            self.initial = Some(self.total());
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.kinetic + self.potential
+    }
+
+    pub fn drift_percent(&self) -> f64 {
+        match self.initial {
+            Some(initial) if initial != 0.0 => ((self.total() - initial) / initial.abs()) * 100.0,
+            _ => 0.0,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+const HISTORY_FRAMES: usize = 6;
+
+#[derive(Default)]
+pub struct Drag {
+    history: VecDeque<Vec2>,
+    pub held: Vec<usize>,
+}
+
+impl Drag {
+    pub fn track(&mut self, mouse: Vec2) {
+        if self.history.len() == HISTORY_FRAMES {
+            self.history.pop_front();
+        }
+        self.history.push_back(mouse);
+    }
+
+    pub fn grab(&mut self, index: usize) {
+        if !self.held.contains(&index) {
+            self.held.push(index);
+        }
+    }
+
+    // average mouse velocity over the recent frames, in world units per simulation time
+    pub fn throw_velocity(&self, dt: f32) -> Vec2 {
+        match (self.history.front(), self.history.back()) {
+            (Some(first), Some(last)) if self.history.len() > 1 => {
+                (*last - *first) / (((self.history.len() - 1) as f32) * dt)
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+
+    pub fn release(&mut self, bodies: &mut Bodies, dt: f32, strength: f32) {
+        let velocity = self.throw_velocity(dt) * strength;
+        for index in self.held.drain(..) {
+            if let Some(held) = bodies.velocities.get_mut(index) {
+                *held = velocity;
+            }
+        }
+        self.history.clear();
+    }
+}
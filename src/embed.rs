@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+
+use crate::json;
+
+// commands handed over by the page embedding the simulator, run by the main loop next frame
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// only the browser build has a page to take commands from
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn queue(command: String) {
+    PENDING.lock().unwrap().push(command);
+}
+
+pub fn take_commands() -> Vec<String> {
+    std::mem::take(&mut *PENDING.lock().unwrap())
+}
+
+// tells the page something happened, `fields` are raw JSON values keyed by name. Native
+// builds have no page to tell, the announcements already go to stdout there
+pub fn emit(kind: &str, fields: &[(&str, String)]) {
+    let mut event = format!("{{\"type\":{}", json::quote(kind));
+    for (name, value) in fields {
+        event.push_str(&format!(",{}:{}", json::quote(name), value));
+    }
+    event.push('}');
+    send(&event);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn send(_event: &str) {}
+
+#[cfg(target_arch = "wasm32")]
+fn send(event: &str) {
+    unsafe { web::threebody_event(event.as_ptr(), event.len()) }
+}
+
+// the other half of `web/threebody.js`: the page writes strings into memory it got from
+// `threebody_alloc` and hands them over, ownership moves to Rust with the call
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use crate::scenario;
+
+    extern "C" {
+        pub fn threebody_event(event: *const u8, len: usize);
+    }
+
+    unsafe fn take_string(ptr: *mut u8, len: usize) -> String {
+        let bytes = Vec::from_raw_parts(ptr, len, len);
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // checked against the plugin version by miniquad's loader
+    #[no_mangle]
+    pub extern "C" fn threebody_crate_version() -> u32 {
+        let major: u32 = env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap();
+        let minor: u32 = env!("CARGO_PKG_VERSION_MINOR").parse().unwrap();
+        let patch: u32 = env!("CARGO_PKG_VERSION_PATCH").parse().unwrap();
+        (major << 24) | (minor << 16) | patch
+    }
+
+    #[no_mangle]
+    pub extern "C" fn threebody_alloc(len: usize) -> *mut u8 {
+        let mut bytes = Vec::<u8>::with_capacity(len);
+        let ptr = bytes.as_mut_ptr();
+        std::mem::forget(bytes);
+        ptr
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn threebody_command(ptr: *mut u8, len: usize) {
+        super::queue(take_string(ptr, len));
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn threebody_set_param(ptr: *mut u8, len: usize, value: f32) {
+        super::queue(format!("set {} {}", take_string(ptr, len), value));
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn threebody_scenario(ptr: *mut u8, len: usize) {
+        match scenario::commands(&take_string(ptr, len)) {
+            Ok(commands) => commands.into_iter().for_each(super::queue),
+            Err(err) => super::emit("error", &[("message", crate::json::quote(&err))]),
+        }
+    }
+}
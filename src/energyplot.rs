@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::diagnostics::EnergyTracker;
+use crate::numbers::Numbers;
+
+const WIDTH: f32 = 260.0;
+// kinetic, potential and total on one scale, then the total alone on its own below it
+const PLOT_HEIGHT: f32 = 70.0;
+const TOTAL_HEIGHT: f32 = 36.0;
+const GAP: f32 = 4.0;
+// clear of the announcer's lines along the bottom
+const BOTTOM_MARGIN: f32 = 40.0;
+const BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+const AXIS_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.25);
+const KINETIC_COLOR: Color = Color::new(1.0, 0.5, 0.3, 1.0);
+const POTENTIAL_COLOR: Color = Color::new(0.4, 0.6, 1.0, 1.0);
+const TOTAL_COLOR: Color = WHITE;
+
+#[derive(Clone, Copy)]
+struct Point {
+    // seconds the simulation has been running for, so pauses don't leave gaps
+    clock: f32,
+    kinetic: f64,
+    potential: f64,
+}
+
+impl Point {
+    fn total(&self) -> f64 {
+        self.kinetic + self.potential
+    }
+}
+
+// kinetic, potential and total energy over the last `Settings::energy_window` seconds of
+// running, scrolling to the left. A steady slope in the total is the integrator drifting,
+// a step down is a collision taking energy out
+#[derive(Default)]
+pub struct EnergyPlot {
+    pub visible: bool,
+    points: VecDeque<Point>,
+    clock: f32,
+}
+
+impl EnergyPlot {
+    // every frame the simulation ran, `frame_time` being how long that took
+    pub fn record(&mut self, frame_time: f32, energy: &EnergyTracker, window: f32) {
+        self.clock += frame_time;
+        self.points.push_back(Point {
+            clock: self.clock,
+            kinetic: energy.kinetic,
+            potential: energy.potential,
+        });
+        while self.points.front().is_some_and(|point| point.clock < self.clock - window) {
+            self.points.pop_front();
+        }
+    }
+
+    // bottom middle of the screen
+    pub fn draw(&self, window: f32, numbers: Numbers) {
+        if !self.visible || self.points.len() < 2 {
+            return;
+        }
+        let left = ((screen_width() - WIDTH) / 2.0).round();
+        let total_top = screen_height() - BOTTOM_MARGIN - TOTAL_HEIGHT;
+        let plot_top = total_top - GAP - PLOT_HEIGHT;
+        draw_rectangle(
+            left - GAP,
+            plot_top - 20.0,
+            WIDTH + 2.0 * GAP,
+            PLOT_HEIGHT + TOTAL_HEIGHT + GAP + 20.0 + GAP,
+            BACKGROUND
+        );
+
+        let start = self.clock - window;
+        let xs: Vec<f32> = self.points
+            .iter()
+            .map(|point| left + ((point.clock - start) / window).max(0.0) * WIDTH)
+            .collect();
+        let kinetic: Vec<f64> = self.points.iter().map(|point| point.kinetic).collect();
+        let potential: Vec<f64> = self.points.iter().map(|point| point.potential).collect();
+        let total: Vec<f64> = self.points.iter().map(Point::total).collect();
+
+        let shared = range(kinetic.iter().chain(&potential).chain(&total));
+        let plot = |values: &[f64], range, top, height, color| {
+            draw_series(&xs, values, range, top, height, color);
+        };
+        if shared.0 < 0.0 && shared.1 > 0.0 {
+            let zero = y_of(0.0, shared, plot_top, PLOT_HEIGHT);
+            draw_line(left, zero, left + WIDTH, zero, 1.0, AXIS_COLOR);
+        }
+        plot(&kinetic, shared, plot_top, PLOT_HEIGHT, KINETIC_COLOR);
+        plot(&potential, shared, plot_top, PLOT_HEIGHT, POTENTIAL_COLOR);
+        plot(&total, shared, plot_top, PLOT_HEIGHT, TOTAL_COLOR);
+        draw_line(left, total_top, left + WIDTH, total_top, 1.0, AXIS_COLOR);
+        plot(&total, range(total.iter()), total_top, TOTAL_HEIGHT, TOTAL_COLOR);
+
+        let latest = self.points.back().unwrap();
+        let mut label_x = left;
+        for (text, color) in [
+            (format!("K {}", numbers.format(latest.kinetic, 3)), KINETIC_COLOR),
+            (format!("U {}", numbers.format(latest.potential, 3)), POTENTIAL_COLOR),
+            (format!("E {}", numbers.format(latest.total(), 4)), TOTAL_COLOR),
+        ] {
+            draw_text(&text, label_x, plot_top - 6.0, 16.0, color);
+            label_x += measure_text(&text, None, 16, 1.0).width + 10.0;
+        }
+        // how far the total moved over the window, on its own scale
+        let first = self.points.front().unwrap().total();
+        let change = if first == 0.0 {
+            0.0
+        } else {
+            ((latest.total() - first) / first.abs()) * 100.0
+        };
+        draw_text(
+            format!("E {}% over {}s", numbers.signed(change, 3), window),
+            left,
+            total_top + TOTAL_HEIGHT - 4.0,
+            14.0,
+            GRAY
+        );
+    }
+}
+
+// the lowest and highest of `values`, padded a little. A flat line sits in the middle rather
+// than dividing by zero
+fn range<'a>(values: impl Iterator<Item = &'a f64>) -> (f64, f64) {
+    let (low, high) = values.fold((f64::MAX, f64::MIN), |(low, high), &value| {
+        (low.min(value), high.max(value))
+    });
+    let pad = ((high - low) * 0.05).max(high.abs().max(low.abs()) * 1e-9).max(1e-12);
+    (low - pad, high + pad)
+}
+
+fn y_of(value: f64, (low, high): (f64, f64), top: f32, height: f32) -> f32 {
+    top + height * (1.0 - ((value - low) / (high - low)) as f32)
+}
+
+fn draw_series(xs: &[f32], values: &[f64], range: (f64, f64), top: f32, height: f32, color: Color) {
+    for i in 1..values.len() {
+        let from = y_of(values[i - 1], range, top, height);
+        let to = y_of(values[i], range, top, height);
+        draw_line(xs[i - 1], from, xs[i], to, 1.0, color);
+    }
+}
@@ -0,0 +1,159 @@
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// nodes of the correction table along each axis, over half a cell
+const TABLE_COLUMNS: usize = 49;
+const TABLE_ROWS: usize = 37;
+// images of the cell summed in real space in every direction, and wave vectors summed in
+// reciprocal space. With `SPLITTING` both sums have converged well below f32 precision
+const IMAGES: i32 = 2;
+const WAVES: i32 = 8;
+// in units of one over the shorter side of the cell
+const SPLITTING: f64 = 4.0;
+
+const ERFC_COEFFICIENTS: [f64; 10] = [
+    -1.265_512_23,
+    1.000_023_68,
+    0.374_091_96,
+    0.096_784_18,
+    -0.186_288_06,
+    0.278_868_07,
+    -1.135_203_98,
+    1.488_515_87,
+    -0.822_152_23,
+    0.170_872_77,
+];
+
+static TABLE: OnceLock<Vec<Vec2>> = OnceLock::new();
+
+// gravity on a torus: every body feels every other and all of their images in the copies of
+// the world tiled around it, against a uniform background that keeps the infinite sum finite.
+// The nearest image is summed directly with the usual law, the rest is the Ewald correction,
+// the difference between the full periodic force and the nearest image alone. It depends on
+// nothing but the offset, so it is tabulated once over the cell (as GADGET does) and
+// interpolated. An exact pairwise sum, whatever the solver
+pub fn forces(bodies: &Bodies, gravity: f32) -> Vec<Vec2> {
+    let (positions, masses, radii) = (&bodies.positions, &bodies.masses, &bodies.radii);
+    let mut forces = vec![Vec2::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let offset = nearest_image(positions[j] - positions[i]);
+            let distance = offset.length();
+            if distance == 0.0 {
+                continue;
+            }
+            // levels off inside contact distance like `solver::pairwise`
+            let reach = distance.max(radii[i] + radii[j]);
+            let direct = (offset / distance) / reach.powi(2);
+            let force = (direct + correction(offset)) * (gravity * masses[i] * masses[j]);
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+    forces
+}
+
+// the shortest way from one point to another on the torus
+pub fn nearest_image(offset: Vec2) -> Vec2 {
+    let size = vec2(SCREEN_WIDTH, SCREEN_HEIGHT);
+    offset - size * (offset / size).round()
+}
+
+// whether going from one point to the next jumped across the edge of the torus rather than
+// moving, more than half the world in one go. Paths drawn in a periodic world leave those out
+pub fn crosses_seam(from: Vec2, to: Vec2) -> bool {
+    let step = (to - from).abs();
+    step.x > SCREEN_WIDTH / 2.0 || step.y > SCREEN_HEIGHT / 2.0
+}
+
+// periodic force minus the nearest image's, for unit masses and gravity, on a body whose
+// partner sits at `offset`. The table covers the positive quarter, the correction is odd
+// along each axis
+fn correction(offset: Vec2) -> Vec2 {
+    let table = TABLE.get_or_init(tabulate);
+    let half = vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+    let last = vec2((TABLE_COLUMNS - 1) as f32, (TABLE_ROWS - 1) as f32);
+    let cell = (offset.abs() / half * last).clamp(Vec2::ZERO, last);
+    let column = (cell.x as usize).min(TABLE_COLUMNS - 2);
+    let row = (cell.y as usize).min(TABLE_ROWS - 2);
+    let fraction = cell - vec2(column as f32, row as f32);
+
+    let at = |column: usize, row: usize| table[row * TABLE_COLUMNS + column];
+    let bottom = at(column, row).lerp(at(column + 1, row), fraction.x);
+    let top = at(column, row + 1).lerp(at(column + 1, row + 1), fraction.x);
+    let value = bottom.lerp(top, fraction.y);
+    value * offset.signum()
+}
+
+fn tabulate() -> Vec<Vec2> {
+    let mut table = Vec::with_capacity(TABLE_COLUMNS * TABLE_ROWS);
+    for row in 0..TABLE_ROWS {
+        for column in 0..TABLE_COLUMNS {
+            let x = (column as f64 / (TABLE_COLUMNS - 1) as f64) * (SCREEN_WIDTH as f64) / 2.0;
+            let y = (row as f64 / (TABLE_ROWS - 1) as f64) * (SCREEN_HEIGHT as f64) / 2.0;
+            let (fx, fy) = periodic_force(x, y);
+            let distance = x.hypot(y);
+            let (nx, ny) = if distance > 0.0 {
+                (x / distance.powi(3), y / distance.powi(3))
+            } else {
+                (0.0, 0.0)
+            };
+            table.push(vec2((fx - nx) as f32, (fy - ny) as f32));
+        }
+    }
+    table
+}
+
+// the Ewald sum for a 1/r^2 force between bodies in a plane periodic in both directions
+// (Parry's two dimensional sum at z = 0): a screened sum over the nearby images plus a sum over
+// wave vectors for the smooth remainder
+fn periodic_force(x: f64, y: f64) -> (f64, f64) {
+    let (width, height) = (SCREEN_WIDTH as f64, SCREEN_HEIGHT as f64);
+    let alpha = SPLITTING / width.min(height);
+    let (mut fx, mut fy) = (0.0, 0.0);
+
+    for i in -IMAGES..=IMAGES {
+        for j in -IMAGES..=IMAGES {
+            let (sx, sy) = (x + (i as f64) * width, y + (j as f64) * height);
+            let s = sx.hypot(sy);
+            if s == 0.0 {
+                continue;
+            }
+            let magnitude =
+                erfc(alpha * s) / s.powi(2) +
+                ((2.0 * alpha) / PI.sqrt()) * (-(alpha * s).powi(2)).exp() / s;
+            fx += (magnitude * sx) / s;
+            fy += (magnitude * sy) / s;
+        }
+    }
+
+    let area = width * height;
+    for i in -WAVES..=WAVES {
+        for j in -WAVES..=WAVES {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            let (kx, ky) = ((2.0 * PI * (i as f64)) / width, (2.0 * PI * (j as f64)) / height);
+            let k = kx.hypot(ky);
+            let magnitude =
+                ((2.0 * PI) / area) * (erfc(k / (2.0 * alpha)) / k) * (kx * x + ky * y).sin();
+            fx += magnitude * kx;
+            fy += magnitude * ky;
+        }
+    }
+    (fx, fy)
+}
+
+// complementary error function, Numerical Recipes' Chebyshev fit (relative error below 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = ERFC_COEFFICIENTS.iter().rev().fold(0.0, |sum, &c| sum * t + c);
+    let value = t * (polynomial - z * z).exp();
+    if x >= 0.0 { value } else { 2.0 - value }
+}
@@ -0,0 +1,28 @@
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+use crate::bodies::Bodies;
+use crate::numbers::exact;
+use crate::provenance::Provenance;
+
+pub fn bodies_csv(bodies: &Bodies, path: &str, provenance: &Provenance) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    provenance.write_header(&mut file)?;
+    writeln!(file, "id,x,y,vx,vy,mass,radius,freezed,black_hole")?;
+    for body in bodies.iter() {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{}",
+            body.id,
+            exact(body.position.x),
+            exact(body.position.y),
+            exact(body.velocity.x),
+            exact(body.velocity.y),
+            exact(body.mass),
+            exact(body.radius),
+            body.freezed,
+            body.black_hole
+        )?;
+    }
+    file.flush()
+}
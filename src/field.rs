@@ -0,0 +1,120 @@
+use std::fs;
+use std::io;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::npy;
+use crate::provenance::Provenance;
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// a regular grid of sample points over a rectangle of the world, corners included
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Grid {
+    pub columns: usize,
+    pub rows: usize,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for Grid {
+    // the whole world at a sample every 10 pixels
+    fn default() -> Self {
+        Grid {
+            columns: 81,
+            rows: 61,
+            min: Vec2::ZERO,
+            max: vec2(SCREEN_WIDTH, SCREEN_HEIGHT),
+        }
+    }
+}
+
+impl Grid {
+    pub fn xs(&self) -> Vec<f32> {
+        spaced(self.min.x, self.max.x, self.columns)
+    }
+
+    pub fn ys(&self) -> Vec<f32> {
+        spaced(self.min.y, self.max.y, self.rows)
+    }
+}
+
+// acceleration and potential per unit mass at every grid point, row by row
+pub struct Field {
+    pub grid: Grid,
+    pub ax: Vec<f32>,
+    pub ay: Vec<f32>,
+    pub potential: Vec<f32>,
+}
+
+// the exact sum over all bodies. Inside a body the pull levels off at its surface value and
+// the potential continues linearly, like `solver::pairwise` and `diagnostics`, so sample
+// points that land on a body stay finite
+pub fn sample(bodies: &Bodies, grid: Grid, gravity: f32) -> Field {
+    let size = grid.columns * grid.rows;
+    let mut field = Field {
+        grid,
+        ax: Vec::with_capacity(size),
+        ay: Vec::with_capacity(size),
+        potential: Vec::with_capacity(size),
+    };
+    for y in grid.ys() {
+        for x in grid.xs() {
+            let point = vec2(x, y);
+            let mut acceleration = Vec2::ZERO;
+            let mut potential = 0.0;
+            for j in 0..bodies.len() {
+                let offset = bodies.positions[j] - point;
+                let distance = offset.length();
+                let (mass, radius) = (bodies.masses[j], bodies.radii[j].max(f32::EPSILON));
+                if distance >= radius {
+                    acceleration += offset * (gravity * mass / distance.powi(3));
+                    potential -= gravity * mass / distance;
+                } else {
+                    if distance > 0.0 {
+                        acceleration += (offset / distance) * (gravity * mass / radius.powi(2));
+                    }
+                    potential += (gravity * mass * (distance - 2.0 * radius)) / radius.powi(2);
+                }
+            }
+            field.ax.push(acceleration.x);
+            field.ay.push(acceleration.y);
+            field.potential.push(potential);
+        }
+    }
+    field
+}
+
+// `.npz` gets the coordinates `x` and `y`, the `ax`, `ay` and `potential` grids indexed
+// [row, column] and the provenance as text. Anything else gets a single `.npy` array
+// stacked as [ax, ay, potential], as the format has no room for more
+pub fn write(field: &Field, path: &str, provenance: &Provenance) -> io::Result<()> {
+    let Grid { columns, rows, .. } = field.grid;
+    if !path.ends_with(".npz") {
+        let stacked: Vec<f32> = [&field.ax, &field.ay, &field.potential]
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        return fs::write(path, npy::encode(&[3, rows, columns], &stacked));
+    }
+
+    let mut header = Vec::new();
+    provenance.write_header(&mut header)?;
+    let files = [
+        ("x.npy".to_string(), npy::encode(&[columns], &field.grid.xs())),
+        ("y.npy".to_string(), npy::encode(&[rows], &field.grid.ys())),
+        ("ax.npy".to_string(), npy::encode(&[rows, columns], &field.ax)),
+        ("ay.npy".to_string(), npy::encode(&[rows, columns], &field.ay)),
+        ("potential.npy".to_string(), npy::encode(&[rows, columns], &field.potential)),
+        ("provenance.txt".to_string(), header),
+    ];
+    fs::write(path, npy::zip(&files))
+}
+
+fn spaced(from: f32, to: f32, count: usize) -> Vec<f32> {
+    if count == 1 {
+        return vec![from];
+    }
+    (0..count).map(|i| from + ((to - from) * (i as f32)) / ((count - 1) as f32)).collect()
+}
@@ -0,0 +1,331 @@
+use macroquad::prelude::*;
+
+use crate::quadtree::QuadTree;
+use crate::bodies::Bodies;
+
+// the highest local expansion order there is: the field and its derivatives up to this order
+pub const MAX_ORDER: usize = 4;
+// about what Barnes-Hut costs at the same theta, with a tenth of its error
+pub const DEFAULT_ORDER: usize = 2;
+// highest total degree any expansion gets to, see `forces`
+const MAX_DEGREE: usize = MAX_ORDER + 2;
+const TERMS: usize = index(0, MAX_DEGREE + 1);
+
+// coefficients of a polynomial in two variables, the term x^i y^j at `index(i, j)`.
+// Multipoles hold the moments of a cell's mass about its center of mass, locals the
+// potential around a cell's center
+type Expansion = [f64; TERMS];
+
+// terms are stored degree by degree, x^i y^j at `index(i, j)`
+const fn index(i: usize, j: usize) -> usize {
+    let degree = i + j;
+    (degree * (degree + 1)) / 2 + j
+}
+
+// Pascal's triangle far enough for the shifts between expansions
+const BINOMIALS: [[f64; 2 * MAX_DEGREE + 1]; 2 * MAX_DEGREE + 1] = {
+    let mut table = [[0.0; 2 * MAX_DEGREE + 1]; 2 * MAX_DEGREE + 1];
+    let mut n = 0;
+    while n <= 2 * MAX_DEGREE {
+        table[n][0] = 1.0;
+        let mut k = 1;
+        while k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+};
+
+fn binomial(n: usize, k: usize) -> f64 {
+    BINOMIALS[n][k]
+}
+
+fn powers(value: f64, degree: usize) -> [f64; MAX_DEGREE + 1] {
+    let mut powers = [1.0; MAX_DEGREE + 1];
+    for k in 1..=degree {
+        powers[k] = powers[k - 1] * value;
+    }
+    powers
+}
+
+// the Taylor coefficients of 1/r at `offset`, d^(i+j) / dx^i dy^j (1 / r) / (i! j!), from
+// the recurrence Lindsay and Krasny (2001) give for them
+fn derivatives(offset: Vec2, degree: usize) -> Expansion {
+    let (x, y) = (offset.x as f64, offset.y as f64);
+    let inverse_r2 = 1.0 / (x * x + y * y);
+    let mut a = [0.0; TERMS];
+    a[0] = inverse_r2.sqrt();
+    for n in 1..=degree {
+        let (first, second) = (2.0 - 1.0 / (n as f64), 1.0 - 1.0 / (n as f64));
+        for j in 0..=n {
+            let i = n - j;
+            let mut sum = 0.0;
+            if i >= 1 {
+                sum -= first * x * a[index(i - 1, j)];
+            }
+            if j >= 1 {
+                sum -= first * y * a[index(i, j - 1)];
+            }
+            if i >= 2 {
+                sum -= second * a[index(i - 2, j)];
+            }
+            if j >= 2 {
+                sum -= second * a[index(i, j - 2)];
+            }
+            a[index(i, j)] = sum * inverse_r2;
+        }
+    }
+    a
+}
+
+struct Fmm<'a> {
+    tree: QuadTree,
+    bodies: &'a Bodies,
+    theta: f32,
+    // degree of the local expansions of the potential, one more than the field's order
+    local_degree: usize,
+    // highest total degree of the terms kept between a multipole and a local
+    degree: usize,
+    gravity: f32,
+    // the products `multipole_to_local` sums, worked out once for the order
+    translation: Vec<Term>,
+    multipoles: Vec<Expansion>,
+    locals: Vec<Expansion>,
+    forces: Vec<Vec2>,
+}
+
+// one product in a multipole to local translation: `factor` times the derivative of 1/r at
+// `derivative` times the moment at `moment` goes into the local's term at `local`
+struct Term {
+    local: usize,
+    derivative: usize,
+    moment: usize,
+    factor: f64,
+}
+
+// the potential -G sum m / |x - s| around the target's center, from the Taylor series of 1/r
+// in both the target's and the source's offsets, keeping terms up to `degree` in all. The
+// constant term is left out as only the gradient is wanted, and so are the first moments,
+// which vanish about the center of mass
+fn translation(local_degree: usize, degree: usize) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for n in 1..=local_degree {
+        for j in 0..=n {
+            let i = n - j;
+            for m in (0..=degree - n).filter(|&m| m != 1) {
+                for l in 0..=m {
+                    let k = m - l;
+                    let sign = if m % 2 == 0 { 1.0 } else { -1.0 };
+                    terms.push(Term {
+                        local: index(i, j),
+                        derivative: index(i + k, j + l),
+                        moment: index(k, l),
+                        factor: sign * binomial(i + k, i) * binomial(j + l, j),
+                    });
+                }
+            }
+        }
+    }
+    terms
+}
+
+// dual tree walk: cell pairs that are far apart interact through their expansions,
+// everything else falls back to the exact pairwise force. Order 0 keeps only the field at
+// each cell center, every order above adds the next derivative of it across the cell, and
+// the cells' multipoles are kept one order further. Cartesian expansions of the potential,
+// as the force here falls off with the square of the distance like in three dimensions
+pub fn forces(
+    bodies: &Bodies,
+    theta: f32,
+    order: usize,
+    leaf_capacity: usize,
+    gravity: f32
+) -> Vec<Vec2> {
+    let tree = QuadTree::build(bodies, leaf_capacity);
+    let order = order.min(MAX_ORDER);
+    let (local_degree, degree) = (order + 1, order + 2);
+    let mut fmm = Fmm {
+        multipoles: vec![[0.0; TERMS]; tree.nodes.len()],
+        locals: vec![[0.0; TERMS]; tree.nodes.len()],
+        translation: translation(local_degree, degree),
+        forces: vec![Vec2::ZERO; bodies.len()],
+        tree,
+        bodies,
+        theta,
+        local_degree,
+        degree,
+        gravity,
+    };
+
+    if !fmm.tree.nodes.is_empty() {
+        fmm.gather();
+        fmm.interact(0, 0);
+        fmm.evaluate();
+    }
+    fmm.forces
+}
+
+impl Fmm<'_> {
+    // the moments of every cell about its center of mass, leaves from their bodies and the
+    // rest from their children's, children before parents
+    fn gather(&mut self) {
+        let degree = self.degree;
+        for cell in (0..self.tree.nodes.len()).rev() {
+            let node = &self.tree.nodes[cell];
+            let mut multipole = [0.0; TERMS];
+            let mut add = |offset: Vec2, moments: &Expansion, moments_degree: usize| {
+                let (xs, ys) = (powers(offset.x as f64, degree), powers(offset.y as f64, degree));
+                for n in 0..=degree {
+                    for j in 0..=n {
+                        let i = n - j;
+                        let mut sum = 0.0;
+                        for l in 0..=j.min(moments_degree) {
+                            for k in 0..=i.min(moments_degree - l) {
+                                let shift = binomial(i, k) * binomial(j, l);
+                                sum += shift * moments[index(k, l)] * xs[i - k] * ys[j - l];
+                            }
+                        }
+                        multipole[index(i, j)] += sum;
+                    }
+                }
+            };
+            for &i in &node.bodies {
+                // a body only has the zeroth moment, its mass
+                let mut point = [0.0; TERMS];
+                point[0] = self.bodies.masses[i] as f64;
+                add(self.bodies.positions[i] - node.center_of_mass, &point, 0);
+            }
+            for &child in &node.children {
+                let offset = self.tree.nodes[child].center_of_mass - node.center_of_mass;
+                add(offset, &self.multipoles[child], degree);
+            }
+            self.multipoles[cell] = multipole;
+        }
+    }
+
+    fn interact(&mut self, target: usize, source: usize) {
+        let (target_leaf, source_leaf) = (
+            self.tree.nodes[target].is_leaf(),
+            self.tree.nodes[source].is_leaf(),
+        );
+
+        if target == source {
+            if target_leaf {
+                self.direct(target, source);
+            } else {
+                let children = self.tree.nodes[target].children.clone();
+                for &a in &children {
+                    for &b in &children {
+                        self.interact(a, b);
+                    }
+                }
+            }
+            return;
+        }
+
+        if self.well_separated(target, source) {
+            self.multipole_to_local(target, source);
+            return;
+        }
+
+        if target_leaf && source_leaf {
+            self.direct(target, source);
+        } else if
+            source_leaf ||
+            (!target_leaf &&
+                self.tree.nodes[target].half_size >= self.tree.nodes[source].half_size)
+        {
+            let children = self.tree.nodes[target].children.clone();
+            for child in children {
+                self.interact(child, source);
+            }
+        } else {
+            let children = self.tree.nodes[source].children.clone();
+            for child in children {
+                self.interact(target, child);
+            }
+        }
+    }
+
+    fn well_separated(&self, target: usize, source: usize) -> bool {
+        let target = &self.tree.nodes[target];
+        let source = &self.tree.nodes[source];
+        let distance = target.center.distance(source.center_of_mass);
+        distance > 0.0 && (2.0 * (target.half_size + source.half_size)) / distance < self.theta
+    }
+
+    fn multipole_to_local(&mut self, target: usize, source: usize) {
+        let offset = self.tree.nodes[target].center - self.tree.nodes[source].center_of_mass;
+        let a = derivatives(offset, self.degree);
+        let moments = &self.multipoles[source];
+        let local = &mut self.locals[target];
+        let gravity = self.gravity as f64;
+        for term in &self.translation {
+            local[term.local] -= gravity * term.factor * a[term.derivative] * moments[term.moment];
+        }
+    }
+
+    fn direct(&mut self, target: usize, source: usize) {
+        for &i in &self.tree.nodes[target].bodies {
+            for &j in &self.tree.nodes[source].bodies {
+                if i != j {
+                    self.forces[i] += self.bodies.force_between(i, j, self.gravity);
+                }
+            }
+        }
+    }
+
+    // pushes expansions down the tree, re-centered on each child, and evaluates them at the
+    // bodies: the field is minus the gradient of the potential
+    fn evaluate(&mut self) {
+        let degree = self.local_degree;
+        for cell in 0..self.tree.nodes.len() {
+            let node = &self.tree.nodes[cell];
+            let local = self.locals[cell];
+
+            for &child in &node.children {
+                let offset = self.tree.nodes[child].center - node.center;
+                let (xs, ys) = (powers(offset.x as f64, degree), powers(offset.y as f64, degree));
+                let shifted = &mut self.locals[child];
+                for n in 0..=degree {
+                    for j in 0..=n {
+                        let i = n - j;
+                        let coefficient = local[index(i, j)];
+                        if coefficient == 0.0 {
+                            continue;
+                        }
+                        for l in 0..=j {
+                            for k in 0..=i {
+                                let shift = binomial(i, k) * binomial(j, l);
+                                shifted[index(k, l)] +=
+                                    coefficient * shift * xs[i - k] * ys[j - l];
+                            }
+                        }
+                    }
+                }
+            }
+
+            for &i in &node.bodies {
+                let offset = self.bodies.positions[i] - node.center;
+                let (xs, ys) = (powers(offset.x as f64, degree), powers(offset.y as f64, degree));
+                let mut field = (0.0, 0.0);
+                for n in 1..=degree {
+                    for j in 0..=n {
+                        let i = n - j;
+                        let coefficient = local[index(i, j)];
+                        if i >= 1 {
+                            field.0 -= coefficient * (i as f64) * xs[i - 1] * ys[j];
+                        }
+                        if j >= 1 {
+                            field.1 -= coefficient * (j as f64) * xs[i] * ys[j - 1];
+                        }
+                    }
+                }
+                let field = vec2(field.0 as f32, field.1 as f32);
+                self.forces[i] += field * self.bodies.masses[i];
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::metadata::Metadata;
+use crate::settings::Settings;
+use crate::view::{ draw_arrow, View };
+
+// the force arrows get this many pixels per unit of force, but never reach past the middle
+// of the pair so the two stay apart
+const ARROW_SCALE: f32 = 200.0;
+const ARROW_REACH: f32 = 0.45;
+const BOX_WIDTH: f32 = 380.0;
+
+// Newton's law of gravitation for the first two selected bodies, with this frame's numbers
+// in it, and the pull on each of them drawn as equal and opposite arrows
+#[derive(Default)]
+pub struct FormulaOverlay {
+    pub visible: bool,
+}
+
+impl FormulaOverlay {
+    pub fn draw(&self, bodies: &Bodies, metadata: &Metadata, settings: &Settings, view: &View) {
+        if !self.visible {
+            return;
+        }
+        let mut selected = (0..bodies.len()).filter(|&index| bodies.selected[index]);
+        let (Some(a), Some(b)) = (selected.next(), selected.next()) else {
+            draw_lines(&["select two bodies (shift-click) to see the force between them"]);
+            return;
+        };
+
+        let (m1, m2) = (bodies.masses[a], bodies.masses[b]);
+        let r = bodies.positions[a].distance(bodies.positions[b]);
+        let force = if r > 0.0 { (settings.gravity * m1 * m2) / r.powi(2) } else { 0.0 };
+        let numbers = settings.numbers;
+        let mut lines = vec![
+            format!("{} and {}", metadata.label(bodies.ids[a]), metadata.label(bodies.ids[b])),
+            "F = G m1 m2 / r^2".to_string(),
+            format!(
+                "  = {} * {} * {} / {}^2",
+                numbers.format(settings.gravity, 2),
+                numbers.format(m1, 2),
+                numbers.format(m2, 2),
+                numbers.format(r, 1)
+            ),
+            format!("  = {}", numbers.format(force, 4)),
+            format!(
+                "a1 = F / m1 = {}   a2 = F / m2 = {}",
+                numbers.format(force / m1, 4),
+                numbers.format(force / m2, 4)
+            ),
+        ];
+        // what the simulation does differently from the textbook. The cutoff is per body, so
+        // inside twice the radius of just one of them only that one stops feeling the pull
+        let pull = |i: usize, j: usize| {
+            if settings.hacks.force_cutoff {
+                bodies.force_between(i, j, settings.gravity).length()
+            } else {
+                force
+            }
+        };
+        let (on_a, on_b) = (pull(a, b), pull(b, a));
+        if on_a == 0.0 || on_b == 0.0 {
+            lines.push("this close the simulation switches the pull off".to_string());
+        }
+        if settings.periodic {
+            lines.push("the periodic world adds the pull of every image".to_string());
+        }
+        if settings.relativity {
+            lines.push("relativity adds a correction on top".to_string());
+        }
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        draw_lines(&lines);
+
+        let from = view.world_to_screen(bodies.positions[a]);
+        let to = view.world_to_screen(bodies.positions[b]);
+        let reach = from.distance(to) * ARROW_REACH;
+        let direction = (to - from).normalize_or_zero();
+        if on_a > 0.0 {
+            draw_arrow(from, from + direction * (on_a * ARROW_SCALE).min(reach), 2.0, ORANGE);
+        }
+        if on_b > 0.0 {
+            draw_arrow(to, to - direction * (on_b * ARROW_SCALE).min(reach), 2.0, ORANGE);
+        }
+    }
+}
+
+// bottom left, clear of the status lines at the top
+fn draw_lines(lines: &[&str]) {
+    let height = 20.0 * (lines.len() as f32) + 10.0;
+    let (x, mut y) = (20.0, screen_height() - height - 10.0);
+    draw_rectangle(x - 10.0, y, BOX_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.6));
+    for line in lines {
+        y += 20.0;
+        draw_text(line, x, y, 20.0, WHITE);
+    }
+}
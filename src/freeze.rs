@@ -0,0 +1,146 @@
+use std::f32::consts::{ FRAC_1_SQRT_2, PI };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::metadata::{ Metadata, Value };
+
+#[derive(Clone)]
+pub struct FreezeGroup {
+    pub name: String,
+    pub release_at: Option<f32>,
+}
+
+#[derive(Clone, Default)]
+pub struct FreezeGroups {
+    pub groups: Vec<Option<FreezeGroup>>,
+}
+
+impl FreezeGroups {
+    // freezes every selected body into a new group, `group N` unless it's given a name, and
+    // returns its index. Members are tagged with the group's name. A body already in a group
+    // leaves it, and a group left without members goes
+    pub fn freeze_selection(
+        &mut self,
+        bodies: &mut Bodies,
+        metadata: &mut Metadata,
+        name: Option<&str>,
+        release_at: Option<f32>
+    ) -> Option<usize> {
+        if !bodies.selected.contains(&true) {
+            return None;
+        }
+
+        let index = self.groups.iter().position(|group| group.is_none()).unwrap_or(self.groups.len());
+        let group = FreezeGroup {
+            name: name.map_or_else(|| format!("group {}", index + 1), str::to_string),
+            release_at,
+        };
+        let tag = Value::Text(group.name.clone());
+        if index == self.groups.len() {
+            self.groups.push(Some(group));
+        } else {
+            self.groups[index] = Some(group);
+        }
+
+        let mut left = Vec::new();
+        for i in 0..bodies.len() {
+            if bodies.selected[i] {
+                left.extend(bodies.freeze_groups[i]);
+                metadata.set(bodies.ids[i], "freeze_group", tag.clone());
+                bodies.freezed[i] = true;
+                bodies.freeze_groups[i] = Some(index);
+                bodies.selected[i] = false;
+            }
+        }
+        for old in left {
+            if old != index && self.count_members(old, bodies) == 0 {
+                self.groups[old] = None;
+            }
+        }
+
+        Some(index)
+    }
+
+    // the index of the group called `name`
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.groups
+            .iter()
+            .position(|group| group.as_ref().is_some_and(|group| group.name == name))
+    }
+
+    // returns whether there was a group to release. Its members lose their tag
+    pub fn release(&mut self, index: usize, bodies: &mut Bodies, metadata: &mut Metadata) -> bool {
+        if !matches!(self.groups.get(index), Some(Some(_))) {
+            return false;
+        }
+        self.groups[index] = None;
+
+        for i in 0..bodies.len() {
+            if bodies.freeze_groups[i] == Some(index) {
+                bodies.freezed[i] = false;
+                bodies.freeze_groups[i] = None;
+                metadata.remove(bodies.ids[i], "freeze_group");
+            }
+        }
+        true
+    }
+
+    pub fn release_all(&mut self, bodies: &mut Bodies, metadata: &mut Metadata) {
+        for index in 0..self.groups.len() {
+            self.release(index, bodies, metadata);
+        }
+    }
+
+    // releases groups whose trigger time has passed
+    pub fn release_due(&mut self, time: f32, bodies: &mut Bodies, metadata: &mut Metadata) {
+        for index in 0..self.groups.len() {
+            let due = self.groups[index]
+                .as_ref()
+                .and_then(|group| group.release_at)
+                .is_some_and(|release_at| time >= release_at);
+            if due {
+                self.release(index, bodies, metadata);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+
+    pub fn count_members(&self, index: usize, bodies: &Bodies) -> usize {
+        bodies.freeze_groups
+            .iter()
+            .filter(|&&group| group == Some(index))
+            .count()
+    }
+}
+
+// frozen bodies are drawn in this instead of their color, hatched and with a snowflake
+pub const FROZEN_COLOR: Color = Color::new(0.55, 0.8, 1.0, 1.0);
+const HATCH_COLOR: Color = Color::new(0.55, 0.8, 1.0, 0.6);
+// bodies smaller than this on screen get just the snowflake
+const MIN_HATCHED_RADIUS: f32 = 4.0;
+
+// diagonal hatching across the body and a snowflake off its top right, over the body
+pub fn draw_marker(position: Vec2, radius: f32) {
+    if radius >= MIN_HATCHED_RADIUS {
+        // chords at right angles to the diagonal, a third of the radius apart
+        let along = vec2(1.0, -1.0).normalize();
+        let across = along.perp();
+        for k in -2..=2 {
+            let offset = (k as f32) * radius / 3.0;
+            let half = (radius * radius - offset * offset).max(0.0).sqrt() * 0.9;
+            let middle = position + across * offset;
+            let (from, to) = (middle - along * half, middle + along * half);
+            draw_line(from.x, from.y, to.x, to.y, 1.0, HATCH_COLOR);
+        }
+    }
+    let icon = position + vec2(radius, -radius) * FRAC_1_SQRT_2 + vec2(4.0, -4.0);
+    for spoke in 0..3 {
+        let direction = Vec2::from_angle((spoke as f32) * PI / 3.0) * 4.0;
+        let (from, to) = (icon - direction, icon + direction);
+        draw_line(from.x, from.y, to.x, to.y, 1.0, FROZEN_COLOR);
+    }
+}
@@ -0,0 +1,374 @@
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::json::{ self, Json };
+use crate::npy;
+use crate::scenario;
+
+const DIRECTORY: &str = "gallery";
+const INDEX: &str = "index.json";
+const THUMBNAIL_WIDTH: u32 = 160;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    // a `.json` scenario, see `scenario`
+    Scenario,
+    // a `.tbr` recording, see `recording`
+    Recording,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 2] = [Kind::Scenario, Kind::Recording];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Scenario => "scenario",
+            Kind::Recording => "recording",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Kind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Kind::Scenario => "json",
+            Kind::Recording => "tbr",
+        }
+    }
+}
+
+pub struct Entry {
+    pub name: String,
+    pub kind: Kind,
+    pub description: String,
+    pub tags: Vec<String>,
+    // seconds since the epoch
+    pub added: u64,
+}
+
+impl Entry {
+    fn file(&self) -> String {
+        format!("{}.{}", self.name, self.kind.extension())
+    }
+
+    fn thumbnail(&self) -> String {
+        format!("{}.png", self.name)
+    }
+
+    // every word somewhere in the name, description or tags, ignoring case
+    fn matches(&self, words: &[String]) -> bool {
+        let text = format!("{} {} {}", self.name, self.description, self.tags.join(" "))
+            .to_lowercase();
+        words.iter().all(|word| text.contains(&word.to_lowercase()))
+    }
+
+    fn to_json(&self) -> String {
+        let tags: Vec<String> = self.tags
+            .iter()
+            .map(|tag| json::quote(tag))
+            .collect();
+        format!(
+            "{{ \"name\": {}, \"kind\": {}, \"description\": {}, \"tags\": [{}], \"added\": {} }}",
+            json::quote(&self.name),
+            json::quote(self.kind.name()),
+            json::quote(&self.description),
+            tags.join(", "),
+            self.added
+        )
+    }
+
+    fn from_json(value: &Json) -> Result<Entry, String> {
+        let text = |key: &str| {
+            value
+                .get(key)
+                .and_then(Json::as_str)
+                .ok_or(format!("every gallery entry needs a `{}` string", key))
+        };
+        let name = check_name(text("name")?)?;
+        let kind = Kind::parse(text("kind")?).ok_or("unknown kind of gallery entry")?;
+        let tags = match value.get("tags").and_then(Json::as_array) {
+            Some(tags) => {
+                tags.iter()
+                    .map(|tag| tag.as_str().map(str::to_string).ok_or("tags must be strings"))
+                    .collect::<Result<Vec<String>, &str>>()?
+            }
+            None => Vec::new(),
+        };
+        Ok(Entry {
+            name,
+            kind,
+            description: text("description").unwrap_or_default().to_string(),
+            tags,
+            added: value.get("added").and_then(Json::as_f64).unwrap_or(0.0) as u64,
+        })
+    }
+}
+
+// scenarios and recordings kept under a name in the `gallery` directory, each with a
+// description, tags and a thumbnail of the screen when it was added. The index is a JSON
+// file read and written on every change, so it can be edited by hand as well
+#[derive(Default)]
+pub struct Gallery {
+    // the entry whose thumbnail is taken once the frame is drawn
+    thumbnail_for: Option<String>,
+}
+
+impl Gallery {
+    fn path(file: &str) -> PathBuf {
+        PathBuf::from(DIRECTORY).join(file)
+    }
+
+    fn load(&self) -> Result<Vec<Entry>, String> {
+        let path = Gallery::path(INDEX);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) if !path.exists() => return Ok(Vec::new()),
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        };
+        parse_index(&text)
+    }
+
+    fn store(&self, entries: &[Entry]) -> Result<(), String> {
+        let path = Gallery::path(INDEX);
+        fs::create_dir_all(DIRECTORY)
+            .and_then(|_| fs::write(&path, index(entries.iter())))
+            .map_err(|err| format!("failed to write {}: {}", path.display(), err))
+    }
+
+    // copies the file in, scenarios are checked first so the gallery only holds ones that run
+    pub fn add(
+        &mut self,
+        source: &str,
+        name: &str,
+        tags: Vec<String>,
+        description: String
+    ) -> Result<String, String> {
+        let name = check_name(name)?;
+        let mut entries = self.load()?;
+        if entries.iter().any(|entry| entry.name == name) {
+            return Err(format!("the gallery already has a `{}`", name));
+        }
+        let kind = match source.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("json") => Kind::Scenario,
+            Some("tbr") => Kind::Recording,
+            _ => return Err("the gallery takes .json scenarios and .tbr recordings".to_string()),
+        };
+        let bytes = fs::read(source).map_err(|err| format!("{}: {}", source, err))?;
+        if kind == Kind::Scenario {
+            let text = String::from_utf8(bytes.clone()).map_err(|_| "not a scenario file")?;
+            scenario::commands(&text)?;
+        }
+        let entry = Entry { name, kind, description, tags, added: date::now() as u64 };
+        let path = Gallery::path(&entry.file());
+        fs::create_dir_all(DIRECTORY)
+            .and_then(|_| fs::write(&path, bytes))
+            .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+        let reply = format!("added {} `{}` to the gallery", kind.name(), entry.name);
+        self.thumbnail_for = Some(entry.name.clone());
+        entries.push(entry);
+        self.store(&entries)?;
+        Ok(reply)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<String, String> {
+        let mut entries = self.load()?;
+        let index = find(&entries, name)?;
+        let entry = entries.remove(index);
+        self.store(&entries)?;
+        // the index is what counts, leftover files do no harm
+        let _ = fs::remove_file(Gallery::path(&entry.file()));
+        let _ = fs::remove_file(Gallery::path(&entry.thumbnail()));
+        Ok(format!("removed `{}` from the gallery", name))
+    }
+
+    // adds the tags, or takes them away with `add` false
+    pub fn tag(&mut self, name: &str, tags: &[String], add: bool) -> Result<String, String> {
+        let mut entries = self.load()?;
+        let index = find(&entries, name)?;
+        let entry = &mut entries[index];
+        entry.tags.retain(|tag| !tags.contains(tag));
+        if add {
+            entry.tags.extend(tags.iter().cloned());
+        }
+        let reply = format!("`{}` tagged {}", name, entry.tags.join(", "));
+        self.store(&entries)?;
+        Ok(reply)
+    }
+
+    pub fn describe(&mut self, name: &str, description: String) -> Result<String, String> {
+        let mut entries = self.load()?;
+        let index = find(&entries, name)?;
+        entries[index].description = description;
+        self.store(&entries)?;
+        Ok(format!("described `{}`", name))
+    }
+
+    // the entries with all of the words, newest first, or all of them without any
+    pub fn search(&self, words: &[String]) -> Result<String, String> {
+        let entries = self.load()?;
+        let mut found: Vec<&Entry> = entries
+            .iter()
+            .filter(|entry| entry.matches(words))
+            .collect();
+        if found.is_empty() {
+            return Ok("nothing in the gallery matches".to_string());
+        }
+        found.sort_by_key(|entry| std::cmp::Reverse(entry.added));
+        let lines: Vec<String> = found
+            .iter()
+            .map(|entry| {
+                let mut line = format!("{} ({})", entry.name, entry.kind.name());
+                if !entry.tags.is_empty() {
+                    line.push_str(&format!(" [{}]", entry.tags.join(", ")));
+                }
+                if !entry.description.is_empty() {
+                    line.push_str(&format!(": {}", entry.description));
+                }
+                line
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    // what `name` is and where its file is
+    pub fn locate(&self, name: &str) -> Result<(Kind, String), String> {
+        let entries = self.load()?;
+        let entry = &entries[find(&entries, name)?];
+        Ok((entry.kind, Gallery::path(&entry.file()).display().to_string()))
+    }
+
+    // a zip of the named entries, or of all of them, with their files, thumbnails and an
+    // index of their own, for `import` in another gallery
+    pub fn export(&self, path: &str, names: &[String]) -> Result<String, String> {
+        let entries = self.load()?;
+        for name in names {
+            find(&entries, name)?;
+        }
+        let chosen: Vec<&Entry> = entries
+            .iter()
+            .filter(|entry| names.is_empty() || names.contains(&entry.name))
+            .collect();
+        if chosen.is_empty() {
+            return Err("the gallery is empty".to_string());
+        }
+        let mut files = vec![(INDEX.to_string(), index(chosen.iter().copied()).into_bytes())];
+        for entry in &chosen {
+            let file = Gallery::path(&entry.file());
+            let bytes = fs::read(&file).map_err(|err| format!("{}: {}", file.display(), err))?;
+            files.push((entry.file(), bytes));
+            // thumbnails are nice to have
+            if let Ok(bytes) = fs::read(Gallery::path(&entry.thumbnail())) {
+                files.push((entry.thumbnail(), bytes));
+            }
+        }
+        fs::write(path, npy::zip(&files)).map_err(|err| format!("{}: {}", path, err))?;
+        Ok(format!("exported {} gallery entries to {}", chosen.len(), path))
+    }
+
+    // the entries of a bundle from `export`, leaving out names the gallery already has
+    pub fn import(&mut self, path: &str) -> Result<String, String> {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+        let files = npy::unzip(&bytes).map_err(|err| format!("{}: {}", path, err))?;
+        let file = |name: &str| {
+            files
+                .iter()
+                .find(|(file, _)| file == name)
+                .map(|(_, bytes)| bytes)
+        };
+        let index = file(INDEX).ok_or(format!("{} has no gallery index", path))?;
+        let index = String::from_utf8(index.clone()).map_err(|_| "the index isn't UTF-8")?;
+        let bundled = parse_index(&index)?;
+
+        let mut entries = self.load()?;
+        let (mut imported, mut skipped) = (0, Vec::new());
+        fs::create_dir_all(DIRECTORY).map_err(|err| format!("{}: {}", DIRECTORY, err))?;
+        for entry in bundled {
+            let Some(bytes) = file(&entry.file()) else {
+                return Err(format!("{} is missing {}", path, entry.file()));
+            };
+            if entries.iter().any(|existing| existing.name == entry.name) {
+                skipped.push(entry.name);
+                continue;
+            }
+            let write = |file: &str, bytes: &[u8]| {
+                fs::write(Gallery::path(file), bytes).map_err(|err| format!("{}: {}", file, err))
+            };
+            write(&entry.file(), bytes)?;
+            if let Some(thumbnail) = file(&entry.thumbnail()) {
+                write(&entry.thumbnail(), thumbnail)?;
+            }
+            entries.push(entry);
+            imported += 1;
+        }
+        self.store(&entries)?;
+        let mut reply = format!("imported {} gallery entries", imported);
+        if !skipped.is_empty() {
+            reply.push_str(&format!(", already had {}", skipped.join(", ")));
+        }
+        Ok(reply)
+    }
+
+    // the screen as it is now, shrunk, for the entry added this frame. Called once the
+    // bodies are drawn and before the HUD
+    pub fn take_thumbnail(&mut self) -> Result<(), String> {
+        let Some(name) = self.thumbnail_for.take() else {
+            return Ok(());
+        };
+        let screen = get_screen_data();
+        let (width, height) = (screen.width as u32, screen.height as u32);
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let thumbnail_height = (THUMBNAIL_WIDTH * height / width).max(1);
+        let mut thumbnail =
+            Image::gen_image_color(THUMBNAIL_WIDTH as u16, thumbnail_height as u16, BLACK);
+        for y in 0..thumbnail_height {
+            for x in 0..THUMBNAIL_WIDTH {
+                let (from_x, from_y) = (x * width / THUMBNAIL_WIDTH, y * height / thumbnail_height);
+                thumbnail.set_pixel(x, y, screen.get_pixel(from_x, from_y));
+            }
+        }
+        let path = Gallery::path(&format!("{}.png", name));
+        // `export_png` panics where it can't write, better to find out first
+        if let Err(err) = fs::File::create(&path) {
+            return Err(format!("no thumbnail for `{}`: {}", name, err));
+        }
+        thumbnail.export_png(&path.display().to_string());
+        Ok(())
+    }
+}
+
+// names become file names, and bundles come from other people
+fn check_name(name: &str) -> Result<String, String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(allowed) {
+        return Err(format!("`{}` can only have letters, digits, `-` and `_`", name));
+    }
+    Ok(name.to_string())
+}
+
+fn find(entries: &[Entry], name: &str) -> Result<usize, String> {
+    entries
+        .iter()
+        .position(|entry| entry.name == name)
+        .ok_or(format!("nothing called `{}` in the gallery", name))
+}
+
+fn index<'a>(entries: impl Iterator<Item = &'a Entry>) -> String {
+    let entries: Vec<String> = entries.map(|entry| format!("    {}", entry.to_json())).collect();
+    format!("{{\n  \"entries\": [\n{}\n  ]\n}}\n", entries.join(",\n"))
+}
+
+fn parse_index(text: &str) -> Result<Vec<Entry>, String> {
+    let index = json::parse(text)?;
+    let entries = index
+        .get("entries")
+        .and_then(Json::as_array)
+        .ok_or("a gallery index needs an `entries` array")?;
+    entries.iter().map(Entry::from_json).collect()
+}
@@ -0,0 +1,132 @@
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::{ diagnostics, rng, Body };
+
+// Plummer spheres are cut off at this many scale radii, which still holds 94% of the mass
+const PLUMMER_CUTOFF: f32 = 5.0;
+// the cluster's mass is picked so sqrt(G M / a) comes out at this, the fastest stars in
+// the core then move at about half of `MAX_VELOCITY`
+const PLUMMER_SPEED: f32 = 6.0;
+const PLUMMER_BODY_RADIUS: f32 = 2.0;
+
+// scale length of the exponential disk as a fraction of its edge
+const DISK_SCALE_LENGTH: f32 = 1.0 / 3.0;
+// the orbital speed at the edge of the disk, which sets its mass like `PLUMMER_SPEED`
+const DISK_SPEED: f32 = 6.0;
+const DISK_BODY_RADIUS: f32 = 2.0;
+
+// a star cluster in virial equilibrium, sampled from the Plummer model like Aarseth, Henon
+// & Wielen (1974) do and then flattened onto the screen. The height above the plane becomes
+// the body's depth, and as a flattened sphere isn't quite in equilibrium under the planar
+// forces, the velocities are rescaled until 2K = -U for the bodies as they end up
+pub fn plummer(count: usize, center: Vec2, radius: f32, gravity: f32) -> Vec<Body> {
+    let total_mass = PLUMMER_SPEED.powi(2) * radius / gravity;
+    let mut stars: Bodies = (0..count)
+        .map(|_| {
+            // in units where G = M = a = 1
+            let (position, depth) = plummer_position();
+            let speed = plummer_speed(position.length());
+            let mut star = Body::new(center + position.truncate() * radius);
+            star.velocity = isotropic().truncate() * speed * PLUMMER_SPEED;
+            star.mass = total_mass / (count as f32);
+            star.radius = PLUMMER_BODY_RADIUS;
+            star.depth = depth;
+            star
+        })
+        .collect();
+
+    // sampled clusters drift a little, keep this one where it was asked for
+    let drift = diagnostics::linear_momentum(&stars) / diagnostics::total_mass(&stars);
+    for velocity in stars.velocities.iter_mut() {
+        *velocity -= drift;
+    }
+    let kinetic = diagnostics::kinetic_energy(&stars);
+    let potential = diagnostics::potential_energy(&stars, gravity);
+    if kinetic > 0.0 {
+        let factor = (-potential / (2.0 * kinetic)).sqrt() as f32;
+        for velocity in stars.velocities.iter_mut() {
+            *velocity *= factor;
+        }
+    }
+    stars.iter().collect()
+}
+
+// a self-gravitating exponential disk cut off at `radius`, every body on a circular orbit
+// around the mass inside it. Nothing holds a cold disk like this together, so it breaks up
+// into spiral arms within a couple of turns
+pub fn rotating_disk(count: usize, center: Vec2, radius: f32, gravity: f32) -> Vec<Body> {
+    let total_mass = DISK_SPEED.powi(2) * radius / gravity;
+    let mass = total_mass / (count as f32);
+    let scale_length = radius * DISK_SCALE_LENGTH;
+
+    // the radius of an exponential disk follows a gamma distribution with shape 2, which is
+    // the sum of two exponentials
+    let mut radii: Vec<f32> = (0..count)
+        .map(|_| {
+            loop {
+                let u: f32 = rng::gen_range(1e-6, 1.0);
+                let v: f32 = rng::gen_range(1e-6, 1.0);
+                let distance = -scale_length * (u * v).ln();
+                if distance <= radius {
+                    break distance;
+                }
+            }
+        })
+        .collect();
+    radii.sort_by(f32::total_cmp);
+
+    radii
+        .into_iter()
+        .enumerate()
+        .map(|(inside, distance)| {
+            let direction = Vec2::from_angle(rng::gen_range(0.0, TAU));
+            // only what's further in pulls on average, as for a spherical mass
+            let enclosed = (inside as f32) * mass;
+            let speed = (gravity * enclosed / distance.max(1.0)).sqrt();
+
+            // counterclockwise on screen, like the other presets
+            let mut body = Body::new(center + direction * distance);
+            body.velocity = -direction.perp() * speed;
+            body.mass = mass;
+            body.radius = DISK_BODY_RADIUS;
+            body
+        })
+        .collect()
+}
+
+// distance from the cumulative mass profile M(r) = r^3 / (1 + r^2)^(3/2), along with the
+// height above the plane scaled into the depth range
+fn plummer_position() -> (Vec3, f32) {
+    loop {
+        let mass_fraction: f32 = rng::gen_range(1e-6, 1.0);
+        let distance = 1.0 / (mass_fraction.powf(-2.0 / 3.0) - 1.0).sqrt();
+        if distance <= PLUMMER_CUTOFF {
+            let position = isotropic() * distance;
+            return (position, position.z / PLUMMER_CUTOFF);
+        }
+    }
+}
+
+// rejection sampling of q = v / v_escape from g(q) = q^2 (1 - q^2)^(7/2), whose peak is
+// just under 0.1
+fn plummer_speed(distance: f32) -> f32 {
+    let escape = (2.0f32).sqrt() * (1.0 + distance.powi(2)).powf(-0.25);
+    loop {
+        let q: f32 = rng::gen_range(0.0, 1.0);
+        let g: f32 = rng::gen_range(0.0, 0.1);
+        if g < q.powi(2) * (1.0 - q.powi(2)).powf(3.5) {
+            return q * escape;
+        }
+    }
+}
+
+// a random unit vector, uniform over the sphere
+fn isotropic() -> Vec3 {
+    let z: f32 = rng::gen_range(-1.0, 1.0);
+    let angle = rng::gen_range(0.0, TAU);
+    let planar = (1.0 - z.powi(2)).sqrt();
+    vec3(planar * angle.cos(), planar * angle.sin(), z)
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+// levels of red, green and blue in the palette, 6 * 7 * 6 = 252 of the 256 colors
+const REDS: u32 = 6;
+const GREENS: u32 = 7;
+const BLUES: u32 = 6;
+const MAX_CODE_SIZE: u32 = 12;
+
+// just enough GIF89a for looping animations: one fixed palette for every frame, so colors
+// are rounded to the nearest of a 6x7x6 cube without dithering, and LZW written by hand
+pub struct GifWriter {
+    file: BufWriter<File>,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl GifWriter {
+    pub fn create(path: &str, width: u16, height: u16) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(b"GIF89a")?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        // a global palette of 2^8 colors, background color 0, square pixels
+        file.write_all(&[0xf7, 0, 0])?;
+        for index in 0..256 {
+            let [r, g, b] = palette_color(index);
+            file.write_all(&[r, g, b])?;
+        }
+        // the Netscape extension, repeating forever
+        file.write_all(&[0x21, 0xff, 11])?;
+        file.write_all(b"NETSCAPE2.0")?;
+        file.write_all(&[3, 1, 0, 0, 0])?;
+        Ok(GifWriter { file, width, height })
+    }
+
+    // `pixels` are RGBA rows from the top, `delay` in hundredths of a second
+    pub fn frame(&mut self, pixels: &[[u8; 4]], delay: u16) -> io::Result<()> {
+        debug_assert_eq!(pixels.len(), (self.width as usize) * (self.height as usize));
+        // graphic control: no transparency, then the image filling the whole screen
+        self.file.write_all(&[0x21, 0xf9, 4, 0])?;
+        self.file.write_all(&delay.to_le_bytes())?;
+        self.file.write_all(&[0, 0, 0x2c, 0, 0, 0, 0])?;
+        self.file.write_all(&self.width.to_le_bytes())?;
+        self.file.write_all(&self.height.to_le_bytes())?;
+        self.file.write_all(&[0])?;
+
+        let indices: Vec<u8> = pixels.iter().map(|&pixel| palette_index(pixel)).collect();
+        let data = lzw(&indices);
+        self.file.write_all(&[8])?;
+        for block in data.chunks(255) {
+            self.file.write_all(&[block.len() as u8])?;
+            self.file.write_all(block)?;
+        }
+        self.file.write_all(&[0])
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.write_all(&[0x3b])?;
+        self.file.flush()
+    }
+}
+
+fn palette_color(index: u32) -> [u8; 3] {
+    if index >= REDS * GREENS * BLUES {
+        return [0, 0, 0];
+    }
+    let level = |value: u32, levels: u32| ((value * 255) / (levels - 1)) as u8;
+    [
+        level(index / (GREENS * BLUES), REDS),
+        level((index / BLUES) % GREENS, GREENS),
+        level(index % BLUES, BLUES),
+    ]
+}
+
+fn palette_index([r, g, b, _]: [u8; 4]) -> u8 {
+    let level = |value: u8, levels: u32| ((value as u32) * (levels - 1) + 127) / 255;
+    (level(r, REDS) * GREENS * BLUES + level(g, GREENS) * BLUES + level(b, BLUES)) as u8
+}
+
+// variable width LZW over 8 bit indices, codes packed from the low bit up. The table starts
+// over when it's full
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 256u32;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    let mut emit = |code: u32, size: u32, out: &mut Vec<u8>| {
+        buffer |= code << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut size = 9;
+    let mut next = end + 1;
+    emit(clear, size, &mut out);
+    let mut current: Option<u32> = None;
+    for &index in indices {
+        let Some(prefix) = current else {
+            current = Some(index as u32);
+            continue;
+        };
+        if let Some(&code) = table.get(&(prefix, index)) {
+            current = Some(code);
+            continue;
+        }
+        emit(prefix, size, &mut out);
+        if next < 1 << MAX_CODE_SIZE {
+            table.insert((prefix, index), next);
+            // the decoder widens its codes one code later than the table grows
+            if next == 1 << size && size < MAX_CODE_SIZE {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            emit(clear, size, &mut out);
+            table.clear();
+            size = 9;
+            next = end + 1;
+        }
+        current = Some(index as u32);
+    }
+    if let Some(prefix) = current {
+        emit(prefix, size, &mut out);
+    }
+    emit(end, size, &mut out);
+    emit(0, 7, &mut out);
+    out
+}
@@ -0,0 +1,129 @@
+use macroquad::miniquad::{
+    BlendFactor,
+    BlendState,
+    BlendValue,
+    Equation,
+    PipelineParams,
+    UniformType,
+};
+use macroquad::prelude::*;
+
+// how far the halo reaches, in body radii
+const HALO: f32 = 4.0;
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+    color = color0 / 255.0;
+}"#;
+
+// a white hot core the size of the body blurring into a halo of its color, the alpha
+// carries how bright it is
+const FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform float halo;
+
+void main() {
+    float r = length(uv * 2.0 - 1.0) * halo;
+    float core = 1.0 - smoothstep(0.6, 1.0, r);
+    float glow = exp(-r * r / 2.0) * (1.0 - smoothstep(halo - 1.0, halo, r));
+    vec3 rgb = mix(color.rgb, vec3(1.0), core * 0.8);
+    gl_FragColor = vec4(rgb, color.a * max(core, glow));
+}"#;
+
+// bodies drawn as bright blurred discs that add up where they overlap, the heavier ones
+// brighter, in place of the rings. Goes through a shader, so it's unavailable where that
+// doesn't compile
+#[derive(Default)]
+pub struct Glow {
+    pub enabled: bool,
+    material: Option<Material>,
+    // what's to be drawn this frame: screen position, radius on screen, color and mass
+    queue: Vec<(Vec2, f32, Color, f32)>,
+}
+
+impl Glow {
+    pub fn new() -> Self {
+        let material = load_material(
+            ShaderSource::Glsl { vertex: VERTEX, fragment: FRAGMENT },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    // additive, so crowded regions burn brighter
+                    color_blend: Some(
+                        BlendState::new(
+                            Equation::Add,
+                            BlendFactor::Value(BlendValue::SourceAlpha),
+                            BlendFactor::One
+                        )
+                    ),
+                    ..Default::default()
+                },
+                uniforms: vec![UniformDesc::new("halo", UniformType::Float1)],
+                ..Default::default()
+            }
+        );
+        match material {
+            Ok(material) => Glow { material: Some(material), ..Default::default() },
+            Err(err) => {
+                eprintln!("glow unavailable: {:?}", err);
+                Glow::default()
+            }
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.material.is_some()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.is_available()
+    }
+
+    pub fn add(&mut self, position: Vec2, radius: f32, color: Color, mass: f32) {
+        self.queue.push((position, radius, color, mass));
+    }
+
+    // everything added since the last call, in one batch
+    pub fn draw(&mut self) {
+        let Some(material) = &self.material else {
+            self.queue.clear();
+            return;
+        };
+        // on a log scale between the lightest and heaviest body, so a few giants don't leave
+        // the rest dim
+        let (light, heavy) = self.queue
+            .iter()
+            .fold((f32::INFINITY, 0.0f32), |(low, high), &(_, _, _, mass)| {
+                (low.min(mass), high.max(mass))
+            });
+        let range = (heavy / light.max(f32::MIN_POSITIVE)).ln();
+        gl_use_material(material);
+        material.set_uniform("halo", HALO);
+        for (position, radius, color, mass) in self.queue.drain(..) {
+            let share = if range > 0.0 && mass > 0.0 {
+                ((mass / light).ln() / range).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            let size = radius * HALO;
+            let color = Color { a: color.a * (0.35 + 0.65 * share), ..color };
+            draw_rectangle(position.x - size, position.y - size, 2.0 * size, 2.0 * size, color);
+        }
+        gl_use_default_material();
+    }
+}
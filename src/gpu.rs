@@ -0,0 +1,426 @@
+use macroquad::miniquad::*;
+// the prelude has a `RenderPass` of its own
+use macroquad::miniquad::RenderPass;
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::solver;
+
+// bodies are laid out in rows of this many texels
+const TEXTURE_WIDTH: u32 = 128;
+// the shader loops need constant bounds, so this caps the body count at 128 * 1024
+const MAX_ROWS: u32 = 1024;
+// how many powers of two below the largest mass or radius the shader can still tell apart
+const LOG_RANGE: f32 = 64.0;
+// what the probe clears the render target to, only a float format holds all of it
+const PROBE_CLEAR: [f32; 4] = [-2.0, 1000.0, 0.5, 1.0];
+// how far the probe's forces may be from the CPU's, as the RMS of the difference over the
+// RMS of the forces. The encoding and the half floats lose about a tenth of that
+const PROBE_TOLERANCE: f32 = 1e-2;
+
+const VERTEX: &str = r#"#version 100
+attribute vec2 in_pos;
+
+void main() {
+    gl_Position = vec4(in_pos, 0.0, 1.0);
+}
+"#;
+
+// one fragment per body: decodes every other body from the data textures and sums
+// the acceleration on it, skipping pairs that overlap just like `Bodies::force_towards`
+const FRAGMENT: &str = r#"#version 100
+precision highp float;
+
+uniform float count;
+uniform float rows;
+uniform vec2 bounds_min;
+uniform vec2 bounds_size;
+uniform float mass_scale;
+uniform float radius_scale;
+uniform float gravity;
+
+uniform sampler2D positions;
+uniform sampler2D properties;
+
+const float WIDTH = 128.0;
+const int MAX_ROWS = 1024;
+const float LOG_RANGE = 64.0;
+
+float decode(float high, float low) {
+    return (high * 255.0 * 256.0 + low * 255.0) / 65535.0;
+}
+
+// the inverse of `encode_log`, zero stays zero
+float decode_log(float high, float low) {
+    float fraction = decode(high, low);
+    if (fraction == 0.0) {
+        return 0.0;
+    }
+    return exp2((fraction - 1.0) * LOG_RANGE);
+}
+
+vec2 texel(float column, float row) {
+    return vec2((column + 0.5) / WIDTH, (row + 0.5) / rows);
+}
+
+void main() {
+    float index = floor(gl_FragCoord.y) * WIDTH + floor(gl_FragCoord.x);
+    if (index >= count) {
+        gl_FragColor = vec4(0.0);
+        return;
+    }
+
+    vec2 uv = texel(floor(gl_FragCoord.x), floor(gl_FragCoord.y));
+    vec4 own = texture2D(positions, uv);
+    vec2 position = bounds_min + vec2(decode(own.r, own.g), decode(own.b, own.a)) * bounds_size;
+    vec4 own_properties = texture2D(properties, uv);
+    float radius = decode_log(own_properties.b, own_properties.a) * radius_scale;
+
+    vec2 acceleration = vec2(0.0);
+    for (int row = 0; row < MAX_ROWS; row++) {
+        if (float(row) >= rows) {
+            break;
+        }
+        for (int column = 0; column < 128; column++) {
+            float other = float(row) * WIDTH + float(column);
+            if (other >= count) {
+                break;
+            }
+            if (other == index) {
+                continue;
+            }
+
+            vec2 other_uv = texel(float(column), float(row));
+            vec4 data = texture2D(positions, other_uv);
+            vec2 other_position =
+                bounds_min + vec2(decode(data.r, data.g), decode(data.b, data.a)) * bounds_size;
+            vec2 offset = other_position - position;
+            float distance = length(offset);
+            if (distance < 2.0 * radius) {
+                continue;
+            }
+
+            vec4 other_properties = texture2D(properties, other_uv);
+            float mass = decode_log(other_properties.r, other_properties.g) * mass_scale;
+            acceleration += gravity * mass / (distance * distance) * (offset / distance);
+        }
+    }
+
+    gl_FragColor = vec4(acceleration, 0.0, 1.0);
+}
+"#;
+
+#[repr(C)]
+struct Uniforms {
+    count: f32,
+    rows: f32,
+    bounds_min: [f32; 2],
+    bounds_size: [f32; 2],
+    mass_scale: f32,
+    radius_scale: f32,
+    gravity: f32,
+}
+
+// pairwise forces evaluated in a fragment shader, miniquad has no compute shaders
+pub struct GpuSolver {
+    pipeline: Pipeline,
+    vertex_buffer: BufferId,
+    index_buffer: BufferId,
+    textures: Option<Textures>,
+}
+
+struct Textures {
+    rows: u32,
+    positions: TextureId,
+    properties: TextureId,
+    output: TextureId,
+    pass: RenderPass,
+}
+
+impl GpuSolver {
+    // None when the shader doesn't compile or the probe fails, callers fall back to the CPU
+    // solvers
+    pub fn new() -> Option<Self> {
+        let gl = unsafe { get_internal_gl() };
+        let context = gl.quad_context;
+
+        let shader = context.new_shader(
+            ShaderSource::Glsl {
+                vertex: VERTEX,
+                fragment: FRAGMENT,
+            },
+            ShaderMeta {
+                images: vec!["positions".to_string(), "properties".to_string()],
+                uniforms: UniformBlockLayout {
+                    uniforms: vec![
+                        UniformDesc::new("count", UniformType::Float1),
+                        UniformDesc::new("rows", UniformType::Float1),
+                        UniformDesc::new("bounds_min", UniformType::Float2),
+                        UniformDesc::new("bounds_size", UniformType::Float2),
+                        UniformDesc::new("mass_scale", UniformType::Float1),
+                        UniformDesc::new("radius_scale", UniformType::Float1),
+                        UniformDesc::new("gravity", UniformType::Float1)
+                    ],
+                },
+            }
+        );
+        let shader = match shader {
+            Ok(shader) => shader,
+            Err(err) => {
+                eprintln!("gpu solver unavailable: {:?}", err);
+                return None;
+            }
+        };
+
+        let pipeline = context.new_pipeline(
+            &[BufferLayout::default()],
+            &[VertexAttribute::new("in_pos", VertexFormat::Float2)],
+            shader,
+            PipelineParams::default()
+        );
+
+        let vertices: [[f32; 2]; 4] = [
+            [-1.0, -1.0],
+            [1.0, -1.0],
+            [1.0, 1.0],
+            [-1.0, 1.0],
+        ];
+        let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+        let vertex_buffer = context.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&vertices)
+        );
+        let index_buffer = context.new_buffer(
+            BufferType::IndexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&indices)
+        );
+
+        let mut solver = GpuSolver {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            textures: None,
+        };
+        if let Err(err) = solver.probe() {
+            eprintln!("gpu solver unavailable: {}", err);
+            let context = unsafe { get_internal_gl() }.quad_context;
+            if let Some(textures) = solver.textures.take() {
+                textures.delete(context);
+            }
+            context.delete_pipeline(solver.pipeline);
+            context.delete_shader(shader);
+            context.delete_buffer(solver.vertex_buffer);
+            context.delete_buffer(solver.index_buffer);
+            return None;
+        }
+        Some(solver)
+    }
+
+    // GLES2 and WebGL1 needn't render to float textures, and where they can't the readback is
+    // garbage rather than an error. Clears the render target to values only a float format
+    // holds, then checks the forces on a few bodies against the CPU's
+    fn probe(&mut self) -> Result<(), String> {
+        {
+            let context = unsafe { get_internal_gl() }.quad_context;
+            let textures = Textures::new(context, 1);
+            let [r, g, b, a] = PROBE_CLEAR;
+            context.begin_pass(Some(textures.pass), PassAction::clear_color(r, g, b, a));
+            context.end_render_pass();
+            let mut output = vec![0u8; (TEXTURE_WIDTH as usize) * 16];
+            context.texture_read_pixels(textures.output, &mut output);
+            self.textures = Some(textures);
+
+            let texel: Vec<f32> = output[..16]
+                .chunks(4)
+                .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+                .collect();
+            if texel != PROBE_CLEAR {
+                let message = "no float render target, cleared to";
+                return Err(format!("{} {:?} but read {:?}", message, PROBE_CLEAR, texel));
+            }
+        }
+
+        // just the arrays the solvers read, with masses and distances spread over a few
+        // orders of magnitude like a busy scene
+        let positions = [
+            vec2(0.0, 0.0),
+            vec2(300.0, 40.0),
+            vec2(-120.0, 250.0),
+            vec2(45.0, -80.0),
+            vec2(610.0, -390.0),
+            vec2(-30.0, 12.0),
+        ];
+        let count = positions.len();
+        let mut bodies = Bodies {
+            ids: vec![0; count],
+            positions: positions.to_vec(),
+            masses: vec![1000.0, 50.0, 2.0e4, 1.0, 300.0, 7.5],
+            radii: vec![5.0, 1.0, 12.0, 0.5, 3.0, 1.5],
+            forces: vec![Vec2::ZERO; count],
+            ..Default::default()
+        };
+        self.compute_forces(&mut bodies, 1.0);
+
+        let expected = solver::brute_force(&bodies, 1.0);
+        let (error, norm) = bodies.forces
+            .iter()
+            .zip(&expected)
+            .fold((0.0, 0.0), |(error, norm), (force, expected)| {
+                (error + force.distance_squared(*expected), norm + expected.length_squared())
+            });
+        let error = (error / norm).sqrt();
+        if error.is_nan() || error > PROBE_TOLERANCE {
+            return Err(format!("test readback off the CPU forces by {:.1e}", error));
+        }
+        Ok(())
+    }
+
+    pub fn max_bodies() -> usize {
+        (TEXTURE_WIDTH * MAX_ROWS) as usize
+    }
+
+    pub fn compute_forces(&mut self, bodies: &mut Bodies, gravity: f32) {
+        if bodies.is_empty() {
+            return;
+        }
+
+        let rows = (bodies.len() as u32).div_ceil(TEXTURE_WIDTH);
+        let (min, max) = bodies.positions
+            .iter()
+            .fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), &position| {
+                (min.min(position), max.max(position))
+            });
+        let size = (max - min).max(Vec2::ONE);
+        let mass_scale = bodies.masses.iter().fold(f32::EPSILON, |scale, &mass| scale.max(mass));
+        let radius_scale = bodies.radii.iter().fold(f32::EPSILON, |scale, &radius| scale.max(radius));
+
+        // positions travel as 16 bit fixed point split over two bytes. Masses and radii can span
+        // many orders of magnitude, so they go as the logarithm of their share of the largest
+        let texel_count = (rows * TEXTURE_WIDTH) as usize;
+        let mut positions = vec![0u8; texel_count * 4];
+        let mut properties = vec![0u8; texel_count * 4];
+        for i in 0..bodies.len() {
+            let relative = (bodies.positions[i] - min) / size;
+            encode(&mut positions[i * 4..i * 4 + 2], relative.x);
+            encode(&mut positions[i * 4 + 2..i * 4 + 4], relative.y);
+            encode_log(&mut properties[i * 4..i * 4 + 2], bodies.masses[i] / mass_scale);
+            encode_log(&mut properties[i * 4 + 2..i * 4 + 4], bodies.radii[i] / radius_scale);
+        }
+
+        let mut gl = unsafe { get_internal_gl() };
+        gl.flush();
+        let context = gl.quad_context;
+
+        if self.textures.as_ref().is_none_or(|textures| textures.rows != rows) {
+            if let Some(old) = self.textures.take() {
+                old.delete(context);
+            }
+            self.textures = Some(Textures::new(context, rows));
+        }
+        let textures = self.textures.as_ref().unwrap();
+
+        context.texture_update(textures.positions, &positions);
+        context.texture_update(textures.properties, &properties);
+
+        context.begin_pass(Some(textures.pass), PassAction::clear_color(0.0, 0.0, 0.0, 0.0));
+        context.apply_viewport(0, 0, TEXTURE_WIDTH as i32, rows as i32);
+        context.apply_pipeline(&self.pipeline);
+        context.apply_bindings(
+            &(Bindings {
+                vertex_buffers: vec![self.vertex_buffer],
+                index_buffer: self.index_buffer,
+                images: vec![textures.positions, textures.properties],
+            })
+        );
+        context.apply_uniforms(
+            UniformsSource::table(
+                &(Uniforms {
+                    count: bodies.len() as f32,
+                    rows: rows as f32,
+                    bounds_min: min.into(),
+                    bounds_size: size.into(),
+                    mass_scale,
+                    radius_scale,
+                    gravity,
+                })
+            )
+        );
+        context.draw(0, 6, 1);
+        context.end_render_pass();
+
+        // half float render target, read back as 32 bit floats
+        let mut output = vec![0u8; texel_count * 16];
+        context.texture_read_pixels(textures.output, &mut output);
+
+        for i in 0..bodies.len() {
+            let texel = &output[i * 16..i * 16 + 8];
+            let acceleration = vec2(
+                f32::from_ne_bytes(texel[0..4].try_into().unwrap()),
+                f32::from_ne_bytes(texel[4..8].try_into().unwrap())
+            );
+            bodies.forces[i] = acceleration * bodies.masses[i];
+        }
+    }
+}
+
+impl Textures {
+    fn new(context: &mut dyn RenderingBackend, rows: u32) -> Self {
+        let data = TextureParams {
+            width: TEXTURE_WIDTH,
+            height: rows,
+            min_filter: FilterMode::Nearest,
+            mag_filter: FilterMode::Nearest,
+            ..Default::default()
+        };
+        let positions = context.new_texture(
+            TextureAccess::Static,
+            TextureSource::Empty,
+            data
+        );
+        let properties = context.new_texture(
+            TextureAccess::Static,
+            TextureSource::Empty,
+            data
+        );
+        let output = context.new_render_texture(TextureParams {
+            format: TextureFormat::RGBA16F,
+            ..data
+        });
+        let pass = context.new_render_pass(output, None);
+
+        Textures {
+            rows,
+            positions,
+            properties,
+            output,
+            pass,
+        }
+    }
+
+    fn delete(self, context: &mut dyn RenderingBackend) {
+        context.delete_render_pass(self.pass);
+        context.delete_texture(self.positions);
+        context.delete_texture(self.properties);
+        context.delete_texture(self.output);
+    }
+}
+
+fn encode(bytes: &mut [u8], value: f32) {
+    let fixed = (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+    bytes[0] = (fixed >> 8) as u8;
+    bytes[1] = (fixed & 0xff) as u8;
+}
+
+// `log2(value)` over the last `LOG_RANGE` powers of two below 1, as 16 bit fixed point, which
+// keeps every value within a twentieth of a percent. Zero, and anything too small to
+// matter, is stored as zero
+fn encode_log(bytes: &mut [u8], value: f32) {
+    let fraction = 1.0 + value.log2() / LOG_RANGE;
+    if value <= 0.0 || fraction <= 0.0 {
+        encode(bytes, 0.0);
+    } else {
+        encode(bytes, fraction.max(1.0 / 65535.0));
+    }
+}
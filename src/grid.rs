@@ -0,0 +1,133 @@
+use macroquad::prelude::*;
+
+use crate::settings::Settings;
+use crate::view::View;
+
+// screen pixels between grid lines, at least, and of the scale bar
+const GRID_SPACING: f32 = 80.0;
+const BAR_LENGTH: f32 = 60.0;
+// in case the zoom goes somewhere the steps can't follow
+const MAX_LINES: usize = 200;
+const LINE_COLOR: Color = Color::new(0.3, 0.3, 0.3, 0.5);
+const AXIS_COLOR: Color = Color::new(0.55, 0.55, 0.55, 0.8);
+
+// world coordinates in the background: lines a round number of pixels apart that get finer
+// as the camera zooms in, each labeled with its x or y, and a bar for judging distances
+#[derive(Default)]
+pub struct WorldGrid {
+    pub visible: bool,
+}
+
+impl WorldGrid {
+    pub fn draw(&self, settings: &Settings, view: &View) {
+        if !self.visible {
+            return;
+        }
+        let step = round_step(GRID_SPACING / view.scale());
+        let decimals = decimals(step);
+        // the part of the world on screen, the view may be rotated
+        let size = vec2(screen_width(), screen_height());
+        let corners = [vec2(0.0, 0.0), vec2(size.x, 0.0), size, vec2(0.0, size.y)]
+            .map(|corner| view.screen_to_world(corner));
+        let low = corners.into_iter().fold(Vec2::splat(f32::INFINITY), Vec2::min);
+        let high = corners.into_iter().fold(Vec2::splat(f32::NEG_INFINITY), Vec2::max);
+
+        for vertical in [true, false] {
+            let (from, to) = if vertical { (low.x, high.x) } else { (low.y, high.y) };
+            let first = (from / step).ceil() as i64;
+            let last = (to / step).floor() as i64;
+            if last - first > MAX_LINES as i64 {
+                continue;
+            }
+            for line in first..=last {
+                let value = (line as f32) * step;
+                let (a, b) = if vertical {
+                    (vec2(value, low.y), vec2(value, high.y))
+                } else {
+                    (vec2(low.x, value), vec2(high.x, value))
+                };
+                let (a, b) = (view.world_to_screen(a), view.world_to_screen(b));
+                let Some((a, b)) = clip(a, b, size) else {
+                    continue;
+                };
+                let color = if line == 0 { AXIS_COLOR } else { LINE_COLOR };
+                draw_line(a.x, a.y, b.x, b.y, 1.0, color);
+
+                // at the top or left end, whichever way the view is turned
+                let first_end = if vertical { a.y <= b.y } else { a.x <= b.x };
+                let end = if first_end { a } else { b };
+                let axis = if vertical { "x" } else { "y" };
+                let label = format!("{} {}", axis, settings.numbers.format(value, decimals));
+                let inside = (end + vec2(4.0, 14.0)).min(size - vec2(40.0, 4.0));
+                draw_text(&label, inside.x, inside.y, 14.0, GRAY);
+            }
+        }
+    }
+
+    // a round distance in world pixels, next to the middle of the bottom edge
+    pub fn draw_scale_bar(&self, settings: &Settings, view: &View) {
+        if !self.visible {
+            return;
+        }
+        let length = round_step(BAR_LENGTH / view.scale());
+        let width = length * view.scale();
+        let right = screen_width() / 2.0 + 100.0;
+        let (left, y) = (right - width, screen_height() - 30.0);
+        draw_line(left, y, right, y, 2.0, WHITE);
+        for x in [left, right] {
+            draw_line(x, y - 5.0, x, y + 5.0, 2.0, WHITE);
+        }
+        let numbers = settings.numbers;
+        let mut label = numbers.quantity(length, decimals(length), "px");
+        // the compressed distances only keep their proportions where nothing is compressed
+        if let Some(scale) = settings.scale.filter(|scale| scale.exponent == 1.0) {
+            let au = scale.au_from_pixels(length);
+            label.push_str(&format!(" = {}", numbers.quantity(au, decimals(au) + 1, "AU")));
+        }
+        let label_width = measure_text(&label, None, 16, 1.0).width;
+        draw_text(&label, (left + right - label_width) / 2.0, y - 10.0, 16.0, WHITE);
+    }
+}
+
+// 1, 2 or 5 times a power of ten, the first at least `target`
+fn round_step(target: f32) -> f32 {
+    let power = (10.0f32).powf(target.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * power)
+        .find(|&step| step >= target)
+        .unwrap_or(10.0 * power)
+}
+
+// enough for a step below 1 to show
+fn decimals(step: f32) -> usize {
+    if step >= 1.0 { 0 } else { (-step.log10().floor()) as usize }
+}
+
+// the part of the line from `a` to `b` on a screen of `size`, none if it's all off screen
+fn clip(a: Vec2, b: Vec2, size: Vec2) -> Option<(Vec2, Vec2)> {
+    let delta = b - a;
+    let (mut enter, mut leave) = (0.0f32, 1.0f32);
+    // one pair per edge, how far along the line crosses it and from which side
+    let edges = [
+        (-delta.x, a.x),
+        (delta.x, size.x - a.x),
+        (-delta.y, a.y),
+        (delta.y, size.y - a.y),
+    ];
+    for (towards, room) in edges {
+        if towards == 0.0 {
+            if room < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = room / towards;
+        if towards < 0.0 {
+            enter = enter.max(t);
+        } else {
+            leave = leave.min(t);
+        }
+    }
+    (enter <= leave).then(|| (a + delta * enter, a + delta * leave))
+}
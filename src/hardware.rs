@@ -0,0 +1,128 @@
+use macroquad::prelude::*;
+
+use crate::settings::Settings;
+use crate::simd;
+use crate::solver::Solver;
+use crate::NUM_OF_BODIES;
+
+// how long the splash stays up unless a key dismisses it
+const SPLASH_SECONDS: f64 = 4.0;
+// bodies the CPU solvers keep at a smooth frame rate per core, roughly
+const BODIES_PER_CORE: usize = 250;
+// never fewer than this, a sparse random preset isn't much to look at
+const MIN_BODIES: usize = 200;
+
+// what the machine running the simulation can do
+pub struct Capabilities {
+    pub cores: usize,
+    // vector instruction sets the CPU solvers could use
+    pub simd: Vec<&'static str>,
+    pub gpu: bool,
+}
+
+impl Capabilities {
+    // `gpu` is whether `GpuSolver::new` succeeded, it needs the GL context to find out
+    pub fn detect(gpu: bool) -> Self {
+        // a browser reports no parallelism to a single threaded wasm build
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        Capabilities { cores, simd: simd_features(), gpu }
+    }
+
+    // a solver and body count the machine keeps up with. The brute force sum on the GPU
+    // handles the full count, the CPU gets a share per core and a tree code once there are
+    // many bodies
+    pub fn choose(&self, settings: &mut Settings) {
+        settings.simd = simd::KERNEL && self.simd.contains(&"sse2");
+        if self.gpu {
+            settings.solver = Solver::Gpu;
+            settings.body_count = NUM_OF_BODIES;
+        } else {
+            settings.body_count = (self.cores * BODIES_PER_CORE).clamp(MIN_BODIES, NUM_OF_BODIES);
+            if settings.body_count > NUM_OF_BODIES / 2 {
+                settings.solver = Solver::BarnesHut;
+            }
+        }
+        settings.default_solver = settings.solver;
+    }
+
+    pub fn report(&self, settings: &Settings) -> Vec<String> {
+        let simd = if self.simd.is_empty() { "none".to_string() } else { self.simd.join(" ") };
+        vec![
+            format!(
+                "{} {}, SIMD: {}, GPU: {}",
+                self.cores,
+                if self.cores == 1 { "core" } else { "cores" },
+                simd,
+                if self.gpu { "yes" } else { "no" }
+            ),
+            format!(
+                "chose the {} solver{}, {} bodies",
+                settings.solver.name(),
+                if settings.simd { " with SIMD" } else { "" },
+                settings.body_count
+            )
+        ]
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn simd_features() -> Vec<&'static str> {
+    // SSE2 is part of the x86_64 baseline, the rest is up to the CPU
+    let mut features = vec!["sse2"];
+    if is_x86_feature_detected!("sse4.1") {
+        features.push("sse4.1");
+    }
+    if is_x86_feature_detected!("avx") {
+        features.push("avx");
+    }
+    if is_x86_feature_detected!("avx2") {
+        features.push("avx2");
+    }
+    if is_x86_feature_detected!("fma") {
+        features.push("fma");
+    }
+    features
+}
+
+#[cfg(target_arch = "aarch64")]
+fn simd_features() -> Vec<&'static str> {
+    // part of the aarch64 baseline
+    vec!["neon"]
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn simd_features() -> Vec<&'static str> {
+    // wasm only has vector instructions when built for them
+    if cfg!(target_feature = "simd128") { vec!["simd128"] } else { Vec::new() }
+}
+
+// shown over the first frames until a key is pressed
+pub struct Splash {
+    lines: Vec<String>,
+    shown_at: Option<f64>,
+}
+
+impl Splash {
+    pub fn new(lines: Vec<String>) -> Self {
+        Splash { lines, shown_at: None }
+    }
+
+    pub fn draw(&mut self) {
+        let shown_at = *self.shown_at.get_or_insert_with(get_time);
+        if self.lines.is_empty() {
+            return;
+        }
+        if get_last_key_pressed().is_some() || get_time() - shown_at > SPLASH_SECONDS {
+            self.lines.clear();
+            return;
+        }
+        let (width, height) = (520.0, 40.0 + 24.0 * (self.lines.len() as f32));
+        let (x, y) = ((screen_width() - width) / 2.0, (screen_height() - height) / 2.0);
+        draw_rectangle(x, y, width, height, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, y, width, height, 1.0, GRAY);
+        draw_text("threebody", x + 16.0, y + 28.0, 26.0, WHITE);
+        for (i, line) in self.lines.iter().enumerate() {
+            draw_text(line, x + 16.0, y + 56.0 + 24.0 * (i as f32), 20.0, LIGHTGRAY);
+        }
+    }
+}
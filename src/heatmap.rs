@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+
+use crate::view::View;
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+pub const CELL_SIZE: f32 = 10.0;
+
+// where collisions happened, accumulated over the world bounds
+pub struct CollisionHeatmap {
+    pub visible: bool,
+    columns: usize,
+    rows: usize,
+    counts: Vec<u32>,
+}
+
+impl Default for CollisionHeatmap {
+    fn default() -> Self {
+        let columns = (SCREEN_WIDTH / CELL_SIZE).ceil() as usize;
+        let rows = (SCREEN_HEIGHT / CELL_SIZE).ceil() as usize;
+        CollisionHeatmap {
+            visible: false,
+            columns,
+            rows,
+            counts: vec![0; columns * rows],
+        }
+    }
+}
+
+impl CollisionHeatmap {
+    pub fn record(&mut self, position: Vec2) {
+        if position.x < 0.0 || position.y < 0.0 {
+            return;
+        }
+        let column = (position.x / CELL_SIZE) as usize;
+        let row = (position.y / CELL_SIZE) as usize;
+        if column < self.columns && row < self.rows {
+            self.counts[row * self.columns + column] += 1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+    }
+
+    // the most collisions in a cell, none while there's nothing to show
+    pub fn max(&self) -> Option<u32> {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        (self.visible && max > 0).then_some(max)
+    }
+
+    pub fn draw(&self, view: &View) {
+        if !self.visible {
+            return;
+        }
+
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return;
+        }
+
+        for (index, &count) in self.counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            // log scale so a single hot spot doesn't wash out everything else
+            let heat = ((count as f32).ln_1p() / (max as f32).ln_1p()).clamp(0.0, 1.0);
+            let color = heat_color(heat);
+
+            let cell = vec2(
+                ((index % self.columns) as f32 + 0.5) * CELL_SIZE,
+                ((index / self.columns) as f32 + 0.5) * CELL_SIZE
+            );
+            let center = view.world_to_screen(cell);
+            let size = CELL_SIZE * view.scale();
+            draw_rectangle(center.x - size / 2.0, center.y - size / 2.0, size, size, color);
+        }
+    }
+}
+
+// `heat` from 0 for a single collision to 1 for the hottest cell
+pub fn heat_color(heat: f32) -> Color {
+    Color::new(1.0, 1.0 - heat, 0.0, 0.15 + 0.5 * heat)
+}
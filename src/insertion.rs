@@ -0,0 +1,167 @@
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::metadata::Metadata;
+use crate::view::View;
+
+// points the previewed orbit is drawn with
+const PREVIEW_SEGMENTS: usize = 128;
+// length of the preview arrow per unit of velocity change
+const DELTA_V_SCALE: f32 = 20.0;
+const PREVIEW_COLOR: Color = Color::new(0.3, 0.9, 1.0, 0.6);
+
+// a planned burn putting `body` on an orbit around `primary` through the point it is at. With a
+// positive eccentricity that point becomes the periapsis, with a negative one the apoapsis.
+// Worked out again every frame until applied, so the preview keeps up with the bodies
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Insertion {
+    pub body: u64,
+    pub primary: u64,
+    pub eccentricity: f32,
+}
+
+// the velocity change an insertion comes to right now
+struct Burn {
+    index: usize,
+    primary: usize,
+    velocity: Vec2,
+    delta_v: Vec2,
+    // of the orbit it leads to: semi-latus rectum, direction of the periapsis from the primary
+    // and gravitational parameter
+    semi_latus: f32,
+    periapsis: Vec2,
+    mu: f32,
+}
+
+impl Insertion {
+    // for the first selected body, around `primary` or whatever pulls on it hardest
+    pub fn new(bodies: &Bodies, eccentricity: f32, primary: Option<u64>) -> Result<Self, String> {
+        if eccentricity.abs() >= 1.0 {
+            return Err("the eccentricity of a closed orbit is between -1 and 1".to_string());
+        }
+        let index = bodies.selected
+            .iter()
+            .position(|&selected| selected)
+            .ok_or("select the body to put into orbit first")?;
+        let primary = match primary {
+            Some(id) => {
+                find(bodies, id).ok_or_else(|| format!("no body with id {}", id))?;
+                id
+            }
+            None => bodies.ids[strongest_pull(bodies, index).ok_or("nothing to orbit")?],
+        };
+        if primary == bodies.ids[index] {
+            return Err("a body can't orbit itself".to_string());
+        }
+        Ok(Insertion { body: bodies.ids[index], primary, eccentricity })
+    }
+
+    fn burn(&self, bodies: &Bodies, gravity: f32) -> Option<Burn> {
+        let (index, primary) = (find(bodies, self.body)?, find(bodies, self.primary)?);
+        let offset = bodies.positions[index] - bodies.positions[primary];
+        let radius = offset.length();
+        if radius == 0.0 {
+            return None;
+        }
+        let relative = bodies.velocities[index] - bodies.velocities[primary];
+        // keeps the way it goes round, counterclockwise on screen like the presets if it
+        // doesn't go round at all (screen y grows downwards)
+        let clockwise = offset.perp_dot(relative) > 0.0;
+        let direction = if clockwise { offset.perp() } else { -offset.perp() };
+        let mu = gravity * (bodies.masses[index] + bodies.masses[primary]);
+        // vis-viva at an apsis, the apoapsis is the periapsis of a negative eccentricity
+        let speed = (mu * (1.0 + self.eccentricity) / radius).sqrt();
+        let velocity = bodies.velocities[primary] + (direction / radius) * speed;
+        Some(Burn {
+            index,
+            primary,
+            velocity,
+            delta_v: velocity - bodies.velocities[index],
+            semi_latus: radius * (1.0 + self.eccentricity),
+            periapsis: (offset / radius) * self.eccentricity.signum(),
+            mu,
+        })
+    }
+
+    pub fn describe(&self, bodies: &Bodies, metadata: &Metadata, gravity: f32) -> String {
+        let Some(burn) = self.burn(bodies, gravity) else {
+            return "the bodies of the planned orbit are gone or on top of each other".to_string();
+        };
+        let radius = bodies.positions[burn.index].distance(bodies.positions[burn.primary]);
+        let shape = match self.eccentricity {
+            0.0 => "circular orbit".to_string(),
+            e if e > 0.0 => format!("orbit with eccentricity {:.2} from periapsis", e),
+            e => format!("orbit with eccentricity {:.2} from apoapsis", -e),
+        };
+        let axis = burn.semi_latus / (1.0 - self.eccentricity.powi(2));
+        let period = TAU * (axis.powi(3) / burn.mu).sqrt();
+        format!(
+            "{} of {} around {} at r = {:.1}, period {:.1}: needs delta-v {:.3} (speed {:.3} -> \
+             {:.3}), `orbit apply` to burn",
+            shape,
+            metadata.label(self.body),
+            metadata.label(self.primary),
+            radius,
+            period,
+            burn.delta_v.length(),
+            bodies.velocities[burn.index].length(),
+            burn.velocity.length()
+        )
+    }
+
+    pub fn apply(&self, bodies: &mut Bodies, gravity: f32) -> Result<f32, String> {
+        let burn = self.burn(bodies, gravity).ok_or("the planned orbit no longer exists")?;
+        if bodies.freezed[burn.index] {
+            return Err("the body is frozen".to_string());
+        }
+        bodies.velocities[burn.index] = burn.velocity;
+        // a sleeping body would ignore its new velocity
+        bodies.still_steps[burn.index] = 0;
+        Ok(burn.delta_v.length())
+    }
+
+    // the orbit the burn leads to, and the burn itself as an arrow from the body
+    pub fn draw(&self, bodies: &Bodies, gravity: f32, view: &View) {
+        let Some(burn) = self.burn(bodies, gravity) else {
+            return;
+        };
+        let center = bodies.positions[burn.primary];
+        let eccentricity = self.eccentricity.abs();
+        let points: Vec<Vec2> = (0..=PREVIEW_SEGMENTS)
+            .map(|segment| {
+                let angle = (segment as f32 / PREVIEW_SEGMENTS as f32) * TAU;
+                let distance = burn.semi_latus / (1.0 + eccentricity * angle.cos());
+                let direction = Vec2::from_angle(angle).rotate(burn.periapsis);
+                view.world_to_screen(center + direction * distance)
+            })
+            .collect();
+        for pair in points.windows(2) {
+            draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.0, PREVIEW_COLOR);
+        }
+
+        let position = bodies.positions[burn.index];
+        let from = view.world_to_screen(position);
+        let to = view.world_to_screen(position + burn.delta_v * DELTA_V_SCALE);
+        draw_line(from.x, from.y, to.x, to.y, 2.0, SKYBLUE);
+        draw_circle(to.x, to.y, 3.0, SKYBLUE);
+    }
+}
+
+// the other body with the largest m / r^2
+fn strongest_pull(bodies: &Bodies, index: usize) -> Option<usize> {
+    let position = bodies.positions[index];
+    (0..bodies.len())
+        .filter(|&other| other != index)
+        .map(|other| {
+            let distance = bodies.positions[other].distance_squared(position).max(f32::EPSILON);
+            (other, bodies.masses[other] / distance)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(other, _)| other)
+}
+
+fn find(bodies: &Bodies, id: u64) -> Option<usize> {
+    bodies.ids.iter().position(|&other| other == id)
+}
@@ -0,0 +1,133 @@
+use macroquad::prelude::*;
+use macroquad::ui::{ hash, root_ui, widgets };
+
+use crate::bodies::Bodies;
+use crate::metadata::Metadata;
+use crate::numbers::exact;
+use crate::settings::Settings;
+
+const PANEL_WIDTH: f32 = 300.0;
+const PANEL_HEIGHT: f32 = 300.0;
+
+// the body clicked last, with its numbers as they change and its mass and velocity open to
+// edits. Keys go to the fields while the mouse is over the window, as with the other panels
+#[derive(Default)]
+pub struct Inspector {
+    id: Option<u64>,
+    // the fields as typed, filled in from the body when it's opened
+    mass: String,
+    velocity_x: String,
+    velocity_y: String,
+    // why the last edit wasn't applied
+    error: Option<String>,
+}
+
+impl Inspector {
+    pub fn open(&mut self, bodies: &Bodies, index: usize) {
+        self.id = Some(bodies.ids[index]);
+        self.error = None;
+        self.fill(bodies, index);
+    }
+
+    pub fn close(&mut self) {
+        self.id = None;
+    }
+
+    // whether `position` is over the window, so the mouse and keys belong to it
+    pub fn captures(&self, position: Vec2) -> bool {
+        self.id.is_some() && root_ui().is_mouse_over(position)
+    }
+
+    fn fill(&mut self, bodies: &Bodies, index: usize) {
+        self.mass = exact(bodies.masses[index]);
+        self.velocity_x = exact(bodies.velocities[index].x);
+        self.velocity_y = exact(bodies.velocities[index].y);
+    }
+
+    // lays the window out for this frame, bottom left. Closes once the body is gone
+    pub fn update(&mut self, bodies: &mut Bodies, metadata: &Metadata, settings: &Settings) {
+        let Some(id) = self.id else {
+            return;
+        };
+        let Some(index) = bodies.ids.iter().position(|&other| other == id) else {
+            self.id = None;
+            return;
+        };
+        let body = bodies.body(index);
+        let numbers = settings.numbers;
+        let pair = |vector: Vec2, decimals: usize| {
+            let (x, y) = (numbers.format(vector.x, decimals), numbers.format(vector.y, decimals));
+            format!("({}, {})", x, y)
+        };
+        let force = bodies.forces[index];
+        let kinetic = 0.5 * (body.mass as f64) * (body.velocity.length_squared() as f64);
+        let lines = [
+            format!("position {}", pair(body.position, 1)),
+            format!("velocity {}", pair(body.velocity, 3)),
+            format!("speed {}", numbers.format(body.velocity.length(), 3)),
+            format!(
+                "mass {}  radius {}",
+                numbers.format(body.mass, 2),
+                numbers.format(body.radius, 1)
+            ),
+            format!("net force {}, {}", pair(force, 3), numbers.format(force.length(), 3)),
+            format!("kinetic energy {}", numbers.format(kinetic, 3)),
+        ];
+
+        let position = vec2(10.0, screen_height() - PANEL_HEIGHT - 10.0);
+        let title = format!("inspecting {}", metadata.label(id));
+        let (mut apply, mut reset, mut close) = (false, false, false);
+        widgets::Window::new(hash!(), position, vec2(PANEL_WIDTH, PANEL_HEIGHT))
+            .label(&title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                for line in &lines {
+                    ui.label(None, line);
+                }
+                ui.separator();
+                ui.input_text(hash!(), "mass", &mut self.mass);
+                ui.input_text(hash!(), "vx", &mut self.velocity_x);
+                ui.input_text(hash!(), "vy", &mut self.velocity_y);
+                if let Some(error) = &self.error {
+                    ui.label(None, error);
+                }
+                apply = ui.button(None, "apply");
+                ui.same_line(0.0);
+                reset = ui.button(None, "from body");
+                ui.same_line(0.0);
+                close = ui.button(None, "close");
+            });
+        if apply {
+            self.error = self.apply(bodies, index).err();
+        }
+        if reset {
+            self.error = None;
+            self.fill(bodies, index);
+        }
+        if close {
+            self.close();
+        }
+    }
+
+    // all three fields or none of them
+    fn apply(&self, bodies: &mut Bodies, index: usize) -> Result<(), String> {
+        let field = |name: &str, text: &str| {
+            text.trim()
+                .replace(',', ".")
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("invalid {} `{}`", name, text.trim()))
+        };
+        let mass = field("mass", &self.mass)?;
+        if mass <= 0.0 {
+            return Err("the mass has to be positive".to_string());
+        }
+        let velocity = vec2(field("vx", &self.velocity_x)?, field("vy", &self.velocity_y)?);
+        bodies.masses[index] = mass;
+        bodies.velocities[index] = velocity;
+        // a sleeping body would ignore the new velocity
+        bodies.still_steps[index] = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+use crate::solver;
+
+// Yoshida's (1990) fourth order composition of three leapfrog steps, the middle one
+// backwards: w1 = 1 / (2 - 2^(1/3)) and w0 = 1 - 2 w1
+const YOSHIDA_W1: f32 = 1.351_207_2;
+const YOSHIDA_W0: f32 = -1.702_414_4;
+
+// drift, kick, drift, ... fractions of the step, always starting and ending with a drift
+const LEAPFROG: [f32; 3] = [0.5, 1.0, 0.5];
+const YOSHIDA: [f32; 7] = [
+    YOSHIDA_W1 / 2.0,
+    YOSHIDA_W1,
+    (YOSHIDA_W0 + YOSHIDA_W1) / 2.0,
+    YOSHIDA_W0,
+    (YOSHIDA_W0 + YOSHIDA_W1) / 2.0,
+    YOSHIDA_W1,
+    YOSHIDA_W1 / 2.0,
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Integrator {
+    // semi-implicit Euler: kick with the forces of this frame, then drift. First order
+    Euler,
+    // drift-kick-drift, second order and time reversible
+    Leapfrog,
+    // fourth order for three force evaluations a step, holds energy far better at large dt
+    Yoshida,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 3] = [Integrator::Euler, Integrator::Leapfrog, Integrator::Yoshida];
+
+    pub fn next(self) -> Self {
+        match self {
+            Integrator::Euler => Integrator::Leapfrog,
+            Integrator::Leapfrog => Integrator::Yoshida,
+            Integrator::Yoshida => Integrator::Euler,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::Euler => "euler",
+            Integrator::Leapfrog => "leapfrog",
+            Integrator::Yoshida => "yoshida",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Integrator::ALL.into_iter().find(|integrator| integrator.name() == name)
+    }
+}
+
+// moves every body forward by `settings.dt`, in `settings.substeps` equal steps. Euler uses
+// the forces already in `Bodies::forces`, the others evaluate their own at every kick, on the
+// CPU whatever the solver, and leave the last ones behind. Every substep after the first
+// evaluates the forces again before it starts
+pub fn advance(bodies: &mut Bodies, settings: &Settings) {
+    if settings.substeps <= 1 {
+        substep(bodies, settings);
+        return;
+    }
+    let settings = Settings { dt: settings.dt / (settings.substeps as f32), ..settings.clone() };
+    for substep_index in 0..settings.substeps {
+        if substep_index > 0 {
+            solver::compute_forces(bodies, &settings);
+        }
+        substep(bodies, &settings);
+    }
+}
+
+fn substep(bodies: &mut Bodies, settings: &Settings) {
+    let dt = settings.dt;
+    let speed_limit = settings.hacks.velocity_clamp.then_some(settings.max_velocity);
+    let fractions: &[f32] = match settings.integrator {
+        Integrator::Euler => {
+            kick(bodies, dt, speed_limit);
+            drift(bodies, dt);
+            return;
+        }
+        Integrator::Leapfrog => &LEAPFROG,
+        Integrator::Yoshida => &YOSHIDA,
+    };
+
+    for (stage, &fraction) in fractions.iter().enumerate() {
+        if stage % 2 == 1 {
+            solver::compute_forces(bodies, settings);
+            kick(bodies, fraction * dt, speed_limit);
+        } else {
+            drift(bodies, fraction * dt);
+        }
+    }
+}
+
+// turns the forces into accelerations and applies them for `dt`
+fn kick(bodies: &mut Bodies, dt: f32, speed_limit: Option<f32>) {
+    for i in 0..bodies.len() {
+        if !bodies.is_active(i) {
+            continue;
+        }
+        bodies.accelerations[i] = bodies.forces[i] / bodies.masses[i];
+        let mut velocity = bodies.velocities[i] + bodies.accelerations[i] * dt;
+        // Limit the velocity to prevent the simulation from exploding
+        if let Some(limit) = speed_limit {
+            if velocity.length() > limit {
+                velocity = velocity.normalize() * limit;
+            }
+        }
+        bodies.velocities[i] = velocity;
+    }
+}
+
+fn drift(bodies: &mut Bodies, dt: f32) {
+    for i in 0..bodies.len() {
+        if bodies.is_active(i) {
+            bodies.positions[i] += bodies.velocities[i] * dt;
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::metadata::{ Metadata, Value };
+use crate::theme::Theme;
+use crate::view::View;
+
+const FONT_SIZE: u16 = 16;
+// bodies smaller than this on screen are too far zoomed out to be worth a label, unless
+// they're selected
+const MIN_RADIUS: f32 = 1.5;
+// past this many the screen is more text than bodies
+const MAX_LABELS: usize = 64;
+// between a body's rim and its label, and the space kept clear around each label
+const GAP: f32 = 3.0;
+
+// the names of the bodies that have one, from the "name" tag, written beside them. Zoomed
+// out they'd pile up on each other, so selected bodies and then the heaviest are placed
+// first and a label that would overlap one already placed is left out
+pub struct Labels {
+    pub visible: bool,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Labels { visible: true }
+    }
+}
+
+impl Labels {
+    pub fn draw(&self, bodies: &Bodies, metadata: &Metadata, view: &View, theme: &Theme) {
+        if !self.visible {
+            return;
+        }
+        let mut named: Vec<(usize, &str)> = (0..bodies.len())
+            .filter_map(|i| {
+                match metadata.get(bodies.ids[i], "name") {
+                    Some(Value::Text(name)) if !name.is_empty() => Some((i, name.as_str())),
+                    _ => None,
+                }
+            })
+            .collect();
+        named.sort_by(|&(a, _), &(b, _)| {
+            bodies.selected[b]
+                .cmp(&bodies.selected[a])
+                .then(bodies.masses[b].total_cmp(&bodies.masses[a]))
+        });
+
+        // set off from whatever is behind them by a shadow in the background's color
+        let shadow = Color { a: 0.7, ..theme.background };
+        let screen = Rect::new(0.0, 0.0, screen_width(), screen_height());
+        let mut placed: Vec<Rect> = Vec::new();
+        for (i, name) in named {
+            if placed.len() == MAX_LABELS {
+                break;
+            }
+            let (position, scale) = view.project(&bodies.body(i));
+            let radius = bodies.radii[i] * scale;
+            if radius < MIN_RADIUS && !bodies.selected[i] {
+                continue;
+            }
+            // to the right of the body, level with its middle
+            let size = measure_text(name, None, FONT_SIZE, 1.0);
+            let left = position.x + radius + GAP;
+            let baseline = position.y + size.offset_y / 2.0;
+            let rect = Rect::new(left, baseline - size.offset_y, size.width, size.height);
+            let padded = Rect::new(
+                rect.x - GAP,
+                rect.y - GAP,
+                rect.w + 2.0 * GAP,
+                rect.h + 2.0 * GAP
+            );
+            if !screen.overlaps(&rect) || placed.iter().any(|other| other.overlaps(&padded)) {
+                continue;
+            }
+            placed.push(rect);
+            let color = if bodies.selected[i] { theme.accent } else { theme.text };
+            let font_size = FONT_SIZE as f32;
+            draw_text(name, left + 1.0, baseline + 1.0, font_size, shadow);
+            draw_text(name, left, baseline, font_size, color);
+        }
+    }
+}
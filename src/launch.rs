@@ -0,0 +1,56 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::numbers::Numbers;
+use crate::prediction;
+use crate::settings::Settings;
+use crate::view::{ self, View };
+use crate::Body;
+
+const ARROW_COLOR: Color = LIME;
+const GHOST_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.5);
+
+// a body being launched by dragging the left mouse button from empty space: it starts where
+// the button went down and leaves along the drag when the button comes up, as fast as the
+// velocity arrows would draw that long, see `Settings::velocity_scale`. A click without a
+// drag leaves it at rest
+pub struct Launch {
+    // not among the bodies until it's let go
+    pub body: Body,
+}
+
+impl Launch {
+    pub fn new(position: Vec2) -> Self {
+        let mut body = Body::random(Some(position));
+        body.velocity = Vec2::ZERO;
+        Launch { body }
+    }
+
+    // towards `mouse`, in the world
+    pub fn aim(&mut self, mouse: Vec2, settings: &Settings) {
+        let mut velocity = (mouse - self.body.position) / settings.velocity_scale.max(0.01);
+        if settings.hacks.velocity_clamp {
+            velocity = velocity.clamp_length_max(settings.max_velocity);
+        }
+        self.body.velocity = velocity;
+    }
+
+    // where it would go if let go now, in a copy of the bodies with it among them
+    pub fn path(&self, bodies: &Bodies, settings: &Settings) -> Vec<Vec2> {
+        let mut with = bodies.clone();
+        with.push(self.body);
+        prediction::path(&with, with.len() - 1, settings)
+    }
+
+    pub fn draw(&self, view: &View, velocity_scale: f32, numbers: Numbers, text_color: Color) {
+        let (position, scale) = view.project(&self.body);
+        draw_circle_lines(position.x, position.y, self.body.radius * scale, 1.0, GHOST_COLOR);
+        if self.body.velocity == Vec2::ZERO {
+            return;
+        }
+        let tip = view.world_to_screen(self.body.position + self.body.velocity * velocity_scale);
+        view::draw_arrow(position, tip, 2.0, ARROW_COLOR);
+        let speed = format!("speed {}", numbers.format(self.body.velocity.length() as f64, 3));
+        draw_text(&speed, tip.x + 8.0, tip.y - 8.0, 18.0, text_color);
+    }
+}
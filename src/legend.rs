@@ -0,0 +1,153 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::coloring::{ ColorMode, Palette };
+use crate::settings::Settings;
+use crate::view::View;
+use crate::{ density, heatmap };
+
+const WIDTH: f32 = 250.0;
+const BAR_HEIGHT: f32 = 10.0;
+// color samples along a bar
+const STOPS: usize = 40;
+// the largest circle the size row draws, bigger bodies are only written down
+const MAX_CIRCLE: f32 = 14.0;
+
+// one color scale: what it measures and its value at either end and halfway, where the
+// log scales aren't halfway in value
+struct Scale {
+    title: String,
+    labels: [String; 3],
+    color: Box<dyn Fn(f32) -> Color>,
+}
+
+// what the colors and sizes on screen stand for, bottom right. Only the scales in use are
+// listed, so it disappears with plain bodies and no maps
+pub struct Legend {
+    pub visible: bool,
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Legend { visible: true }
+    }
+}
+
+impl Legend {
+    pub fn draw(
+        &self,
+        bodies: &Bodies,
+        palette: &Palette,
+        density: Option<u32>,
+        collisions: Option<u32>,
+        settings: &Settings,
+        view: &View
+    ) {
+        if !self.visible || bodies.is_empty() {
+            return;
+        }
+        let mut scales = Vec::new();
+        if let Some(scale) = palette_scale(palette, settings) {
+            scales.push(scale);
+        }
+        if let Some(max) = density.filter(|&max| max > 0) {
+            scales.push(Scale {
+                title: format!("bodies per {} px of screen", density::CELL_SIZE),
+                labels: count_labels(max),
+                color: Box::new(density::shade),
+            });
+        }
+        if let Some(max) = collisions {
+            scales.push(Scale {
+                title: format!("collisions per {} px cell", heatmap::CELL_SIZE),
+                labels: count_labels(max),
+                color: Box::new(heatmap::heat_color),
+            });
+        }
+        if scales.is_empty() {
+            return;
+        }
+
+        let (smallest, largest) = bodies.radii
+            .iter()
+            .fold((f32::INFINITY, 0.0f32), |(low, high), &radius| {
+                (low.min(radius), high.max(radius))
+            });
+        let sized = largest > smallest;
+        let height = 50.0 * (scales.len() as f32) + if sized { 45.0 } else { 0.0 } + 10.0;
+        let (x, mut y) = (screen_width() - WIDTH - 10.0, screen_height() - height - 10.0);
+        draw_rectangle(x, y, WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.6));
+        let (left, right) = (x + 10.0, x + WIDTH - 10.0);
+        for scale in &scales {
+            y += 20.0;
+            draw_text(&scale.title, left, y, 18.0, WHITE);
+            y += 6.0;
+            let step = (right - left) / (STOPS as f32);
+            for stop in 0..STOPS {
+                let t = ((stop as f32) + 0.5) / (STOPS as f32);
+                let from = left + (stop as f32) * step;
+                draw_rectangle(from, y, step + 0.5, BAR_HEIGHT, (scale.color)(t));
+            }
+            y += BAR_HEIGHT + 14.0;
+            let [low, middle, high] = &scale.labels;
+            draw_text(low, left, y, 16.0, LIGHTGRAY);
+            let middle_width = measure_text(middle, None, 16, 1.0).width;
+            draw_text(middle, (left + right - middle_width) / 2.0, y, 16.0, LIGHTGRAY);
+            let high_width = measure_text(high, None, 16, 1.0).width;
+            draw_text(high, right - high_width, y, 16.0, LIGHTGRAY);
+        }
+
+        // radii don't follow mass, they're only worth a row where they differ
+        if sized {
+            y += 20.0;
+            draw_text("radius in world px", left, y, 18.0, WHITE);
+            let center = y + 4.0 + MAX_CIRCLE / 2.0;
+            let mut from = left;
+            for radius in [smallest, largest] {
+                let drawn = (radius * view.scale()).min(MAX_CIRCLE / 2.0);
+                draw_circle_lines(from + drawn, center, drawn, 1.0, LIGHTGRAY);
+                let label = settings.numbers.format(radius, 1);
+                draw_text(&label, from + 2.0 * drawn + 6.0, center + 5.0, 16.0, LIGHTGRAY);
+                from += 2.0 * drawn + 6.0 + measure_text(&label, None, 16, 1.0).width + 20.0;
+            }
+        }
+    }
+}
+
+fn palette_scale(palette: &Palette, settings: &Settings) -> Option<Scale> {
+    let numbers = settings.numbers;
+    let (title, labels) = match palette.mode {
+        ColorMode::Plain => {
+            return None;
+        }
+        ColorMode::Speed => {
+            let middle = (palette.low + palette.high) / 2.0;
+            let labels = [palette.low, middle, palette.high].map(|speed| numbers.format(speed, 2));
+            (String::from("speed in px per time unit"), labels)
+        }
+        ColorMode::Mass => {
+            // the palette works on the log of the mass, halfway is the geometric mean
+            let middle = (palette.low + palette.high) / 2.0;
+            let masses = [palette.low, middle, palette.high].map(f32::exp);
+            match settings.scale {
+                Some(scale) => {
+                    let sun = scale.central_mass(settings.gravity);
+                    let labels = masses.map(|mass| numbers.format(mass / sun, 2));
+                    (String::from("mass in suns, log scale"), labels)
+                }
+                None => {
+                    let labels = masses.map(|mass| numbers.format(mass, 2));
+                    (String::from("mass, log scale"), labels)
+                }
+            }
+        }
+    };
+    let palette = Palette { mode: palette.mode, low: 0.0, high: 1.0 };
+    Some(Scale { title, labels, color: Box::new(move |t| palette.gradient(t)) })
+}
+
+// from a single one to the most on the same log scale the maps shade with
+fn count_labels(max: u32) -> [String; 3] {
+    let middle = ((max as f32).ln_1p() / 2.0).exp_m1();
+    [String::from("1"), format!("{:.0}", middle.max(1.0)), format!("{}", max)]
+}
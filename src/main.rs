@@ -1,14 +1,181 @@
+use std::fs;
+use std::sync::atomic::{ AtomicU64, Ordering };
+
+use macroquad::miniquad::date;
 use macroquad::prelude::*;
 
+mod accessibility;
+mod assist;
+mod barycenter;
+mod batch;
+mod bench;
+mod blackhole;
+mod bodies;
+mod broadphase;
+mod brush;
+mod ccd;
+mod checkpoints;
+mod coloring;
+mod commands;
+mod compare;
+mod console;
+mod contacts;
+mod cosim;
+mod deadline;
+mod density;
+mod diagnostics;
+mod drag;
+mod embed;
+mod energyplot;
+mod ewald;
+mod export;
+mod field;
+mod formula;
+mod fmm;
+mod freeze;
+mod gallery;
+mod generators;
+mod gif;
+mod glow;
+mod gpu;
+mod grid;
+mod hardware;
+mod heatmap;
+mod insertion;
+mod inspector;
+mod integrator;
+mod json;
+mod labels;
+mod launch;
+mod legend;
+mod marquee;
+mod minimap;
+mod metadata;
+mod noise;
+mod npy;
+mod numbers;
+mod panel;
+mod perf;
+mod prediction;
+mod presets;
+mod provenance;
+mod query;
+mod quiz;
+mod quadtree;
+mod recording;
+mod resonance;
+mod rewind;
+mod rng;
+mod scenario;
+mod schedule;
+mod screenshot;
+mod separation;
+mod settings;
+mod simd;
+mod sleep;
+mod soak;
+mod starfield;
+mod solver;
+mod units;
+mod sonify;
+mod style;
+mod subcluster;
+mod theme;
+mod tide;
+mod tipsy;
+mod trails;
+mod treeview;
+mod video;
+mod view;
+mod watchdog;
+mod window;
+
+use accessibility::{ Announcer, KeyboardCursor, CURSOR_FAST, CURSOR_SPEED };
+use assist::Plan;
+use barycenter::Barycenters;
+use batch::BodyBatch;
+use bodies::Bodies;
+use brush::Brush;
+use checkpoints::Checkpoints;
+use coloring::Palette;
+use console::Console;
+use contacts::{ ContactStats, Impact };
+use cosim::CoSimulation;
+use deadline::Deadline;
+use density::{ DensityMap, RenderMode };
+use diagnostics::{ DiagnosticsLog, EnergyTracker, Sample };
+use drag::Drag;
+use energyplot::EnergyPlot;
+use formula::FormulaOverlay;
+use freeze::FreezeGroups;
+use gallery::Gallery;
+use glow::Glow;
+use gpu::GpuSolver;
+use grid::WorldGrid;
+use hardware::{ Capabilities, Splash };
+use heatmap::CollisionHeatmap;
+use labels::Labels;
+use launch::Launch;
+use legend::Legend;
+use insertion::Insertion;
+use inspector::Inspector;
+use marquee::Marquee;
+use minimap::Minimap;
+use metadata::{ Metadata, Value };
+use noise::Noise;
+use numbers::Separator;
+use panel::ControlPanel;
+use perf::PerfGraph;
+use presets::Preset;
+use provenance::Provenance;
+use quiz::Quiz;
+use recording::Recorder;
+use resonance::ResonanceDetector;
+use rewind::RewindBuffer;
+use schedule::Schedules;
+use settings::{ Hacks, Settings, MAX_THETA, MIN_THETA };
+use solver::Solver;
+use sonify::Sonifier;
+use starfield::Starfield;
+use style::BodyStyle;
+use subcluster::Subcluster;
+use trails::Trails;
+use treeview::TreeOverlay;
+use video::Video;
+use view::View;
+use watchdog::{ Verdict, Watchdog };
+use window::WindowConfig;
+
 const G: f32 = 1.0;
 const NUM_OF_BODIES: usize = 1000;
 const SCREEN_WIDTH: f32 = 800.0;
 const SCREEN_HEIGHT: f32 = 600.0;
 const FRICTION: f32 = 0.99;
 const MAX_VELOCITY: f32 = 20.0;
+// six times the speed limit: close orbits precess by degrees a turn while the correction
+// stays a few percent of Newtonian gravity
+const LIGHT_SPEED: f32 = 120.0;
+const DT: f32 = 0.5;
+const FREEZE_GROUP_DELAY: f32 = 100.0;
+const ROTATION_STEP: f32 = 0.002;
+// pixels the mouse has to move with the middle button down before it pans instead of clicking
+const PAN_THRESHOLD: f32 = 4.0;
+const THETA_STEP: f32 = 0.05;
+const SOAK_STEPS: usize = 100_000;
+// frame time while paused or minimized, about 20 fps is plenty for a still picture
+#[cfg(not(target_arch = "wasm32"))]
+const IDLE_FRAME: std::time::Duration = std::time::Duration::from_millis(50);
+
+static NEXT_BODY_ID: AtomicU64 = AtomicU64::new(0);
 
+fn next_body_id() -> u64 {
+    NEXT_BODY_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+// a single body, the simulation itself keeps them column-wise in `bodies::Bodies`
 #[derive(Clone, Copy, PartialEq, Debug)]
-struct Body {
+pub struct Body {
+    id: u64,
     position: Vec2,
     velocity: Vec2,
     acceleration: Vec2,
@@ -16,11 +183,22 @@ struct Body {
     mass: f32,
     radius: f32,
     freezed: bool,
+    selected: bool,
+    freeze_group: Option<usize>,
+    // purely visual depth in [-1, 1] for the 2.5D view, never touches the physics
+    depth: f32,
+    // swallows whatever crosses its horizon instead of colliding, see `blackhole`
+    black_hole: bool,
+    // pushed on from outside the simulation on top of gravity, set anew every frame
+    external_force: Vec2,
+    // how long it has been still, asleep from `sleep::SLEEP_STEPS` on
+    still_steps: u32,
 }
 
 impl Body {
     fn new(initial_pos: Vec2) -> Self {
         Body {
+            id: next_body_id(),
             position: initial_pos,
             velocity: Vec2::ZERO,
             acceleration: Vec2::ZERO,
@@ -28,21 +206,28 @@ impl Body {
             mass: 1000.0,
             radius: 10.0,
             freezed: false,
+            selected: false,
+            freeze_group: None,
+            depth: 0.0,
+            black_hole: false,
+            external_force: Vec2::ZERO,
+            still_steps: 0,
         }
     }
 
     fn random(initial_pos: Option<Vec2>) -> Self {
         let position = initial_pos.unwrap_or(
-            vec2(rand::gen_range(0.0, SCREEN_WIDTH), rand::gen_range(0.0, SCREEN_HEIGHT))
+            vec2(rng::gen_range(0.0, SCREEN_WIDTH), rng::gen_range(0.0, SCREEN_HEIGHT))
         );
         let velocity = vec2(
-            rand::gen_range(-2.0, 2.0), // Random initial velocities
-            rand::gen_range(-2.0, 2.0)
+            rng::gen_range(-2.0, 2.0), // Random initial velocities
+            rng::gen_range(-2.0, 2.0)
         );
-        let mass = rand::gen_range(500.0, 1500.0); // Random mass between 500 and 1500
+        let mass = rng::gen_range(500.0, 1500.0); // Random mass between 500 and 1500
         let radius = mass / 209.0;
 
         Body {
+            id: next_body_id(),
             position,
             velocity,
             acceleration: Vec2::ZERO,
@@ -50,6 +235,12 @@ impl Body {
             mass,
             radius,
             freezed: false,
+            selected: false,
+            freeze_group: None,
+            depth: rng::gen_range(-1.0, 1.0),
+            black_hole: false,
+            external_force: Vec2::ZERO,
+            still_steps: 0,
         }
     }
 
@@ -60,152 +251,1445 @@ impl Body {
         (x.powi(2) + y.powi(2)).sqrt()
     }
 
-    fn calculate_force(&self, other_body: &Self) -> Vec2 {
-        let distance = self.get_distance(other_body);
-        if distance < 2.0 * self.radius {
-            // Adjust to avoid division by zero in force calculation
-            return Vec2::ZERO; // Collision detected, no force applied
-        }
+    pub fn update_position(&mut self, dt: f32) {
+        self.position += self.velocity * dt;
+    }
 
-        let numer = self.mass * other_body.mass;
-        let denom = distance.powi(2);
-        let magnitude = G * (numer / denom);
+    // how close the centers get before the collision response kicks in
+    pub fn contact_distance(&self, other_body: &Body, hacks: &Hacks) -> f32 {
+        if hacks.velocity_swap {
+            2.0 * self.radius.max(other_body.radius)
+        } else {
+            self.radius + other_body.radius
+        }
+    }
 
-        // separate into directions
-        let x_dir = magnitude * ((other_body.position.x - self.position.x) / distance);
-        let y_dir = magnitude * ((other_body.position.y - self.position.y) / distance);
+    pub fn check_and_resolve_collision(
+        &mut self,
+        other_body: &mut Body,
+        settings: &Settings
+    ) -> bool {
+        let hacks = &settings.hacks;
+        let friction = if hacks.collision_friction { settings.restitution } else { 1.0 };
+        if hacks.velocity_swap {
+            if
+                self.get_distance(other_body) < 2.0 * self.radius ||
+                self.get_distance(other_body) < 2.0 * other_body.radius
+            {
+                let temp_velocity = self.velocity;
+                self.velocity = other_body.velocity * friction;
+                other_body.velocity = temp_velocity * friction;
+                return true;
+            }
+            return false;
+        }
 
-        vec2(x_dir, y_dir)
+        // an impulse along the line between the centers, only while the bodies close in so
+        // overlapping ones don't bounce back and forth
+        let offset = other_body.position - self.position;
+        let distance = offset.length();
+        if distance >= self.radius + other_body.radius || distance == 0.0 {
+            return false;
+        }
+        let normal = offset / distance;
+        let closing = (self.velocity - other_body.velocity).dot(normal);
+        if closing <= 0.0 {
+            return false;
+        }
+        let impulse = ((1.0 + friction) * closing) / (1.0 / self.mass + 1.0 / other_body.mass);
+        self.velocity -= normal * (impulse / self.mass);
+        other_body.velocity += normal * (impulse / other_body.mass);
+        true
     }
 
-    pub fn update_force(&mut self, other_body: &Self) {
-        self.force = self.calculate_force(other_body);
+    pub fn is_sleeping(&self) -> bool {
+        self.still_steps >= sleep::SLEEP_STEPS
     }
 
-    pub fn update_acceleration(&mut self) {
-        self.acceleration.x = self.force.x / self.mass;
-        self.acceleration.y = self.force.y / self.mass;
+    // neither frozen nor asleep, so it gets integrated
+    pub fn is_active(&self) -> bool {
+        !self.freezed && !self.is_sleeping()
     }
+}
+
+fn spawn(bodies: &mut Bodies, metadata: &mut Metadata, body: Body, time: f32) -> u64 {
+    metadata.set(body.id, "spawned_at", Value::Number(time as f64));
+    bodies.push(body);
+    body.id
+}
 
-    pub fn update_velocity(&mut self, dt: f32) {
-        self.velocity += self.acceleration * dt;
+// resolves collisions and moves every body forward by `settings.dt`, the forces have to be
+// computed already. Returns the collisions
+fn step(bodies: &mut Bodies, settings: &Settings) -> Vec<Impact> {
+    // the separation pass does without collisions altogether
+    if settings.separate {
+        integrator::advance(bodies, settings);
+        separation::enforce(bodies, settings);
+        apply_boundaries(bodies, settings);
+        sleep::update(bodies, &settings.hacks);
+        return Vec::new();
+    }
 
-        // Limit the velocity to prevent the simulation from exploding
-        if self.velocity.length() > MAX_VELOCITY {
-            self.velocity = self.velocity.normalize() * MAX_VELOCITY;
+    let mut collisions = Vec::new();
+    for (i, j) in broadphase::candidate_pairs(settings.broadphase, bodies) {
+        // nothing bounces off a black hole, it falls in
+        if bodies.black_holes[i] || bodies.black_holes[j] {
+            continue;
+        }
+        let (mut body, mut other_body) = (bodies.body(i), bodies.body(j));
+        let before = (bodies.body(i), bodies.body(j));
+        if body.check_and_resolve_collision(&mut other_body, settings) {
+            let impact = Impact::between((i, j), (&before.0, &before.1), (&body, &other_body));
+            collisions.push(impact);
+            bodies.set(i, body);
+            bodies.set(j, other_body);
         }
     }
 
-    pub fn update_position(&mut self, dt: f32) {
-        self.position += self.velocity * dt;
+    let start = bodies.positions.clone();
+    integrator::advance(bodies, settings);
+    let mut hits = vec![0; bodies.len()];
+    for impact in &collisions {
+        hits[impact.pair.0] += 1;
+        hits[impact.pair.1] += 1;
+    }
+    for impact in &mut collisions {
+        impact.settle(bodies, &hits);
     }
+    if settings.ccd {
+        collisions.extend(ccd::resolve(bodies, &start, settings));
+    }
+    apply_boundaries(bodies, settings);
+    sleep::update(bodies, &settings.hacks);
+    collisions
+}
 
-    pub fn check_and_resolve_collision(&mut self, other_body: &mut Body) {
-        if
-            self.get_distance(other_body) < 2.0 * self.radius ||
-            self.get_distance(other_body) < 2.0 * other_body.radius
-        {
-            let temp_velocity = self.velocity;
-            self.velocity = other_body.velocity * FRICTION;
-            other_body.velocity = temp_velocity * FRICTION;
+// bounces off the walls, or wraps around them in a periodic world. Bodies touching across
+// the seam don't collide, only their gravity reaches through
+fn apply_boundaries(bodies: &mut Bodies, settings: &Settings) {
+    if settings.periodic {
+        // on a torus whatever leaves one side comes back in on the other
+        for position in &mut bodies.positions {
+            position.x = position.x.rem_euclid(SCREEN_WIDTH);
+            position.y = position.y.rem_euclid(SCREEN_HEIGHT);
         }
+        return;
     }
-
-    pub fn check_boundary_collisions(&mut self) {
-        // Check for collision with the left or right boundary
-        if self.position.x <= self.radius || self.position.x >= SCREEN_WIDTH - self.radius {
-            self.velocity.x = -self.velocity.x * FRICTION;
-            self.position.x = self.position.x.clamp(self.radius, SCREEN_WIDTH - self.radius);
+    let friction = if settings.hacks.wall_loss { settings.wall_friction } else { 1.0 };
+    let size = vec2(SCREEN_WIDTH, SCREEN_HEIGHT);
+    for i in 0..bodies.len() {
+        let radius = bodies.radii[i];
+        let position = &mut bodies.positions[i];
+        let velocity = &mut bodies.velocities[i];
+        // Check for collision with the left or right boundary, then the top or bottom one
+        for axis in 0..2 {
+            if position[axis] <= radius || position[axis] >= size[axis] - radius {
+                velocity[axis] = -velocity[axis] * friction;
+                position[axis] = position[axis].clamp(radius, size[axis] - radius);
+            }
         }
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on { "on" } else { "off" }
+}
 
-        // Check for collision with the top or bottom boundary
-        if self.position.y <= self.radius || self.position.y >= SCREEN_HEIGHT - self.radius {
-            self.velocity.y = -self.velocity.y * FRICTION;
-            self.position.y = self.position.y.clamp(self.radius, SCREEN_HEIGHT - self.radius);
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("bench") {
+        let count = args
+            .get(2)
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(NUM_OF_BODIES);
+        let seed = args
+            .get(3)
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or(0);
+        bench::run(count, seed);
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("soak") {
+        let steps = args
+            .get(2)
+            .and_then(|steps| steps.parse().ok())
+            .unwrap_or(SOAK_STEPS);
+        let seed = args
+            .get(3)
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(|| (date::now() * 1e9) as u64);
+        soak::run(steps, seed);
+        return;
+    }
+    let words: Vec<&str> = args.iter().skip(1).map(String::as_str).collect();
+    if let ["analyze", "info", path] = words[..] {
+        provenance::print_info(path);
+        return;
+    }
+    if let ["analyze", "compare", a, b, ref rest @ ..] = words[..] {
+        let prefix = rest.first().copied().unwrap_or("compare");
+        match compare::run(a, b, prefix) {
+            Ok(report) => println!("{}", report),
+            Err(err) => eprintln!("{}", err),
         }
+        return;
     }
 
-    pub fn update(&mut self, dt: f32) {
-        if !self.freezed {
-            self.update_acceleration();
-            self.update_velocity(dt);
-            self.update_position(dt);
+    // console commands to run before the first frame, same syntax as the in-app console
+    let mut startup = Vec::new();
+    let mut seed = None;
+    let mut cosim = None;
+    let mut deadline = None;
+    let mut then = None;
+    let mut window = WindowConfig::default();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        // the options without a value
+        if arg == "--fullscreen" {
+            window.fullscreen = true;
+            continue;
+        }
+        if arg == "--low-dpi" {
+            window.high_dpi = false;
+            continue;
+        }
+        match (arg.as_str(), args.next()) {
+            ("--exec", Some(command)) => startup.push(command),
+            ("--script", Some(path)) => startup.push(format!("exec {}", path)),
+            ("--theme", Some(theme)) => startup.push(format!("theme {}", theme)),
+            ("--scenario", Some(path)) => {
+                let commands = fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| scenario::commands(&text));
+                match commands {
+                    Ok(commands) => startup.extend(commands),
+                    Err(err) => {
+                        eprintln!("{}: {}", path, err);
+                        return;
+                    }
+                }
+            }
+            ("--cosim", Some(command)) => {
+                match CoSimulation::spawn(&command) {
+                    Ok(connected) => cosim = Some(connected),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                }
+            }
+            ("--cosim-tcp", Some(address)) => {
+                match CoSimulation::connect(&address) {
+                    Ok(connected) => cosim = Some(connected),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                }
+            }
+            ("--seed", Some(value)) if value.parse::<u64>().is_ok() => {
+                seed = value.parse().ok();
+            }
+            ("--run-for", Some(duration)) => {
+                match Deadline::parse(&duration) {
+                    Ok(parsed) => deadline = Some(parsed),
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                }
+            }
+            ("--then", Some(paths)) => then = Some(paths),
+            ("--window", Some(size)) => {
+                if let Err(err) = window.set_size(&size) {
+                    eprintln!("{}", err);
+                    return;
+                }
+            }
+            ("--msaa", Some(samples)) => {
+                if let Err(err) = window.set_samples(&samples) {
+                    eprintln!("{}", err);
+                    return;
+                }
+            }
+            _ => {
+                eprintln!(
+                    "usage: threebody [bench [bodies] [seed] | soak [steps] [seed] | \
+                     analyze info <file> | analyze compare <a.tbr> <b.tbr> [prefix]] \
+                     [--exec <command>] [--script <file>] [--scenario <file>] [--seed <n>] \
+                     [--cosim <command> | --cosim-tcp <address>] \
+                     [--run-for <duration> [--then <file.csv>,<file.png>]] \
+                     [--window <width>x<height>] [--fullscreen] [--msaa <samples>] \
+                     [--low-dpi] [--theme <name | file.json>]"
+                );
+                return;
+            }
+        }
+    }
+    if let Some(paths) = then {
+        let Some(deadline) = deadline.as_mut() else {
+            eprintln!("--then needs --run-for");
+            return;
+        };
+        if let Err(err) = deadline.then(&paths) {
+            eprintln!("{}", err);
+            return;
         }
     }
+
+    let fullscreen = window.fullscreen;
+    macroquad::Window::from_config(
+        window.conf(),
+        run(startup, seed, cosim, deadline, fullscreen)
+    );
 }
 
-#[macroquad::main("threebody")]
-async fn main() {
-    let mut bodies: Vec<Body> = Vec::with_capacity(NUM_OF_BODIES);
-    for _ in 0..NUM_OF_BODIES {
-        bodies.push(Body::random(None));
+async fn run(
+    startup: Vec<String>,
+    seed: Option<u64>,
+    mut cosim: Option<CoSimulation>,
+    mut deadline: Option<Deadline>,
+    // miniquad switches in and out of fullscreen but can't tell which it's in
+    mut fullscreen: bool
+) {
+    let mut bodies = Bodies::with_capacity(NUM_OF_BODIES);
+    // a fresh seed per run unless one was given, it ends up in every export either way.
+    // miniquad's clock because `SystemTime` isn't available in the browser
+    let seed = seed.unwrap_or_else(|| (date::now() * 1e9) as u64);
+    let mut settings = Settings { seed, ..Settings::default() };
+    settings.numbers.separator = Separator::from_locale();
+    let mut gpu = GpuSolver::new();
+    let mut glow = Glow::new();
+    let mut batch = BodyBatch::new();
+    // defaults the machine can keep up with, startup commands can still override them
+    let capabilities = Capabilities::detect(gpu.is_some());
+    capabilities.choose(&mut settings);
+    let report = capabilities.report(&settings);
+    for line in &report {
+        println!("{}", line);
+    }
+    let mut splash = Splash::new(report);
+    let mut freeze_groups = FreezeGroups::default();
+    let mut metadata = Metadata::default();
+    let mut drag = Drag::default();
+    let mut view = View::default();
+    let mut brush = Brush::default();
+    let mut energy = EnergyTracker::default();
+    let mut energy_plot = EnergyPlot::default();
+    let mut diagnostics_log = DiagnosticsLog::default();
+    let mut console = Console::default();
+    let mut heatmap = CollisionHeatmap::default();
+    let mut contact_stats = ContactStats::default();
+    let mut trails = Trails::default();
+    let mut panel = ControlPanel::default();
+    let mut formula = FormulaOverlay::default();
+    let mut tree_overlay = TreeOverlay::default();
+    let mut legend = Legend::default();
+    let mut grid = WorldGrid::default();
+    let mut inspector = Inspector::default();
+    let mut marquee = Marquee::default();
+    let mut minimap = Minimap::default();
+    let mut density_map = DensityMap::default();
+    let mut sonifier = Sonifier::new().await;
+    let mut cursor = KeyboardCursor::default();
+    let mut announcer = Announcer::default();
+    let mut rewind = RewindBuffer::default();
+    let mut resonances = ResonanceDetector::default();
+    let mut subcluster = Subcluster::default();
+    let mut barycenters = Barycenters::default();
+    let mut labels = Labels::default();
+    let starfield = Starfield::default();
+    let mut perf_graph = PerfGraph::default();
+    let mut schedules = Schedules::default();
+    let mut recorder: Option<Recorder> = None;
+    let mut video: Option<Video> = None;
+    let mut checkpoints = Checkpoints::default();
+    let mut insertion: Option<Insertion> = None;
+    let mut assist: Option<Plan> = None;
+    let mut launch: Option<Launch> = None;
+    let mut quiz = Quiz::default();
+    let mut watchdog = Watchdog::default();
+    let mut time = 0.0;
+    let mut paused = false;
+    // what the watchdog paused for, shown until resumed
+    let mut pause_reason: Option<(String, &str)> = None;
+    // where the middle button last dragged the view from, and whether it has moved yet
+    let mut pan_from: Option<Vec2> = None;
+    let mut panned = false;
+    // kept while idle, so a paused frame costs nothing but drawing
+    let mut force_error = None;
+    let mut approach = None;
+    let mut approach_for = None;
+    let mut preset = Preset::Random;
+    preset.load(&mut bodies, &mut settings, &mut metadata, time);
+    let mut noise = Noise::new(settings.seed);
+    let mut gallery = Gallery::default();
+    // what the console gets to work on, the same for startup commands and typed ones
+    macro_rules! command_context {
+        () => {
+            commands::Context {
+                bodies: &mut bodies,
+                settings: &mut settings,
+                metadata: &mut metadata,
+                diagnostics: &diagnostics_log,
+                contacts: &contact_stats,
+                schedules: &mut schedules,
+                recorder: &mut recorder,
+                video: &mut video,
+                checkpoints: &mut checkpoints,
+                insertion: &mut insertion,
+                assist: &mut assist,
+                time: &mut time,
+                quiz: &mut quiz,
+                noise: &mut noise,
+                gallery: &mut gallery,
+                freeze_groups: &mut freeze_groups,
+                exec_depth: 0,
+            }
+        };
     }
 
+    for line in startup {
+        let mut context = command_context!();
+        match commands::run(&line, &mut context) {
+            Ok(output) => println!("{}", output),
+            Err(err) => eprintln!("{}: {}", line, err),
+        }
+    }
+
+    if let Some(deadline) = deadline.as_mut() {
+        deadline.start();
+    }
     loop {
-        clear_background(BLACK);
+        // a minimized window reports no size on most platforms, nothing to simulate or draw for.
+        // An unattended run still ends on time
+        let minimized = screen_width() < 1.0 || screen_height() < 1.0;
+        if minimized && !deadline.as_ref().is_some_and(Deadline::is_due) {
+            idle_wait();
+            next_frame().await;
+            continue;
+        }
+        clear_background(settings.theme.background);
+
+        if is_key_pressed(KeyCode::GraveAccent) {
+            console.toggle();
+        } else if !console.open && is_key_pressed(KeyCode::Slash) {
+            // the filter box is the console with a select already typed
+            console.open_with("select ");
+        }
+        // commands from an embedding page run just like lines typed into the console
+        let mut lines = embed::take_commands();
+        lines.extend(console.update());
+        // an unattended run ends with its exports, the screenshots wait for this frame to be
+        // drawn
+        let finishing = deadline.as_ref().is_some_and(Deadline::is_due);
+        if let Some(deadline) = deadline.as_ref().filter(|_| finishing) {
+            lines.extend(deadline.commands());
+            if recorder.is_some() {
+                lines.push("record stop".to_string());
+            }
+            if video.is_some() {
+                lines.push("video stop".to_string());
+            }
+        }
+        // commands can do anything to the bodies, the watchdog doesn't blame the step for it
+        let commanded = !lines.is_empty();
+        for line in lines {
+            let time_before = time;
+            let mut context = command_context!();
+            let output = match commands::run(&line, &mut context) {
+                Ok(output) => output,
+                Err(err) => format!("error: {}", err),
+            };
+            console.print(&output);
+            if finishing {
+                println!("{}", output);
+            }
+            announcer.say(output);
+            // indices held across frames are stale once bodies are deleted
+            drag.held.retain(|&index| index < bodies.len());
+            // a restored checkpoint starts a new branch, the past of the old one is no use
+            if time != time_before {
+                rewind.clear();
+                trails.clear();
+            }
+        }
+        let screen_mouse: Vec2 = mouse_position().into();
+        // the control panel has the mouse and the keys while the pointer is over it
+        let over_window =
+            panel.captures() ||
+            quiz.captures(screen_mouse) ||
+            inspector.captures(screen_mouse);
+        // the minimap only takes the mouse
+        let over_panel = over_window || minimap.captures(screen_mouse);
+        let restart = panel.update(&mut settings);
+        quiz.update();
+        inspector.update(&mut bodies, &metadata, &settings);
+        // keyboard shortcuts are off while typing into the console
+        let typing = console.open || over_window;
+        let pressed = |key: KeyCode| !typing && is_key_pressed(key);
 
-        for i in 0..bodies.len() {
-            for j in 0..bodies.len() {
-                if i != j {
-                    let other_body = bodies[j];
-                    bodies[i].update_force(&other_body);
+        // holding backspace plays the recent past backwards, the simulation resumes on release
+        let rewinding = !typing && is_key_down(KeyCode::Backspace);
+        if pressed(KeyCode::P) {
+            paused = !paused;
+            pause_reason = None;
+            announcer.say(if paused { "paused" } else { "resumed" });
+        }
+        // one physics step at a time, pausing first if it's running
+        let stepping = !rewinding && pressed(KeyCode::J);
+        if stepping && !paused {
+            paused = true;
+            announcer.say("paused");
+        }
+        if pressed(KeyCode::Minus) || pressed(KeyCode::Equal) {
+            settings.step_time_scale(pressed(KeyCode::Equal));
+            announcer.say(format!("time scale {:.2}x", settings.time_scale));
+        }
+        // physics, diagnostics and prediction all stand still while paused
+        let running = !rewinding && (!paused || stepping);
+        if rewinding {
+            force_error = None;
+            if let Some((snapshot_time, snapshot)) = rewind.step_back() {
+                bodies = snapshot;
+                time = snapshot_time;
+                drag.held.clear();
+                trails.step_back(rewind::FRAMES_PER_SNAPSHOT);
+            }
+        } else if running {
+            perf_graph.start_physics();
+            bodies.external_forces.fill(Vec2::ZERO);
+            schedules.accelerate(&mut bodies, time);
+            noise.perturb(&mut bodies, &settings, settings.scaled().dt);
+            tide::accelerate(&mut bodies, &settings);
+            if let Some(coupled) = cosim.as_mut() {
+                if let Err(err) = coupled.exchange(&mut bodies, time, settings.scaled().dt) {
+                    // carries on without the external model rather than stalling every frame
+                    announcer.say(format!("{}, decoupled", err));
+                    cosim = None;
+                }
+            }
+            // the GPU path falls back to the CPU when it's unavailable or the system is too big
+            let use_gpu =
+                settings.solver == Solver::Gpu &&
+                settings.hacks.force_cutoff &&
+                !settings.relativity &&
+                !settings.periodic &&
+                bodies.len() <= GpuSolver::max_bodies();
+            match gpu.as_mut() {
+                Some(gpu) if use_gpu => {
+                    let started = date::now();
+                    gpu.compute_forces(&mut bodies, settings.gravity);
+                    perf::add_force_time(date::now() - started);
+                    solver::add_external_forces(&mut bodies);
                 }
+                _ => solver::compute_forces(&mut bodies, &settings),
+            }
+            // the approximate solvers show what their speed costs
+            let exact =
+                settings.solver == Solver::BruteForce ||
+                !settings.hacks.force_cutoff ||
+                settings.periodic;
+            force_error = (!exact && !settings.relativity).then(|| {
+                solver::force_error(&bodies, settings.gravity)
+            });
+            perf_graph.stop_physics();
+        }
+
+        let mouse = view.screen_to_world(screen_mouse);
+        let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let control_down =
+            is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+
+        if !over_window && is_mouse_button_pressed(MouseButton::Left) {
+            minimap.click(screen_mouse, &mut view);
+        }
+        if !over_panel && is_mouse_button_pressed(MouseButton::Left) {
+            // clicking a body selects just that one, shift-click adds or removes bodies from
+            // the selection, ctrl-drag selects a rectangle and a drag from empty space launches
+            // a new body
+            let clicked = bodies.iter().position(|body| view.hits(&body, screen_mouse));
+            if control_down {
+                marquee.start(screen_mouse);
+            } else if shift_down {
+                bodies.for_each_mut(|body| {
+                    if view.hits(body, screen_mouse) {
+                        body.selected = !body.selected;
+                    }
+                });
+            } else if let Some(index) = clicked {
+                bodies.selected = vec![false; bodies.len()];
+                bodies.selected[index] = true;
+                inspector.open(&bodies, index);
+                announcer.say(format!("selected {}", accessibility::describe(&bodies, index)));
+            } else {
+                launch = Some(Launch::new(mouse));
+            }
+        }
+        if let Some(aimed) = launch.as_mut() {
+            aimed.aim(mouse, &settings);
+        }
+        if pressed(KeyCode::Escape) && launch.take().is_some() {
+            announcer.say("launch cancelled");
+        }
+        if is_mouse_button_released(MouseButton::Left) {
+            if let Some(launched) = launch.take() {
+                let speed = launched.body.velocity.length();
+                let id = spawn(&mut bodies, &mut metadata, launched.body, time);
+                announcer.say(format!("launched body {} at speed {:.1}", id, speed));
             }
         }
+        // shift keeps what was selected before
+        if marquee.is_active() && is_mouse_button_released(MouseButton::Left) {
+            let count = marquee.finish(screen_mouse, &mut bodies, &view, shift_down);
+            announcer.say(format!("selected {} bodies", count));
+        }
+        if pressed(KeyCode::Delete) {
+            let count = bodies.selected.iter().filter(|&&selected| selected).count();
+            bodies.retain(|body| !body.selected);
+            metadata.retain(&bodies);
+            drag.held.clear();
+            announcer.say(format!("deleted {} bodies", count));
+        }
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let mouse_position = mouse_position();
-            bodies.push(Body::random(Some(vec2(mouse_position.0, mouse_position.1))));
+        // keyboard path for the mouse: arrows move a cursor, enter spawns, tab selects
+        if !typing {
+            let directions = [
+                (KeyCode::Left, vec2(-1.0, 0.0)),
+                (KeyCode::Right, vec2(1.0, 0.0)),
+                (KeyCode::Up, vec2(0.0, -1.0)),
+                (KeyCode::Down, vec2(0.0, 1.0)),
+            ];
+            let speed = if shift_down { CURSOR_FAST } else { CURSOR_SPEED };
+            for (key, direction) in directions {
+                if is_key_down(key) {
+                    cursor.nudge(direction * speed);
+                }
+            }
+        }
+        let alt_down = is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt);
+        if alt_down && pressed(KeyCode::Enter) {
+            // the view fits the world to the new size by itself once it's known next frame
+            fullscreen = !fullscreen;
+            set_fullscreen(fullscreen);
+            announcer.say(format!("fullscreen {}", on_off(fullscreen)));
+        } else if pressed(KeyCode::Enter) {
+            let position = cursor.world(&view);
+            let id = spawn(&mut bodies, &mut metadata, Body::random(Some(position)), time);
+            announcer.say(format!("spawned body {} at ({:.0}, {:.0})", id, position.x, position.y));
+        }
+        if pressed(KeyCode::X) {
+            let position = if cursor.visible { cursor.world(&view) } else { mouse };
+            let hole = blackhole::new(position, &settings);
+            metadata.set(hole.id, "spawned_at", Value::Number(time as f64));
+            bodies.push(hole);
+            announcer.say(
+                format!("spawned black hole {} at ({:.0}, {:.0})", hole.id, position.x, position.y)
+            );
+        }
+        if pressed(KeyCode::Tab) {
+            // shift goes backwards, the cursor jumps to the body so enter spawns next to it
+            if let Some(index) = accessibility::cycle_selection(&mut bodies, !shift_down) {
+                cursor.position = view.world_to_screen(bodies.positions[index]);
+                announcer.say(format!("selected {}", accessibility::describe(&bodies, index)));
+            }
+        }
+        if pressed(KeyCode::I) {
+            // shift keeps the description on the status line instead of announcing it once
+            if shift_down {
+                announcer.verbose = !announcer.verbose;
+                announcer.say(format!("verbose status {}", on_off(announcer.verbose)));
+            } else {
+                let message = match bodies.selected.iter().position(|&selected| selected) {
+                    Some(index) => accessibility::describe(&bodies, index),
+                    None => "nothing selected".to_string(),
+                };
+                announcer.say(message);
+            }
         }
 
         // drag bodies with mouse
-        if is_mouse_button_down(MouseButton::Right) {
-            let mouse_position = mouse_position();
-            for body in bodies.iter_mut() {
-                if
-                    body.get_distance(&Body::new(vec2(mouse_position.0, mouse_position.1))) <
-                    2.0 * body.radius
-                {
-                    body.position = vec2(mouse_position.0, mouse_position.1);
+        if !over_panel && is_mouse_button_down(MouseButton::Right) {
+            drag.track(mouse);
+            for index in 0..bodies.len() {
+                let mut body = bodies.body(index);
+                if body.get_distance(&Body::new(mouse)) < 2.0 * body.radius {
+                    body.position = mouse;
                     body.velocity = Vec2::ZERO;
+                    bodies.set(index, body);
+                    drag.grab(index);
                 }
             }
         }
 
-        if is_mouse_button_pressed(MouseButton::Middle) {
-            let mouse_position = mouse_position();
-            for body in bodies.iter_mut() {
-                if
-                    body.get_distance(&Body::new(vec2(mouse_position.0, mouse_position.1))) <
-                    2.0 * body.radius
-                {
-                    body.freezed = !body.freezed;
-                }
+        // throw dragged bodies with the velocity of the mouse when released
+        if is_mouse_button_released(MouseButton::Right) {
+            drag.release(&mut bodies, settings.scaled().dt, settings.throw_strength);
+        }
+
+        // dragging with the middle button pans the view, clicking it freezes what's underneath
+        if !over_panel && is_mouse_button_pressed(MouseButton::Middle) {
+            pan_from = Some(screen_mouse);
+            panned = false;
+        }
+        if let Some(from) = pan_from {
+            if panned || from.distance(screen_mouse) > PAN_THRESHOLD {
+                view.pan_by(screen_mouse - from);
+                pan_from = Some(screen_mouse);
+                panned = true;
+            }
+        }
+        if is_mouse_button_released(MouseButton::Middle) {
+            if !panned {
+                bodies.for_each_mut(|body| {
+                    if view.hits(body, screen_mouse) {
+                        body.freezed = !body.freezed;
+                        body.freeze_group = None;
+                        metadata.remove(body.id, "freeze_group");
+                    }
+                });
+            }
+            pan_from = None;
+        }
+        if pressed(KeyCode::Home) {
+            view.reset_camera();
+            announcer.say("view reset");
+        }
+
+        if pressed(KeyCode::G) {
+            // shift schedules the group to release on its own after a delay
+            let release_at = if shift_down { Some(time + FREEZE_GROUP_DELAY) } else { None };
+            let frozen =
+                freeze_groups.freeze_selection(&mut bodies, &mut metadata, None, release_at);
+            if let Some(index) = frozen {
+                let name = freeze_groups.groups[index].as_ref().map(|group| group.name.clone());
+                let count = freeze_groups.count_members(index, &bodies);
+                announcer.say(format!("froze {} bodies into {}", count, name.unwrap_or_default()));
+            }
+        }
+
+        let group_keys = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        for (index, key) in group_keys.iter().enumerate() {
+            if pressed(*key) && freeze_groups.release(index, &mut bodies, &mut metadata) {
+                announcer.say(format!("released group {}", index + 1));
             }
         }
 
-        if is_key_pressed(KeyCode::Space) {
+        if pressed(KeyCode::U) {
+            freeze_groups.release_all(&mut bodies, &mut metadata);
+            announcer.say("released all groups");
+        }
+
+        freeze_groups.release_due(time, &mut bodies, &mut metadata);
+
+        // hold B to paint velocities with the brush
+        if pressed(KeyCode::V) {
+            brush.mode = brush.mode.next();
+            announcer.say(format!("brush mode {}", brush.mode.name()));
+        }
+        let brush_active = !typing && is_key_down(KeyCode::B);
+        // the wheel sizes the brush while it's held, or with shift sets its strength, and zooms
+        // the view otherwise
+        let wheel = mouse_wheel().1;
+        if brush_active {
+            if wheel != 0.0 && shift_down {
+                settings.brush_strength = Brush::stronger(settings.brush_strength, wheel.signum());
+            } else if wheel != 0.0 {
+                brush.resize(wheel.signum() * 10.0);
+            }
+            brush.apply(mouse, &mut bodies, settings.brush_strength, settings.dt);
+        } else if wheel != 0.0 && !over_panel {
+            view.zoom_at(screen_mouse, wheel.signum());
+        }
+
+        if pressed(KeyCode::F) {
+            settings.solver = settings.solver.next();
+            announcer.say(format!("solver {}", settings.solver.name()));
+        }
+
+        if pressed(KeyCode::E) {
+            settings.integrator = settings.integrator.next();
+            announcer.say(format!("integrator {}", settings.integrator.name()));
+        }
+
+        // tree quality: [ and ] open and close the opening angle, with shift they change the
+        // leaf capacity instead, and \ switches the fmm expansion order
+        if pressed(KeyCode::LeftBracket) || pressed(KeyCode::RightBracket) {
+            let up = is_key_pressed(KeyCode::RightBracket);
+            if shift_down {
+                settings.leaf_capacity = if up {
+                    settings.leaf_capacity + 1
+                } else {
+                    settings.leaf_capacity.saturating_sub(1).max(1)
+                };
+                announcer.say(format!("leaf capacity {}", settings.leaf_capacity));
+            } else {
+                let step = if up { THETA_STEP } else { -THETA_STEP };
+                settings.theta = (settings.theta + step).clamp(MIN_THETA, MAX_THETA);
+                announcer.say(format!("opening angle {:.2}", settings.theta));
+            }
+        }
+        if pressed(KeyCode::Backslash) {
+            settings.expansion_order = (settings.expansion_order + 1) % (fmm::MAX_ORDER + 1);
+            announcer.say(format!("expansion order {}", settings.expansion_order));
+        }
+
+        if pressed(KeyCode::A) {
+            panel.visible = !panel.visible;
+            announcer.say(format!("control panel {}", on_off(panel.visible)));
+        }
+        if pressed(KeyCode::D) {
+            formula.visible = !formula.visible;
+            announcer.say(format!("formula overlay {}", on_off(formula.visible)));
+        }
+
+        if pressed(KeyCode::K) {
+            settings.broadphase = settings.broadphase.next();
+            announcer.say(format!("broadphase {}", settings.broadphase.name()));
+        }
+
+        if pressed(KeyCode::C) {
+            view.toggle_center_of_mass();
+            announcer.say(format!("{} frame", view.frame.name()));
+        }
+        if pressed(KeyCode::Key0) {
+            // shift marks where each pair of selected bodies balances instead
+            if shift_down {
+                barycenters.pairs = !barycenters.pairs;
+                announcer.say(format!("pair barycenters {}", on_off(barycenters.pairs)));
+            } else {
+                barycenters.center_of_mass = !barycenters.center_of_mass;
+                announcer.say(
+                    format!("center of mass marker {}", on_off(barycenters.center_of_mass))
+                );
+            }
+        }
+        if pressed(KeyCode::O) {
+            // shift also draws trails and velocities relative to the pinned body
+            view.toggle_pinned(&bodies, shift_down);
+            announcer.say(format!("{} frame", view.frame.name()));
+        }
+        if pressed(KeyCode::W) {
+            view.toggle_follow(&bodies);
+            match view.follow {
+                Some(id) => announcer.say(format!("following {}", metadata.label(id))),
+                None => announcer.say("camera free"),
+            }
+        }
+        if pressed(KeyCode::R) {
+            view.toggle_rotation(&bodies, ROTATION_STEP);
+            announcer.say(format!("rotating view {}", on_off(view.rotation.is_some())));
+        }
+        if pressed(KeyCode::Comma) {
+            view.adjust_rotation(-ROTATION_STEP);
+        }
+        if pressed(KeyCode::Period) {
+            view.adjust_rotation(ROTATION_STEP);
+        }
+        if pressed(KeyCode::Z) {
+            view.depth_mode = !view.depth_mode;
+            announcer.say(format!("depth view {}", on_off(view.depth_mode)));
+        }
+        if pressed(KeyCode::Q) {
+            // shift changes how they're drawn instead of what they're colored by
+            if shift_down {
+                settings.body_style = settings.body_style.next();
+                announcer.say(format!("bodies drawn {}", settings.body_style.name()));
+            } else {
+                view.color_mode = view.color_mode.next();
+                announcer.say(format!("{} coloring", view.color_mode.name()));
+            }
+        }
+        if pressed(KeyCode::T) {
+            view.show_fictitious_forces = !view.show_fictitious_forces;
+            announcer.say(format!("fictitious forces {}", on_off(view.show_fictitious_forces)));
+        }
+        if pressed(KeyCode::Semicolon) {
+            view.show_velocities = !view.show_velocities;
+            announcer.say(format!("velocity arrows {}", on_off(view.show_velocities)));
+        }
+        if pressed(KeyCode::Apostrophe) {
+            view.show_accelerations = !view.show_accelerations;
+            announcer.say(format!("acceleration arrows {}", on_off(view.show_accelerations)));
+        }
+        if pressed(KeyCode::F1) {
+            labels.visible = !labels.visible;
+            announcer.say(format!("name labels {}", on_off(labels.visible)));
+        }
+        if pressed(KeyCode::F2) {
+            tree_overlay.visible = !tree_overlay.visible;
+            announcer.say(format!("quadtree overlay {}", on_off(tree_overlay.visible)));
+        }
+        if pressed(KeyCode::F3) {
+            view.render_mode = view.render_mode.next();
+            announcer.say(format!("rendering {}", view.render_mode.name()));
+        }
+        if pressed(KeyCode::F4) {
+            legend.visible = !legend.visible;
+            announcer.say(format!("legend {}", on_off(legend.visible)));
+        }
+        if pressed(KeyCode::F6) {
+            grid.visible = !grid.visible;
+            announcer.say(format!("grid {}", on_off(grid.visible)));
+        }
+        if pressed(KeyCode::F7) {
+            minimap.visible = !minimap.visible;
+            announcer.say(format!("minimap {}", on_off(minimap.visible)));
+        }
+        if pressed(KeyCode::F8) {
+            if glow.is_available() {
+                glow.enabled = !glow.enabled;
+                announcer.say(format!("glow {}", on_off(glow.enabled)));
+            } else {
+                announcer.say("glow is unavailable".to_string());
+            }
+        }
+        // taken once the frame is drawn
+        let take_screenshot = pressed(KeyCode::F12);
+        if pressed(KeyCode::F9) {
+            subcluster.visible = !subcluster.visible;
+            announcer.say(format!("subcluster statistics {}", on_off(subcluster.visible)));
+        }
+        if pressed(KeyCode::F10) {
+            perf_graph.visible = !perf_graph.visible;
+            announcer.say(format!("frame time graph {}", on_off(perf_graph.visible)));
+        }
+        if pressed(KeyCode::F11) {
+            energy_plot.visible = !energy_plot.visible;
+            announcer.say(format!("energy plot {}", on_off(energy_plot.visible)));
+        }
+        if running {
+            view.apply_frame(&mut bodies, settings.scaled().dt);
+        }
+
+        if pressed(KeyCode::H) {
+            // shift clears the accumulated collisions instead of toggling the overlay
+            if shift_down {
+                heatmap.reset();
+                announcer.say("heat map reset");
+            } else {
+                heatmap.visible = !heatmap.visible;
+                announcer.say(format!("heat map {}", on_off(heatmap.visible)));
+            }
+        }
+
+        if pressed(KeyCode::Y) {
+            trails.toggle();
+            announcer.say(format!("trails {}", on_off(trails.visible)));
+        }
+
+        if pressed(KeyCode::S) {
+            // shift starts the statistics over, like the heat map
+            if shift_down {
+                contact_stats.reset();
+                announcer.say("collision statistics reset");
+            } else {
+                contact_stats.visible = !contact_stats.visible;
+                announcer.say(format!("collision statistics {}", on_off(contact_stats.visible)));
+            }
+        }
+
+        if pressed(KeyCode::N) {
+            resonances.toggle();
+            announcer.say(format!("resonance detector {}", on_off(resonances.visible)));
+        }
+
+        if pressed(KeyCode::M) {
+            if let Some(sonifier) = sonifier.as_mut() {
+                sonifier.toggle();
+                announcer.say(format!("sonification {}", on_off(sonifier.enabled)));
+            }
+        }
+
+        // the panel's restart loads the same preset again with its body count
+        let next_preset = pressed(KeyCode::L);
+        if next_preset || restart {
+            if next_preset {
+                preset = preset.next();
+            }
+            preset.load(&mut bodies, &mut settings, &mut metadata, time);
+            schedules.clear();
+            noise.reset(settings.seed);
+            freeze_groups.clear();
+            drag.held.clear();
+            // the old scenario ran with other settings, so it can't be rewound into
+            rewind.clear();
+            trails.clear();
+            announcer.say(format!("loaded {} preset with {} bodies", preset.name(), bodies.len()));
+        }
+
+        if pressed(KeyCode::Space) {
             bodies.clear();
+            trails.clear();
+            schedules.clear();
+            freeze_groups.clear();
+            metadata.clear();
+            drag.held.clear();
+            // stepping back would bring the bodies back without their tags
+            rewind.clear();
+            announcer.say("cleared all bodies");
         }
 
-        for i in 0..bodies.len() {
-            for j in i + 1..bodies.len() {
-                let mut other_body = bodies[j];
-                bodies[i].check_and_resolve_collision(&mut other_body);
-                bodies[j] = other_body;
-            }
-            bodies[i].update(0.5);
-            bodies[i].check_boundary_collisions();
-            draw_circle_lines(
-                bodies[i].position.x,
-                bodies[i].position.y,
-                bodies[i].radius,
-                4.0,
-                RED
-            );
+        if running {
+            perf_graph.start_physics();
+            let frame = settings.scaled();
+            watchdog.before_step(&bodies);
+            let collisions = step(&mut bodies, &frame);
+            for impact in &collisions {
+                heatmap.record(impact.position);
+            }
+            contact_stats.record(&collisions);
+            if !collisions.is_empty() {
+                embed::emit("collisions", &[("count", collisions.len().to_string())]);
+            }
+            let swallowed = blackhole::absorb(&mut bodies, &settings);
+            if !swallowed.is_empty() {
+                metadata.retain(&bodies);
+                drag.held.clear();
+                embed::emit("absorbed", &[("count", swallowed.len().to_string())]);
+            }
+            time += frame.dt;
+            schedules.guide(&mut bodies, time);
+            rewind.record(time, &bodies);
+            trails.record(&bodies, settings.trail_length);
+            if let Some(recording) = recorder.as_mut() {
+                if let Err(err) = recording.record(time, &bodies) {
+                    announcer.say(format!("recording to {} stopped: {}", recording.path, err));
+                    recorder = None;
+                }
+            }
+            resonances.update(&bodies, settings.gravity);
+            perf_graph.stop_physics();
         }
+        // also while paused, the selection can change
+        subcluster.update(&bodies, settings.gravity);
 
-        draw_text(&format!("{}", get_fps()), 100.0, 100.0, 30.0, WHITE);
+        // the selected body's next collision or near miss, counted down on the HUD
+        let selected = bodies.selected.iter().position(|&selected| selected);
+        if !paused || stepping || selected != approach_for {
+            approach = selected.and_then(|index| {
+                prediction::soonest_approach(&bodies, index, &settings)
+            });
+            approach_for = selected;
+        }
+        // every frame, also while paused, so a body thrown or dragged shows where it's going
+        let predicted_path = selected
+            .filter(|_| settings.predict_path)
+            .map(|index| prediction::path(&bodies, index, &settings))
+            .unwrap_or_default();
+        let launch_path = launch
+            .as_ref()
+            .filter(|_| settings.predict_path)
+            .map(|aimed| aimed.path(&bodies, &settings))
+            .unwrap_or_default();
+
+        perf_graph.start_render();
+        view.track(&bodies);
+        if settings.starfield {
+            starfield.draw(&view, settings.theme.stars);
+        }
+        grid.draw(&settings, &view);
+        heatmap.draw(&view);
+        if view.render_mode.shows_density() {
+            density_map.draw(&bodies, &view);
+        }
+        trails.draw(&view, settings.trail_fade, settings.theme.trail);
+        tree_overlay.draw(&bodies, &settings, &view);
+
+        let palette = Palette::fit(view.color_mode, &bodies);
+        // drawn over everything once the bodies are
+        let mut frozen_markers = Vec::new();
+        for i in view.draw_order(&bodies) {
+            let body = bodies.body(i);
+            if view.render_mode == RenderMode::Density && !body.selected && !body.black_hole {
+                continue;
+            }
+            let (screen_position, scale) = view.project(&body);
+            if body.black_hole {
+                batch.flush();
+                blackhole::draw(screen_position, body.radius * scale, body.selected);
+                continue;
+            }
+            let color = if body.selected {
+                settings.theme.accent
+            } else if body.freezed {
+                freeze::FROZEN_COLOR
+            } else if let Some(color) = palette.color(&body) {
+                color
+            } else if body.is_sleeping() {
+                settings.theme.sleeping
+            } else {
+                settings.theme.body
+            };
+            let color = view.depth_tint(&body, color);
+            if body.freezed && body.radius * scale >= settings.lod_radius {
+                frozen_markers.push((screen_position, body.radius * scale));
+            }
+            // selected bodies keep their ring on top so they can still be told apart
+            if glow.is_active() {
+                glow.add(screen_position, body.radius * scale, color, body.mass);
+                if !body.selected {
+                    continue;
+                }
+            }
+            // too small for their shape to show, and selected ones keep it to stand out
+            if body.radius * scale < settings.lod_radius && !body.selected {
+                batch.add_point(screen_position, body.radius * scale, color);
+                continue;
+            }
+            let style = BodyStyle::of(&metadata, body.id, settings.body_style);
+            batch.add(style, screen_position, body.radius * scale, scale, color);
+        }
+        batch.flush();
+
+        glow.draw();
+        for (position, radius) in frozen_markers {
+            freeze::draw_marker(position, radius);
+        }
+        resonances.draw(&bodies, &view);
+        subcluster.draw(&view);
+        barycenters.draw(&bodies, &view);
+        labels.draw(&bodies, &metadata, &view, &settings.theme);
+        if let Some(insertion) = &insertion {
+            insertion.draw(&bodies, settings.gravity, &view);
+        }
+        if let Some(plan) = &assist {
+            plan.draw(&view);
+        }
+        prediction::draw_path(&predicted_path, &view);
+        if let Some(aimed) = &launch {
+            prediction::draw_path(&launch_path, &view);
+            let text_color = settings.theme.text;
+            aimed.draw(&view, settings.velocity_scale, settings.numbers, text_color);
+        }
+        if let (Some(index), Some(approach)) = (selected, &approach) {
+            if let Some(partner) = bodies.ids.iter().position(|&id| id == approach.partner) {
+                let (from, _) = view.project(&bodies.body(index));
+                let (to, _) = view.project(&bodies.body(partner));
+                let faded = Color::new(1.0, 0.63, 0.0, 0.4);
+                let color = if approach.collision { ORANGE } else { faded };
+                draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+            }
+        }
+        view.draw_bounds();
+        view.draw_fictitious_forces(&bodies);
+        view.draw_velocities(&bodies, settings.velocity_scale);
+        // the periodic sum has no cutoff
+        let cutoff = settings.hacks.force_cutoff && !settings.periodic;
+        view.draw_accelerations(&bodies, settings.acceleration_scale, cutoff);
+        formula.draw(&bodies, &metadata, &settings, &view);
+        let density = view.render_mode.shows_density().then(|| density_map.max());
+        legend.draw(&bodies, &palette, density, heatmap.max(), &settings, &view);
+        grid.draw_scale_bar(&settings, &view);
+
+        if brush_active {
+            brush.draw(view.world_to_screen(mouse), view.scale(), settings.brush_strength);
+        }
+        cursor.draw();
+        marquee.draw(screen_mouse, settings.theme.accent);
+        if let Err(err) = gallery.take_thumbnail() {
+            announcer.say(err);
+        }
+
+        let momentum = diagnostics::linear_momentum(&bodies);
+        let angular_momentum = diagnostics::angular_momentum(&bodies);
+        if !paused || stepping {
+            energy.update(&bodies, settings.gravity);
+            if let Some(sonifier) = sonifier.as_mut() {
+                sonifier.update(&bodies, energy.kinetic);
+            }
+        }
+        // the log only moves forward in time
+        if running {
+            energy_plot.record(get_frame_time(), &energy, settings.energy_window);
+            diagnostics_log.record(Sample {
+                time,
+                kinetic: energy.kinetic,
+                potential: energy.potential,
+                momentum,
+                angular_momentum,
+            });
+            // the mouse and a reloaded preset move bodies outside the step
+            let disturbed =
+                commanded ||
+                restart ||
+                next_preset ||
+                brush_active ||
+                is_mouse_button_down(MouseButton::Right) ||
+                is_mouse_button_released(MouseButton::Right);
+            match watchdog.check(&bodies, &energy, disturbed, &mut settings) {
+                Some(Verdict::Tuned(message)) => announcer.say(message),
+                Some(Verdict::Paused { problem, advice }) => {
+                    paused = true;
+                    announcer.say(format!("paused, {}", problem));
+                    pause_reason = Some((problem, advice));
+                }
+                None => {}
+            }
+        }
+
+        if pressed(KeyCode::F5) {
+            match diagnostics_log.export_csv("diagnostics.csv", &Provenance::new(&settings)) {
+                Ok(()) => println!("exported diagnostics to diagnostics.csv"),
+                Err(err) => eprintln!("failed to export diagnostics: {}", err),
+            }
+        }
+
+        perf_graph.draw(100.0, settings.theme.text);
+        let numbers = settings.numbers;
+        let mut hud_y = 130.0;
+        let text_color = settings.theme.text;
+        draw_text(
+            format!(
+                "solver: {}  broadphase: {}  integrator: {}",
+                settings.solver.name(),
+                settings.broadphase.name(),
+                settings.integrator.name()
+            ),
+            100.0,
+            hud_y,
+            20.0,
+            text_color
+        );
+        hud_y += 20.0;
+        if let Some(error) = force_error {
+            let mut line = String::new();
+            if matches!(settings.solver, Solver::BarnesHut | Solver::Fmm) {
+                let theta = numbers.format(settings.theta, 2);
+                line.push_str(&format!("theta {}  leaf {}  ", theta, settings.leaf_capacity));
+            }
+            if settings.solver == Solver::Fmm {
+                line.push_str(&format!("order {}  ", settings.expansion_order));
+            }
+            line.push_str(&format!("force error ~{}%", numbers.format(error * 100.0, 2)));
+            draw_text(&line, 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        draw_text(
+            format!(
+                "E = {} (K {}, U {})",
+                numbers.format(energy.total(), 4),
+                numbers.format(energy.kinetic, 3),
+                numbers.format(energy.potential, 3)
+            ),
+            100.0,
+            hud_y,
+            20.0,
+            text_color
+        );
+        hud_y += 20.0;
+        let drift = numbers.signed(energy.drift_percent(), 3);
+        draw_text(format!("drift {}%", drift), 100.0, hud_y, 20.0, text_color);
+        hud_y += 20.0;
+        draw_text(
+            format!(
+                "p = ({}, {})  L = {}",
+                numbers.format(momentum.x, 3),
+                numbers.format(momentum.y, 3),
+                numbers.format(angular_momentum, 4)
+            ),
+            100.0,
+            hud_y,
+            20.0,
+            text_color
+        );
+        hud_y += 20.0;
+        if let Some(scale) = settings.scale {
+            let mut line = format!("{} years", numbers.format(scale.years(time), 2));
+            if let Some(index) = bodies.selected.iter().position(|&selected| selected) {
+                let center = diagnostics::center_of_mass(&bodies);
+                let distance = scale.au_from_pixels(bodies.positions[index].distance(center));
+                let distance = numbers.quantity(distance, 2, "AU");
+                line.push_str(&format!("  selected body at {}", distance));
+            }
+            draw_text(&line, 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if settings.hacks.is_ideal() {
+            draw_text("ideal physics", 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if settings.periodic {
+            draw_text("periodic world, Ewald gravity", 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if view.zoom != 1.0 || view.pan != Vec2::ZERO || view.follow.is_some() {
+            let following = view.follow.map_or(String::new(), |id| {
+                format!(", following {}", metadata.label(id))
+            });
+            let zoom = numbers.format(view.zoom, 2);
+            let line = format!("zoom {}x{}, Home to reset", zoom, following);
+            draw_text(&line, 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if settings.relativity {
+            let line = format!("1PN relativity, c = {}", numbers.format(settings.light_speed, 0));
+            draw_text(&line, 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if insertion.is_some() {
+            let line = "orbit planned, `orbit apply` to burn or `orbit cancel`";
+            draw_text(line, 100.0, hud_y, 20.0, SKYBLUE);
+            hud_y += 20.0;
+        }
+        if assist.is_some() {
+            let line = "flyby planned, `assist apply` to launch or `assist cancel`";
+            draw_text(line, 100.0, hud_y, 20.0, GOLD);
+            hud_y += 20.0;
+        }
+        if settings.time_scale != 1.0 {
+            let scale = numbers.format(settings.time_scale, 2);
+            let line = format!("time scale {}x, - and = to change", scale);
+            draw_text(&line, 100.0, hud_y, 20.0, YELLOW);
+            hud_y += 20.0;
+        }
+        if paused {
+            draw_text("paused, P to resume, J to step", 100.0, hud_y, 20.0, ORANGE);
+            hud_y += 20.0;
+            if let Some((problem, advice)) = &pause_reason {
+                for line in [problem.as_str(), advice] {
+                    draw_text(line, 100.0, hud_y, 20.0, ORANGE);
+                    hud_y += 20.0;
+                }
+            }
+        }
+        if let Some(recording) = &recorder {
+            let line = format!("recording to {}, {} frames", recording.path, recording.frames);
+            draw_text(&line, 100.0, hud_y, 20.0, RED);
+            hud_y += 20.0;
+        }
+        if let Some(filming) = &video {
+            let line = format!("filming to {}, {} frames", filming.path, filming.frames);
+            draw_text(&line, 100.0, hud_y, 20.0, RED);
+            hud_y += 20.0;
+        }
+        if rewinding {
+            let span = numbers.format(rewind.span(time), 0);
+            let line = format!("<< rewinding, {} time units left", span);
+            draw_text(&line, 100.0, hud_y, 20.0, ORANGE);
+            hud_y += 20.0;
+        }
+        if let Some(approach) = &approach {
+            let partner = metadata.label(approach.partner);
+            let line = if approach.collision {
+                format!("collision with {} in {}", partner, numbers.format(approach.time, 1))
+            } else {
+                format!(
+                    "close approach to {} ({}) in {}",
+                    partner,
+                    numbers.quantity(approach.distance, 0, "px"),
+                    numbers.format(approach.time, 1)
+                )
+            };
+            draw_text(&line, 100.0, hud_y, 20.0, ORANGE);
+            hud_y += 20.0;
+        }
+        if sonifier.as_ref().is_some_and(|sonifier| sonifier.enabled) {
+            // without the feature macroquad only has a silent audio backend
+            let status = if cfg!(feature = "audio") { "on" } else { "on (built without audio)" };
+            draw_text(format!("sonification {}", status), 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        if resonances.visible {
+            let lines = resonances.summary(&metadata);
+            if lines.is_empty() {
+                draw_text("no resonances", 100.0, hud_y, 20.0, GRAY);
+                hud_y += 20.0;
+            }
+            for line in lines {
+                draw_text(&line, 100.0, hud_y, 20.0, SKYBLUE);
+                hud_y += 20.0;
+            }
+        }
+        for line in subcluster.summary(numbers) {
+            draw_text(&line, 100.0, hud_y, 20.0, text_color);
+            hud_y += 20.0;
+        }
+        // in groups or frozen one at a time
+        let frozen = bodies.freezed.iter().filter(|&&freezed| freezed).count();
+        if frozen > 0 {
+            let line = format!("{} frozen", frozen);
+            draw_text(&line, 100.0, hud_y, 20.0, freeze::FROZEN_COLOR);
+            hud_y += 20.0;
+        }
+        for (index, group) in freeze_groups.groups.iter().enumerate() {
+            if let Some(group) = group {
+                let release = match group.release_at {
+                    Some(release_at) => {
+                        format!(" releases in {}", numbers.format(release_at - time, 0))
+                    }
+                    None => String::new(),
+                };
+                draw_text(
+                    format!(
+                        "[{}] {}: {} bodies{}",
+                        index + 1,
+                        group.name,
+                        freeze_groups.count_members(index, &bodies),
+                        release
+                    ),
+                    100.0,
+                    hud_y,
+                    20.0,
+                    text_color
+                );
+                hud_y += 20.0;
+            }
+        }
+        if let Some(body) = bodies.iter().find(|body| body.selected) {
+            for (key, value) in metadata.entries(body.id) {
+                draw_text(format!("{} = {}", key, value), 100.0, hud_y, 20.0, GRAY);
+                hud_y += 20.0;
+            }
+        }
+        contact_stats.draw(&settings);
+        energy_plot.draw(settings.energy_window, numbers);
+        minimap.draw(&bodies, &view, contact_stats.bottom());
+        perf_graph.stop_render();
+        // without the announcer and the console on top
+        if take_screenshot {
+            match screenshot::save() {
+                Ok(path) => announcer.say(format!("saved a screenshot to {}", path)),
+                Err(err) => announcer.say(err),
+            }
+        }
+        if let Some(filming) = video.as_mut() {
+            if let Err(err) = filming.capture(get_frame_time()) {
+                announcer.say(format!("filming to {} stopped: {}", filming.path, err));
+                video = None;
+            }
+        }
+        announcer.draw(&bodies);
+        splash.draw();
+        console.draw();
+        panel.draw();
+        if paused {
+            idle_wait();
+        }
+        if let Some(deadline) = deadline.as_ref().filter(|_| finishing) {
+            for line in deadline.take_screenshots() {
+                println!("{}", line);
+            }
+            break;
+        }
+        perf_graph.end_frame();
         next_frame().await;
     }
 }
+
+// gives the CPU back between idle frames. The browser already throttles pages that aren't
+// visible and can't block its only thread
+fn idle_wait() {
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::sleep(IDLE_FRAME);
+}
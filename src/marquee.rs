@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::view::View;
+
+const FILL: Color = Color::new(1.0, 1.0, 0.0, 0.08);
+
+// ctrl-drag with the left button: a rectangle on screen selecting the bodies drawn inside
+// it, for the console's bulk commands and the keys that act on the selection
+#[derive(Default)]
+pub struct Marquee {
+    // the corner the drag started from, in screen coordinates
+    from: Option<Vec2>,
+}
+
+impl Marquee {
+    pub fn start(&mut self, mouse: Vec2) {
+        self.from = Some(mouse);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.from.is_some()
+    }
+
+    // selects what's inside, next to the current selection with `add`. Returns how many
+    // bodies are selected now
+    pub fn finish(&mut self, mouse: Vec2, bodies: &mut Bodies, view: &View, add: bool) -> usize {
+        let Some(from) = self.from.take() else {
+            return 0;
+        };
+        let (low, high) = (from.min(mouse), from.max(mouse));
+        for index in 0..bodies.len() {
+            // where it's drawn, so the rotated and perspective views select what they show
+            let (position, _) = view.project(&bodies.body(index));
+            let inside = position.cmpge(low).all() && position.cmple(high).all();
+            bodies.selected[index] = inside || (add && bodies.selected[index]);
+        }
+        bodies.selected
+            .iter()
+            .filter(|&&selected| selected)
+            .count()
+    }
+
+    pub fn draw(&self, mouse: Vec2, color: Color) {
+        let Some(from) = self.from else {
+            return;
+        };
+        let (low, size) = (from.min(mouse), (from - mouse).abs());
+        draw_rectangle(low.x, low.y, size.x, size.y, FILL);
+        draw_rectangle_lines(low.x, low.y, size.x, size.y, 1.0, color);
+    }
+}
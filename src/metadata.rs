@@ -0,0 +1,82 @@
+use std::collections::{ BTreeMap, HashMap, HashSet };
+use std::fmt;
+
+use crate::bodies::Bodies;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    // numbers and booleans are recognised, anything else is kept as (unquoted) text
+    pub fn parse(word: &str) -> Self {
+        if let Ok(number) = word.parse() {
+            return Value::Number(number);
+        }
+        match word {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::Text(word.trim_matches(|c| c == '"' || c == '\'').to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Text(text) => write!(f, "{:?}", text),
+            Value::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// free-form per-body data keyed by `Body::id`, kept outside the body so it stays `Copy`
+#[derive(Clone, Default)]
+pub struct Metadata {
+    entries: HashMap<u64, BTreeMap<String, Value>>,
+}
+
+impl Metadata {
+    pub fn set(&mut self, id: u64, key: &str, value: Value) {
+        self.entries.entry(id).or_default().insert(key.to_string(), value);
+    }
+
+    pub fn remove(&mut self, id: u64, key: &str) {
+        if let Some(entries) = self.entries.get_mut(&id) {
+            entries.remove(key);
+            if entries.is_empty() {
+                self.entries.remove(&id);
+            }
+        }
+    }
+
+    pub fn get(&self, id: u64, key: &str) -> Option<&Value> {
+        self.entries.get(&id).and_then(|entries| entries.get(key))
+    }
+
+    // the body's name where it has one
+    pub fn label(&self, id: u64) -> String {
+        match self.get(id, "name") {
+            Some(Value::Text(name)) => name.clone(),
+            _ => format!("body {}", id),
+        }
+    }
+
+    pub fn entries(&self, id: u64) -> impl Iterator<Item = (&String, &Value)> {
+        self.entries.get(&id).into_iter().flatten()
+    }
+
+    // drops the data of bodies that no longer exist
+    pub fn retain(&mut self, bodies: &Bodies) {
+        let ids: HashSet<u64> = bodies.ids.iter().copied().collect();
+        self.entries.retain(|id, _| ids.contains(id));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,107 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::view::View;
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+const MAP_WIDTH: f32 = 180.0;
+const MAP_HEIGHT: f32 = 120.0;
+// between the map's edge and what's drawn in it
+const MARGIN: f32 = 6.0;
+const BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+const VIEWPORT_COLOR: Color = Color::new(1.0, 1.0, 0.0, 0.8);
+
+// the whole system in a corner while the camera is zoomed in: every body, the world's
+// bounds and the part of it on screen. Clicking in it moves the camera there
+pub struct Minimap {
+    pub visible: bool,
+    // where it was drawn last frame and the world area it showed, for clicks before it's
+    // drawn again
+    shown: Option<(Rect, Rect)>,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap { visible: true, shown: None }
+    }
+}
+
+impl Minimap {
+    pub fn captures(&self, position: Vec2) -> bool {
+        self.shown.is_some_and(|(rect, _)| rect.contains(position))
+    }
+
+    // recenters the camera on the world position under `position`, if it's on the map
+    pub fn click(&self, position: Vec2, view: &mut View) -> bool {
+        let Some((rect, world)) = self.shown else {
+            return false;
+        };
+        if !rect.contains(position) {
+            return false;
+        }
+        view.center_on(from_map(position, rect, world));
+        true
+    }
+
+    // top right, from `top` down so it stays clear of the panels above it
+    pub fn draw(&mut self, bodies: &Bodies, view: &View, top: f32) {
+        self.shown = None;
+        if !self.visible || view.zoom <= 1.0 {
+            return;
+        }
+        let size = vec2(screen_width(), screen_height());
+        let viewport = [vec2(0.0, 0.0), vec2(size.x, 0.0), size, vec2(0.0, size.y)]
+            .map(|corner| view.screen_to_world(corner));
+        // everything it has to fit: the world, the bodies and the screen
+        let mut low = Vec2::ZERO;
+        let mut high = vec2(SCREEN_WIDTH, SCREEN_HEIGHT);
+        for point in bodies.positions.iter().chain(&viewport) {
+            if point.is_finite() {
+                low = low.min(*point);
+                high = high.max(*point);
+            }
+        }
+        let rect = Rect::new(size.x - MAP_WIDTH - 10.0, top + 10.0, MAP_WIDTH, MAP_HEIGHT);
+        // same scale on both axes, the leftover space split evenly
+        let extent = high - low;
+        let inner = vec2(MAP_WIDTH, MAP_HEIGHT) - 2.0 * MARGIN;
+        let scale = (inner.x / extent.x).min(inner.y / extent.y);
+        let world_size = inner / scale;
+        let world_low = (low + high) / 2.0 - world_size / 2.0;
+        let world = Rect::new(world_low.x, world_low.y, world_size.x, world_size.y);
+        self.shown = Some((rect, world));
+
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, BACKGROUND);
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, GRAY);
+        let bounds = [
+            vec2(0.0, 0.0),
+            vec2(SCREEN_WIDTH, 0.0),
+            vec2(SCREEN_WIDTH, SCREEN_HEIGHT),
+            vec2(0.0, SCREEN_HEIGHT),
+        ].map(|corner| to_map(corner, rect, world));
+        draw_outline(&bounds, DARKGRAY);
+        for (index, position) in bodies.positions.iter().enumerate() {
+            let point = to_map(*position, rect, world);
+            let color = if bodies.selected[index] { YELLOW } else { WHITE };
+            draw_rectangle(point.x - 1.0, point.y - 1.0, 2.0, 2.0, color);
+        }
+        draw_outline(&viewport.map(|corner| to_map(corner, rect, world)), VIEWPORT_COLOR);
+    }
+}
+
+fn to_map(position: Vec2, rect: Rect, world: Rect) -> Vec2 {
+    let share = (position - world.point()) / world.size();
+    rect.point() + MARGIN + share * (rect.size() - 2.0 * MARGIN)
+}
+
+fn from_map(position: Vec2, rect: Rect, world: Rect) -> Vec2 {
+    let share = (position - rect.point() - MARGIN) / (rect.size() - 2.0 * MARGIN);
+    world.point() + share * world.size()
+}
+
+fn draw_outline(corners: &[Vec2; 4], color: Color) {
+    for i in 0..corners.len() {
+        let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+        draw_line(a.x, a.y, b.x, b.y, 1.0, color);
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use macroquad::math::Vec2;
+use rand::rngs::StdRng;
+use rand::{ Rng, SeedableRng };
+
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+
+// mixed into the run's seed so the kicks don't follow the stream the presets are built from
+const STREAM: u64 = 0x6e6f697365;
+
+// small random accelerations on every body standing in for whatever the simulation leaves
+// out, to see how well an orbit holds up against them. Each body's kick drifts from one
+// random direction to the next over `Settings::noise_correlation` (an Ornstein-Uhlenbeck
+// process), each component spread by `Settings::noise_amplitude`. It has its own generator
+// seeded from `Settings::seed`, so a seed reproduces the kicks along with the bodies
+pub struct Noise {
+    rng: StdRng,
+    // by body id, a body keeps its kick when others are added or removed
+    kicks: HashMap<u64, Vec2>,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        Noise {
+            rng: StdRng::seed_from_u64(seed ^ STREAM),
+            kicks: HashMap::new(),
+        }
+    }
+
+    // back to the start of the sequence, with the preset it goes with
+    pub fn reset(&mut self, seed: u64) {
+        *self = Noise::new(seed);
+    }
+
+    // moves every kick on by `dt` and adds it to the external forces
+    pub fn perturb(&mut self, bodies: &mut Bodies, settings: &Settings, dt: f32) {
+        if settings.noise_amplitude <= 0.0 {
+            self.kicks.clear();
+            return;
+        }
+        // how much of the last kick is left after `dt`, and how much new noise keeps the
+        // spread at the amplitude
+        let keep = (-dt / settings.noise_correlation.max(f32::EPSILON)).exp();
+        let fresh = settings.noise_amplitude * (1.0 - keep * keep).sqrt();
+        let mut kicks = HashMap::with_capacity(bodies.len());
+        for index in 0..bodies.len() {
+            if bodies.freezed[index] {
+                continue;
+            }
+            let id = bodies.ids[index];
+            let kick = match self.kicks.get(&id) {
+                Some(&kick) => kick * keep + self.gaussian() * fresh,
+                // new bodies start with a kick of the right size rather than none
+                None => self.gaussian() * settings.noise_amplitude,
+            };
+            bodies.external_forces[index] += kick * bodies.masses[index];
+            kicks.insert(id, kick);
+        }
+        self.kicks = kicks;
+    }
+
+    // standard normal in each component, by the Box-Muller transform
+    fn gaussian(&mut self) -> Vec2 {
+        let u: f32 = self.rng.gen_range(1e-6..1.0);
+        let v: f32 = self.rng.gen_range(0.0..1.0);
+        Vec2::from_angle(TAU * v) * (-2.0 * u.ln()).sqrt()
+    }
+}
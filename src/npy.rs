@@ -0,0 +1,133 @@
+// just enough of NumPy's file formats to hand arrays over to Python: `.npy` version 1.0
+// with little endian f32 data, and `.npz`, which is a zip of those stored uncompressed. The
+// zips also carry gallery bundles, see `gallery`
+
+// `np.load` reads this back as a float32 array of the given shape, in C order
+pub fn encode(shape: &[usize], data: &[f32]) -> Vec<u8> {
+    debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+    let dimensions: Vec<String> = shape.iter().map(|size| size.to_string()).collect();
+    // one dimensional shapes need the trailing comma to be a tuple
+    let shape = if dimensions.len() == 1 {
+        format!("({},)", dimensions[0])
+    } else {
+        format!("({})", dimensions.join(", "))
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {}, }}", shape);
+    // magic, version and header length take 10 bytes, the whole preamble pads out to a
+    // multiple of 64 and ends in a newline
+    let padding = 63 - ((10 + header.len()) % 64);
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + 4 * data.len());
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for value in data {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+// a zip archive of named files without compression. Arrays go in as `<name>.npy`, which
+// `np.load` then offers under `<name>`
+pub fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        // local header, then the data
+        archive.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        file_header(&mut archive, name, data, crc);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        // the same again for the central directory, plus where to find the file
+        directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        file_header(&mut directory, name, data, crc);
+        // comment length, disk, internal and external attributes
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let start = archive.len() as u32;
+    let count = files.len() as u16;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    // this disk and the one the directory starts on
+    archive.extend_from_slice(&[0; 4]);
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&count.to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&start.to_le_bytes());
+    // comment length
+    archive.extend_from_slice(&[0; 2]);
+    archive
+}
+
+// the files of a zip whose entries are stored, as `zip` writes them. Only the local headers
+// are read, archives with data descriptors or compression are turned down
+pub fn unzip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let word = |at: usize| -> Result<u32, String> {
+        let word = bytes.get(at..at + 4).ok_or("the archive is cut short")?;
+        Ok(u32::from_le_bytes(word.try_into().unwrap()))
+    };
+    let half = |at: usize| word(at).map(|value| value & 0xffff);
+    let mut files = Vec::new();
+    let mut at = 0;
+    while at < bytes.len() && word(at)? == 0x04034b50 {
+        let (flags, method) = (half(at + 6)?, half(at + 8)?);
+        if flags & 0x08 != 0 || method != 0 {
+            return Err("only uncompressed archives can be read".to_string());
+        }
+        let (crc, size) = (word(at + 14)?, word(at + 18)? as usize);
+        let (name_length, extra_length) = (half(at + 26)? as usize, half(at + 28)? as usize);
+        let name_start = at + 30;
+        let data_start = name_start + name_length + extra_length;
+        let (Some(name), Some(data)) = (
+            bytes.get(name_start..name_start + name_length),
+            bytes.get(data_start..data_start + size),
+        ) else {
+            return Err("the archive is cut short".to_string());
+        };
+        let name = String::from_utf8(name.to_vec()).map_err(|_| "a file name isn't UTF-8")?;
+        if crc32(data) != crc {
+            return Err(format!("{} is damaged", name));
+        }
+        files.push((name, data.to_vec()));
+        at = data_start + size;
+    }
+    if files.is_empty() {
+        return Err("not a zip archive".to_string());
+    }
+    Ok(files)
+}
+
+// the fields local and central headers share: version needed, flags, method (stored),
+// modification time and date (midnight, 1980-01-01), checksum, sizes and the name length
+// with no extra field
+fn file_header(out: &mut Vec<u8>, name: &str, data: &[u8], crc: u32) {
+    out.extend_from_slice(&20u16.to_le_bytes());
+    out.extend_from_slice(&[0; 6]);
+    out.extend_from_slice(&0x21u16.to_le_bytes());
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    out.extend_from_slice(&[0; 2]);
+}
+
+// the CRC-32 zip uses, a bit at a time, the arrays are small
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,164 @@
+use std::env;
+use std::fmt::{ Display, LowerExp };
+
+// automatic notation goes scientific from this magnitude up
+const SCIENTIFIC_ABOVE: f64 = 1e5;
+// every factor of 1000 from pico to tera
+const PREFIXES: [&str; 9] = ["p", "n", "µ", "m", "", "k", "M", "G", "T"];
+// where `PREFIXES` has no prefix
+const UNPREFIXED: i32 = 4;
+// languages that write a decimal comma, by the first part of the locale name
+const COMMA_LANGUAGES: [&str; 24] = [
+    "cs", "da", "de", "el", "es", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv", "nb", "nl",
+    "nn", "pl", "pt", "ro", "ru", "sk", "sl", "sv", "tr",
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Notation {
+    // fixed point, scientific where that would be all zeros or too many digits
+    Auto,
+    Scientific,
+    // fixed point with an SI prefix for every factor of 1000
+    Si,
+}
+
+impl Notation {
+    pub const ALL: [Notation; 3] = [Notation::Auto, Notation::Scientific, Notation::Si];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Notation::Auto => "auto",
+            Notation::Scientific => "scientific",
+            Notation::Si => "si",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Notation::ALL.into_iter().find(|notation| notation.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Separator {
+    Point,
+    Comma,
+}
+
+impl Separator {
+    pub const ALL: [Separator; 2] = [Separator::Point, Separator::Comma];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Separator::Point => "point",
+            Separator::Comma => "comma",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Separator::ALL.into_iter().find(|separator| separator.name() == name)
+    }
+
+    // the decimal separator of the locale the process runs in, `de_DE.UTF-8` and the like.
+    // The browser has no environment, it stays with the point
+    pub fn from_locale() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|locale| !locale.is_empty());
+        let language = locale
+            .as_deref()
+            .and_then(|locale| locale.split(['_', '.', '-']).next())
+            .unwrap_or("");
+        if COMMA_LANGUAGES.contains(&language) {
+            Separator::Comma
+        } else {
+            Separator::Point
+        }
+    }
+}
+
+// how quantities are written on screen: the HUD, the overlays and the console. Files are
+// written with `exact` so they read back the same anywhere
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Numbers {
+    pub notation: Notation,
+    pub separator: Separator,
+}
+
+impl Default for Numbers {
+    fn default() -> Self {
+        Numbers { notation: Notation::Auto, separator: Separator::Point }
+    }
+}
+
+impl Numbers {
+    // `decimals` after the separator, of the mantissa where it's scientific or prefixed
+    pub fn format(&self, value: impl Into<f64>, decimals: usize) -> String {
+        let (number, prefix) = self.split(value.into(), decimals);
+        format!("{}{}", number, prefix)
+    }
+
+    // with a plus sign in front of positive values, for changes
+    pub fn signed(&self, value: impl Into<f64>, decimals: usize) -> String {
+        let value = value.into();
+        let sign = if value >= 0.0 { "+" } else { "" };
+        format!("{}{}", sign, self.format(value, decimals))
+    }
+
+    // followed by `unit`, which takes the SI prefix
+    pub fn quantity(&self, value: impl Into<f64>, decimals: usize, unit: &str) -> String {
+        let (number, prefix) = self.split(value.into(), decimals);
+        format!("{} {}{}", number, prefix, unit)
+    }
+
+    // the number with the separator, and the SI prefix that goes after it
+    fn split(&self, value: f64, decimals: usize) -> (String, &'static str) {
+        if !value.is_finite() {
+            return (format!("{}", value), "");
+        }
+        let magnitude = value.abs();
+        let (number, prefix) = match self.notation {
+            Notation::Auto => {
+                // what would round to zero is written out rather than shown as 0.00
+                let tiny = magnitude > 0.0 && magnitude < 0.5 * (10.0f64).powi(-(decimals as i32));
+                if tiny || magnitude >= SCIENTIFIC_ABOVE {
+                    (format!("{:.*e}", decimals, value), "")
+                } else {
+                    (format!("{:.*}", decimals, value), "")
+                }
+            }
+            Notation::Scientific => (format!("{:.*e}", decimals, value), ""),
+            Notation::Si => {
+                let mut power = if magnitude > 0.0 {
+                    (magnitude.log10() / 3.0).floor() as i32
+                } else {
+                    0
+                };
+                power = power.clamp(-UNPREFIXED, (PREFIXES.len() as i32) - 1 - UNPREFIXED);
+                let mut mantissa = value / (1000.0f64).powi(power);
+                // 999.96 rounds up to the next prefix rather than to 1000.0
+                let rounded = format!("{:.*}", decimals, mantissa.abs());
+                if rounded.parse::<f64>().unwrap_or(0.0) >= 1000.0 && power < UNPREFIXED {
+                    power += 1;
+                    mantissa /= 1000.0;
+                }
+                (format!("{:.*}", decimals, mantissa), PREFIXES[(power + UNPREFIXED) as usize])
+            }
+        };
+        match self.separator {
+            Separator::Point => (number, prefix),
+            Separator::Comma => (number.replace('.', ","), prefix),
+        }
+    }
+}
+
+// every digit it takes to read the value back, with a point and an exponent where plain
+// digits would run long. For files, the same whatever the display settings
+pub fn exact<T: Copy + Display + LowerExp + Into<f64>>(value: T) -> String {
+    let magnitude = value.into().abs();
+    if magnitude != 0.0 && !(1e-4..1e15).contains(&magnitude) {
+        format!("{:e}", value)
+    } else {
+        format!("{}", value)
+    }
+}
@@ -0,0 +1,78 @@
+use egui_macroquad::egui::{ self, Align2, ComboBox, Slider };
+use macroquad::prelude::*;
+
+use crate::integrator::Integrator;
+use crate::settings::{ Settings, MAX_DT, MAX_SUBSTEPS, MAX_TIME_SCALE, MIN_DT, MIN_TIME_SCALE };
+use crate::NUM_OF_BODIES;
+
+const PANEL_WIDTH: f32 = 320.0;
+// below the collision statistics, which share the right edge
+const PANEL_TOP: f32 = 130.0;
+
+// sliders for the constants worth tuning while the simulation runs, the same settings the
+// console's `set` changes. Drawn with egui, which wants `update` and `draw` every frame
+// whether the panel shows or not
+#[derive(Default)]
+pub struct ControlPanel {
+    pub visible: bool,
+}
+
+impl ControlPanel {
+    // whether the pointer is over the panel or a field of it is being typed into, as of the
+    // last frame, so the mouse and keys belong to it
+    pub fn captures(&self) -> bool {
+        let mut captures = false;
+        egui_macroquad::cfg(|context| {
+            captures = context.is_pointer_over_area() || context.wants_keyboard_input();
+        });
+        self.visible && captures
+    }
+
+    // lays the panel out for this frame, `draw` puts it on screen. Returns whether the preset
+    // should be loaded again with the new body count
+    pub fn update(&self, settings: &mut Settings) -> bool {
+        let mut restart = false;
+        egui_macroquad::ui(|context| {
+            if !self.visible {
+                return;
+            }
+            egui::Window::new("controls")
+                .anchor(Align2::RIGHT_TOP, [0.0, PANEL_TOP])
+                .default_width(PANEL_WIDTH)
+                .resizable(false)
+                .collapsible(false)
+                .show(context, |ui| {
+                    ui.add(Slider::new(&mut settings.gravity, 0.0..=5.0).text("G"));
+                    ui.add(Slider::new(&mut settings.dt, MIN_DT..=MAX_DT).text("dt"));
+                    ui.add(Slider::new(&mut settings.substeps, 1..=MAX_SUBSTEPS).text("substeps"));
+                    let range = MIN_TIME_SCALE..=MAX_TIME_SCALE;
+                    ui.add(Slider::new(&mut settings.time_scale, range).text("time scale"));
+                    let restitution = Slider::new(&mut settings.restitution, 0.0..=1.0);
+                    ui.add(restitution.text("restitution"));
+                    let wall_friction = Slider::new(&mut settings.wall_friction, 0.0..=1.0);
+                    ui.add(wall_friction.text("wall friction"));
+                    let max_velocity = Slider::new(&mut settings.max_velocity, 1.0..=100.0);
+                    ui.add(max_velocity.text("max velocity"));
+                    let body_count = Slider::new(&mut settings.body_count, 1..=4 * NUM_OF_BODIES);
+                    ui.add(body_count.text("body count"));
+
+                    ComboBox::from_label("integrator")
+                        .selected_text(settings.integrator.name())
+                        .show_ui(ui, |ui| {
+                            for integrator in Integrator::ALL {
+                                let name = integrator.name();
+                                ui.selectable_value(&mut settings.integrator, integrator, name);
+                            }
+                        });
+
+                    restart = ui.button("restart with this body count").clicked();
+                });
+        });
+        restart
+    }
+
+    // over everything drawn so far
+    pub fn draw(&self) {
+        egui_macroquad::draw();
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+// one pixel per frame, four seconds at 60 fps
+const HISTORY_FRAMES: usize = 240;
+const GRAPH_HEIGHT: f32 = 60.0;
+const GRAPH_TOP: f32 = 20.0;
+const BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+const BUDGET_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.25);
+const PHYSICS_COLOR: Color = Color::new(0.3, 0.6, 1.0, 1.0);
+const FORCES_COLOR: Color = Color::new(1.0, 0.6, 0.2, 1.0);
+const RENDER_COLOR: Color = Color::new(0.4, 0.9, 0.4, 1.0);
+// what one frame may take at 60 fps, drawn across the graph once the scale reaches it
+const FRAME_BUDGET_MS: f32 = 1000.0 / 60.0;
+
+// seconds spent in `solver::compute_forces` since it was last taken. The integrator evaluates
+// forces of its own at every kick, so they're counted where they're computed
+static FORCE_SECONDS: Mutex<f64> = Mutex::new(0.0);
+
+pub fn add_force_time(seconds: f64) {
+    *FORCE_SECONDS.lock().unwrap() += seconds;
+}
+
+fn take_force_time() -> f64 {
+    std::mem::take(&mut *FORCE_SECONDS.lock().unwrap())
+}
+
+// milliseconds one frame spent on each
+#[derive(Clone, Copy, Default)]
+struct FrameTimes {
+    // the whole step, forces included
+    physics: f32,
+    forces: f32,
+    // building the draw calls, the GPU does its part after the frame ends
+    render: f32,
+}
+
+impl FrameTimes {
+    fn series(&self) -> [f32; 3] {
+        [self.physics, self.forces, self.render]
+    }
+}
+
+// physics step, force and render times of the last few seconds of frames, where the fps
+// number used to be, so a slow frame shows which part of it got slower
+pub struct PerfGraph {
+    pub visible: bool,
+    frames: VecDeque<FrameTimes>,
+    current: FrameTimes,
+    physics_started: Option<f64>,
+    render_started: Option<f64>,
+}
+
+impl Default for PerfGraph {
+    fn default() -> Self {
+        PerfGraph {
+            visible: true,
+            frames: VecDeque::with_capacity(HISTORY_FRAMES),
+            current: FrameTimes::default(),
+            physics_started: None,
+            render_started: None,
+        }
+    }
+}
+
+impl PerfGraph {
+    // around every part of the frame that advances the bodies, forces counted in between
+    // only, so a prediction or a benchmark doesn't show up as physics
+    pub fn start_physics(&mut self) {
+        take_force_time();
+        self.physics_started = Some(date::now());
+    }
+
+    pub fn stop_physics(&mut self) {
+        if let Some(started) = self.physics_started.take() {
+            self.current.physics += milliseconds(date::now() - started);
+            self.current.forces += milliseconds(take_force_time());
+        }
+    }
+
+    pub fn start_render(&mut self) {
+        self.render_started = Some(date::now());
+    }
+
+    pub fn stop_render(&mut self) {
+        if let Some(started) = self.render_started.take() {
+            self.current.render += milliseconds(date::now() - started);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        if self.frames.len() == HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(std::mem::take(&mut self.current));
+    }
+
+    // top left, with the fps and the latest times underneath. Hidden, just the fps
+    pub fn draw(&self, left: f32, text_color: Color) {
+        let fps = format!("{} fps", get_fps());
+        if !self.visible {
+            draw_text(&fps, left, 100.0, 30.0, text_color);
+            return;
+        }
+        let width = HISTORY_FRAMES as f32;
+        let bottom = GRAPH_TOP + GRAPH_HEIGHT;
+        draw_rectangle(left, GRAPH_TOP, width, GRAPH_HEIGHT, BACKGROUND);
+
+        // rounded up to a power of two so the scale doesn't jitter with every frame
+        let slowest = self.frames
+            .iter()
+            .fold(0.0f32, |slowest, frame| slowest.max(frame.physics).max(frame.render));
+        let scale = slowest.max(1.0).log2().ceil().exp2();
+        if FRAME_BUDGET_MS <= scale {
+            let y = bottom - (FRAME_BUDGET_MS / scale) * GRAPH_HEIGHT;
+            draw_line(left, y, left + width, y, 1.0, BUDGET_COLOR);
+        }
+        // the newest frame at the right edge
+        let offset = width - (self.frames.len() as f32);
+        for (series, color) in [PHYSICS_COLOR, FORCES_COLOR, RENDER_COLOR].into_iter().enumerate() {
+            let mut previous: Option<Vec2> = None;
+            for (x, frame) in self.frames.iter().enumerate() {
+                let point = vec2(
+                    left + offset + (x as f32),
+                    bottom - (frame.series()[series] / scale).min(1.0) * GRAPH_HEIGHT
+                );
+                if let Some(previous) = previous {
+                    draw_line(previous.x, previous.y, point.x, point.y, 1.0, color);
+                }
+                previous = Some(point);
+            }
+        }
+        draw_text(format!("{} ms", scale), left + width + 4.0, GRAPH_TOP + 10.0, 16.0, GRAY);
+
+        let latest = self.frames.back().copied().unwrap_or_default();
+        let mut x = left;
+        for (text, color) in [
+            (fps, text_color),
+            (format!("physics {:.1}", latest.physics), PHYSICS_COLOR),
+            (format!("forces {:.1}", latest.forces), FORCES_COLOR),
+            (format!("render {:.1} ms", latest.render), RENDER_COLOR),
+        ] {
+            draw_text(&text, x, 100.0, 20.0, color);
+            x += measure_text(&text, None, 20, 1.0).width + 12.0;
+        }
+    }
+}
+
+fn milliseconds(seconds: f64) -> f32 {
+    (seconds * 1000.0) as f32
+}
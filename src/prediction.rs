@@ -0,0 +1,136 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::ewald;
+use crate::settings::Settings;
+use crate::integrator;
+use crate::solver::{ self, Solver };
+use crate::view::View;
+use crate::apply_boundaries;
+
+// how far ahead the prediction looks, in steps
+pub const PREDICTION_STEPS: usize = 600;
+// only the bodies pulling hardest on the one being predicted come along, the rest of the
+// system hardly changes its path over the horizon
+const NEIGHBOURS: usize = 16;
+// within this many contact distances a passing body counts as a close approach
+const CLOSE_APPROACH: f32 = 3.0;
+// steps between the points of a predicted path
+const PATH_STRIDE: usize = 2;
+
+pub struct Approach {
+    pub partner: u64,
+    // from now, in time units
+    pub time: f32,
+    pub distance: f32,
+    pub collision: bool,
+}
+
+// `index` and the bodies pulling hardest on it, `index` first, as a small system that can
+// be stepped ahead cheaply every frame
+pub fn neighbourhood(bodies: &Bodies, index: usize, gravity: f32) -> Bodies {
+    let mut others: Vec<(usize, f32)> = (0..bodies.len())
+        .filter(|&j| j != index)
+        .map(|j| (j, bodies.force_between(index, j, gravity).length()))
+        .collect();
+    others.sort_by(|a, b| b.1.total_cmp(&a.1));
+    others.truncate(NEIGHBOURS);
+
+    let mut system = Bodies::with_capacity(others.len() + 1);
+    system.push(bodies.body(index));
+    for (j, _) in others {
+        system.push(bodies.body(j));
+    }
+    system
+}
+
+// steps the system ahead without resolving collisions, calling `visit` with the step
+// number after each step until it returns false
+pub fn advance(
+    system: &mut Bodies,
+    settings: &Settings,
+    mut visit: impl FnMut(usize, &Bodies) -> bool
+) {
+    // a handful of bodies is summed directly whatever the solver
+    let settings = Settings { solver: Solver::BruteForce, ..settings.clone() };
+    for step in 1..=PREDICTION_STEPS {
+        solver::compute_forces(system, &settings);
+        integrator::advance(system, &settings);
+        apply_boundaries(system, &settings);
+        if !visit(step, system) {
+            return;
+        }
+    }
+}
+
+// the soonest collision or close approach `index` is heading for within the horizon: the
+// first partner to touch it, or the closest point of the first pass within `CLOSE_APPROACH`
+pub fn soonest_approach(bodies: &Bodies, index: usize, settings: &Settings) -> Option<Approach> {
+    let mut system = neighbourhood(bodies, index, settings.gravity);
+    let mut approach: Option<Approach> = None;
+    let mut done = false;
+
+    advance(&mut system, settings, |step, system| {
+        let time = (step as f32) * settings.dt;
+        for j in 1..system.len() {
+            let distance = system.positions[0].distance(system.positions[j]);
+            let contact = system.body(0).contact_distance(&system.body(j), &settings.hacks);
+            let partner = system.ids[j];
+            if distance < contact {
+                approach = Some(Approach { partner, time, distance, collision: true });
+                done = true;
+                return false;
+            }
+            match &mut approach {
+                // still closing in, or already past the closest point
+                Some(closest) if closest.partner == partner => {
+                    if distance < closest.distance {
+                        closest.time = time;
+                        closest.distance = distance;
+                    } else {
+                        done = true;
+                    }
+                }
+                None if distance < CLOSE_APPROACH * contact => {
+                    approach = Some(Approach { partner, time, distance, collision: false });
+                }
+                _ => {}
+            }
+        }
+        !done
+    });
+
+    // a pass still getting closer at the horizon may yet become a collision, don't guess
+    if done { approach } else { None }
+}
+
+// where `index` is headed over the horizon, from where it is now, until it runs into one
+// of its neighbours
+pub fn path(bodies: &Bodies, index: usize, settings: &Settings) -> Vec<Vec2> {
+    let mut system = neighbourhood(bodies, index, settings.gravity);
+    let mut path = vec![system.positions[0]];
+    advance(&mut system, settings, |step, system| {
+        let touching = (1..system.len()).any(|j| {
+            let contact = system.body(0).contact_distance(&system.body(j), &settings.hacks);
+            system.positions[0].distance(system.positions[j]) < contact
+        });
+        if step % PATH_STRIDE == 0 || touching {
+            path.push(system.positions[0]);
+        }
+        !touching
+    });
+    path
+}
+
+// fading out towards the horizon, where it's least certain
+pub fn draw_path(path: &[Vec2], view: &View) {
+    for (i, pair) in path.windows(2).enumerate() {
+        // jumps across the seam of a periodic world aren't part of the path
+        if ewald::crosses_seam(pair[0], pair[1]) {
+            continue;
+        }
+        let alpha = 0.8 * (1.0 - (i as f32) / (path.len() as f32));
+        let (from, to) = (view.world_to_screen(pair[0]), view.world_to_screen(pair[1]));
+        draw_line(from.x, from.y, to.x, to.y, 1.0, Color::new(0.5, 0.9, 1.0, alpha));
+    }
+}
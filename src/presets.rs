@@ -0,0 +1,363 @@
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::{ diagnostics, generators, rng };
+use crate::metadata::{ Metadata, Value };
+use crate::settings::Settings;
+use crate::solver::Solver;
+use crate::units::Scale;
+use crate::{ Body, SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// Chenciner & Montgomery's figure-eight choreography for G = 1 and unit masses: the third
+// body starts in the middle, the other two mirrored around it with half its velocity each.
+// The published values are rounded to what fits an f32.
+const FIGURE_EIGHT_POSITION: Vec2 = Vec2::new(0.9700044, -0.2430875);
+const FIGURE_EIGHT_VELOCITY: Vec2 = Vec2::new(-0.9324074, -0.8647315);
+// on screen size and mass of the choreography, its period is 6.3259 * sqrt(L^3 / (G M))
+const FIGURE_EIGHT_LENGTH: f32 = 150.0;
+const FIGURE_EIGHT_MASS: f32 = 5000.0;
+// about 800 steps per period keeps the orbit closed for a long time
+const FIGURE_EIGHT_DT: f32 = 0.2;
+
+// name, semi-major axis in AU, mass in solar masses, J2000 mean longitude in degrees
+const PLANETS: [(&str, f32, f32, f32); 8] = [
+    ("Mercury", 0.387, 1.66e-7, 252.25),
+    ("Venus", 0.723, 2.448e-6, 181.98),
+    ("Earth", 1.0, 3.003e-6, 100.46),
+    ("Mars", 1.524, 3.227e-7, 355.45),
+    ("Jupiter", 5.203, 9.545e-4, 34.4),
+    ("Saturn", 9.537, 2.858e-4, 49.94),
+    ("Uranus", 19.19, 4.366e-5, 313.23),
+    ("Neptune", 30.07, 5.151e-5, 304.88),
+];
+// Neptune ends up just inside the bottom edge and a year takes 600 frames at the default dt
+const SOLAR_SCALE: Scale = Scale {
+    au: 51.0,
+    exponent: 0.5,
+    year: 300.0,
+    epoch: 0.0,
+};
+
+const GALAXY_STARS: usize = 2000;
+const GALAXY_CORE_MASS: f32 = 3600.0;
+const GALAXY_STAR_MASS: f32 = 0.2;
+// the inner edge keeps orbital speeds well under `MAX_VELOCITY`
+const GALAXY_INNER_RADIUS: f32 = 35.0;
+const GALAXY_OUTER_RADIUS: f32 = 130.0;
+// the innermost stars still get ~100 steps per orbit
+const GALAXY_DT: f32 = 0.2;
+
+// unequal stars on a circular mutual orbit. The planet starts a little inside the critical
+// distance (about 2.4 separations for this mass ratio) where no orbit is stable, so it
+// wanders chaotically for a few minutes before the binary flings it out
+const BINARY_MASSES: [f32; 2] = [2400.0, 1600.0];
+const BINARY_SEPARATION: f32 = 60.0;
+const PLANET_MASS: f32 = 5.0;
+const PLANET_DISTANCE: f32 = 2.1 * BINARY_SEPARATION;
+// around 180 steps per binary period
+const BINARY_DT: f32 = 0.25;
+
+// small enough that the whole cluster, out to its cutoff, fits between the walls
+const PLUMMER_RADIUS: f32 = 50.0;
+const DISK_RADIUS: f32 = 250.0;
+
+// a light planet on an eccentric orbit close to a heavy star, with the 1PN correction on.
+// Its periapsis advances about 10 degrees a turn, `set relativity 0` for the closed
+// Newtonian ellipse to compare
+const MERCURY_STAR_MASS: f32 = 15000.0;
+const MERCURY_SEMI_MAJOR_AXIS: f32 = 150.0;
+const MERCURY_ECCENTRICITY: f32 = 0.5;
+// around 470 steps per orbit
+const MERCURY_DT: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Preset {
+    Random,
+    FigureEight,
+    SolarSystem,
+    Galaxies,
+    CircumbinaryPlanet,
+    Plummer,
+    RotatingDisk,
+    Mercury,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 8] = [
+        Preset::Random,
+        Preset::FigureEight,
+        Preset::SolarSystem,
+        Preset::Galaxies,
+        Preset::CircumbinaryPlanet,
+        Preset::Plummer,
+        Preset::RotatingDisk,
+        Preset::Mercury,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            Preset::Random => Preset::FigureEight,
+            Preset::FigureEight => Preset::SolarSystem,
+            Preset::SolarSystem => Preset::Galaxies,
+            Preset::Galaxies => Preset::CircumbinaryPlanet,
+            Preset::CircumbinaryPlanet => Preset::Plummer,
+            Preset::Plummer => Preset::RotatingDisk,
+            Preset::RotatingDisk => Preset::Mercury,
+            Preset::Mercury => Preset::Random,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Random => "random",
+            Preset::FigureEight => "figure8",
+            Preset::SolarSystem => "solar",
+            Preset::Galaxies => "galaxies",
+            Preset::CircumbinaryPlanet => "binary",
+            Preset::Plummer => "plummer",
+            Preset::RotatingDisk => "disk",
+            Preset::Mercury => "mercury",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Preset::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    // replaces every body and sets the gravity, time step and units the scenario was made for
+    pub fn load(
+        self,
+        bodies: &mut Bodies,
+        settings: &mut Settings,
+        metadata: &mut Metadata,
+        time: f32
+    ) {
+        let defaults = Settings::default();
+        rng::seed(settings.seed);
+        bodies.clear();
+        metadata.clear();
+        settings.scale = None;
+        settings.relativity = defaults.relativity;
+        settings.solver = settings.default_solver;
+
+        match self {
+            Preset::Random => {
+                settings.gravity = defaults.gravity;
+                settings.dt = defaults.dt;
+                for _ in 0..settings.body_count {
+                    bodies.push(Body::random(None));
+                }
+            }
+            Preset::FigureEight => {
+                settings.gravity = defaults.gravity;
+                settings.dt = FIGURE_EIGHT_DT;
+
+                // lengths scale by L and masses by M, so velocities scale by sqrt(G M / L)
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let speed = (settings.gravity * FIGURE_EIGHT_MASS / FIGURE_EIGHT_LENGTH).sqrt();
+                let states = [
+                    (FIGURE_EIGHT_POSITION, -FIGURE_EIGHT_VELOCITY / 2.0),
+                    (-FIGURE_EIGHT_POSITION, -FIGURE_EIGHT_VELOCITY / 2.0),
+                    (Vec2::ZERO, FIGURE_EIGHT_VELOCITY),
+                ];
+                for ((position, velocity), name) in states.into_iter().zip(["A", "B", "C"]) {
+                    let mut body = Body::new(center + position * FIGURE_EIGHT_LENGTH);
+                    body.velocity = velocity * speed;
+                    body.mass = FIGURE_EIGHT_MASS;
+                    body.radius = 8.0;
+                    metadata.set(body.id, "name", Value::Text(name.to_string()));
+                    bodies.push(body);
+                }
+            }
+            Preset::SolarSystem => {
+                settings.gravity = defaults.gravity;
+                settings.dt = defaults.dt;
+                let scale = Scale { epoch: time, ..SOLAR_SCALE };
+                settings.scale = Some(scale);
+
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let sun_mass = scale.central_mass(settings.gravity);
+                let mut sun = Body::new(center);
+                sun.mass = sun_mass;
+                sun.radius = 12.0;
+
+                // circular orbits, counterclockwise on screen where y points down
+                let mut planets = Vec::new();
+                for (name, semi_major_axis, mass, longitude) in PLANETS {
+                    let radius = scale.distance(semi_major_axis);
+                    let direction = Vec2::from_angle(longitude.to_radians()) * vec2(1.0, -1.0);
+                    let speed = scale.circular_speed(settings.gravity, sun_mass, radius);
+
+                    let mut planet = Body::new(center + direction * radius);
+                    planet.velocity = -direction.perp() * speed;
+                    planet.mass = mass * sun_mass;
+                    planet.radius = if mass > 1e-5 { 6.0 } else { 3.0 };
+                    planets.push((name, planet));
+                }
+
+                // the sun recoils so the system as a whole stays put
+                let momentum = planets
+                    .iter()
+                    .fold(Vec2::ZERO, |sum, (_, planet)| sum + planet.velocity * planet.mass);
+                sun.velocity = -momentum / sun.mass;
+                metadata.set(sun.id, "name", Value::Text("Sun".to_string()));
+                bodies.push(sun);
+                for (name, planet) in planets {
+                    metadata.set(planet.id, "name", Value::Text(name.to_string()));
+                    bodies.push(planet);
+                }
+            }
+            Preset::Galaxies => {
+                settings.gravity = defaults.gravity;
+                settings.dt = GALAXY_DT;
+                // thousands of bodies is what the tree codes are for
+                settings.solver = Solver::BarnesHut;
+
+                // offset so they swing past each other before merging, the second one retrograde
+                let galaxies = [
+                    ("A", vec2(200.0, 240.0), vec2(0.8, 0.3), false),
+                    ("B", vec2(600.0, 360.0), vec2(-0.8, -0.3), true),
+                ];
+                for (name, center, velocity, clockwise) in galaxies {
+                    galaxy(bodies, metadata, name, center, velocity, clockwise, settings.gravity);
+                }
+            }
+            Preset::CircumbinaryPlanet => {
+                settings.gravity = defaults.gravity;
+                settings.dt = BINARY_DT;
+
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let [primary, secondary] = BINARY_MASSES;
+                let total = primary + secondary;
+                // each star sits at the far side of the barycenter from the other
+                let relative_speed = (settings.gravity * total / BINARY_SEPARATION).sqrt();
+                let stars = [
+                    ("primary", primary, secondary / total, -1.0),
+                    ("secondary", secondary, primary / total, 1.0),
+                ];
+                for (name, mass, share, side) in stars {
+                    let mut star = Body::new(center + vec2(side * BINARY_SEPARATION * share, 0.0));
+                    star.velocity = vec2(0.0, -side * relative_speed * share);
+                    star.mass = mass;
+                    star.radius = 8.0;
+                    metadata.set(star.id, "name", Value::Text(name.to_string()));
+                    bodies.push(star);
+                }
+
+                // circular orbit around the pair as if it were a single mass
+                let speed = (settings.gravity * total / PLANET_DISTANCE).sqrt();
+                let mut planet = Body::new(center + vec2(PLANET_DISTANCE, 0.0));
+                planet.velocity = vec2(0.0, -speed);
+                planet.mass = PLANET_MASS;
+                planet.radius = 4.0;
+                metadata.set(planet.id, "name", Value::Text("planet".to_string()));
+                bodies.push(planet);
+
+                // take out the planet's share of the momentum so the system doesn't drift
+                let drift = diagnostics::linear_momentum(bodies) / diagnostics::total_mass(bodies);
+                for velocity in bodies.velocities.iter_mut() {
+                    *velocity -= drift;
+                }
+            }
+            Preset::Plummer => {
+                settings.gravity = defaults.gravity;
+                settings.dt = defaults.dt;
+
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let stars = generators::plummer(
+                    settings.body_count,
+                    center,
+                    PLUMMER_RADIUS,
+                    settings.gravity
+                );
+                bodies.extend(stars);
+            }
+            Preset::RotatingDisk => {
+                settings.gravity = defaults.gravity;
+                settings.dt = defaults.dt;
+                // a flat mass distribution is where the tree codes shine
+                settings.solver = Solver::BarnesHut;
+
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let disk = generators::rotating_disk(
+                    settings.body_count,
+                    center,
+                    DISK_RADIUS,
+                    settings.gravity
+                );
+                bodies.extend(disk);
+            }
+            Preset::Mercury => {
+                settings.gravity = defaults.gravity;
+                settings.dt = MERCURY_DT;
+                settings.relativity = true;
+
+                // periapsis to the right of the star, the orbit's center on the screen's
+                let (axis, eccentricity) = (MERCURY_SEMI_MAJOR_AXIS, MERCURY_ECCENTRICITY);
+                let center = vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0);
+                let mut star = Body::new(center + vec2(axis * eccentricity, 0.0));
+                star.mass = MERCURY_STAR_MASS;
+                star.radius = 10.0;
+
+                let periapsis = axis * (1.0 - eccentricity);
+                let speed = (
+                    (settings.gravity * MERCURY_STAR_MASS * (1.0 + eccentricity)) /
+                    periapsis
+                ).sqrt();
+                let mut planet = Body::new(star.position + vec2(periapsis, 0.0));
+                planet.velocity = vec2(0.0, -speed);
+                planet.mass = 1.0;
+                planet.radius = 3.0;
+                star.velocity = -planet.velocity * planet.mass / star.mass;
+
+                metadata.set(star.id, "name", Value::Text("star".to_string()));
+                metadata.set(planet.id, "name", Value::Text("Mercury".to_string()));
+                bodies.push(star);
+                bodies.push(planet);
+            }
+        }
+    }
+}
+
+// a heavy core with a thin disk of stars on circular orbits around it, all moving together
+fn galaxy(
+    bodies: &mut Bodies,
+    metadata: &mut Metadata,
+    name: &str,
+    center: Vec2,
+    velocity: Vec2,
+    clockwise: bool,
+    gravity: f32
+) {
+    let mut core = Body::new(center);
+    core.velocity = velocity;
+    core.mass = GALAXY_CORE_MASS;
+    core.radius = 6.0;
+    metadata.set(core.id, "galaxy", Value::Text(name.to_string()));
+    metadata.set(core.id, "name", Value::Text(format!("galaxy {}", name)));
+    bodies.push(core);
+
+    // sqrt spreads the stars evenly over the disk area
+    let (inner, outer) = (GALAXY_INNER_RADIUS.powi(2), GALAXY_OUTER_RADIUS.powi(2));
+    let mut radii: Vec<f32> = (0..GALAXY_STARS)
+        .map(|_| rng::gen_range(inner, outer).sqrt())
+        .collect();
+    radii.sort_by(f32::total_cmp);
+
+    for (inside, radius) in radii.into_iter().enumerate() {
+        let direction = Vec2::from_angle(rng::gen_range(0.0, TAU));
+        // the stars further in pull too, so they count towards the mass being orbited
+        let enclosed = GALAXY_CORE_MASS + (inside as f32) * GALAXY_STAR_MASS;
+        let speed = (gravity * enclosed / radius).sqrt();
+        let tangent = if clockwise { -direction.perp() } else { direction.perp() };
+
+        let mut star = Body::new(center + direction * radius);
+        star.velocity = velocity + tangent * speed;
+        star.mass = GALAXY_STAR_MASS;
+        star.radius = 1.5;
+        metadata.set(star.id, "galaxy", Value::Text(name.to_string()));
+        bodies.push(star);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader, Write };
+
+use macroquad::miniquad::date;
+
+use crate::settings::Settings;
+use crate::tide;
+
+// where a file came from: build, platform, seed and settings, written as `# key: value`
+// comment lines at the top of every export so runs can be reproduced and compared later
+pub struct Provenance {
+    pub entries: Vec<(String, String)>,
+}
+
+impl Provenance {
+    pub fn new(settings: &Settings) -> Self {
+        let created = date::now() as u64;
+        let entries = [
+            ("version", env!("CARGO_PKG_VERSION").to_string()),
+            ("commit", env!("THREEBODY_COMMIT").to_string()),
+            ("platform", format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)),
+            ("created", created.to_string()),
+            ("seed", settings.seed.to_string()),
+            ("body_count", settings.body_count.to_string()),
+            ("gravity", settings.gravity.to_string()),
+            ("dt", settings.dt.to_string()),
+            ("substeps", settings.substeps.to_string()),
+            ("time_scale", settings.time_scale.to_string()),
+            ("integrator", settings.integrator.name().to_string()),
+            ("solver", settings.solver.name().to_string()),
+            ("theta", settings.theta.to_string()),
+            ("expansion_order", settings.expansion_order.to_string()),
+            ("leaf_capacity", settings.leaf_capacity.to_string()),
+            ("broadphase", settings.broadphase.name().to_string()),
+            ("ccd", settings.ccd.to_string()),
+            ("simd", settings.simd.to_string()),
+            ("hacks", settings.hacks.names()),
+            ("restitution", settings.restitution.to_string()),
+            ("wall_friction", settings.wall_friction.to_string()),
+            ("max_velocity", settings.max_velocity.to_string()),
+            ("relativity", settings.relativity.to_string()),
+            ("light_speed", settings.light_speed.to_string()),
+            ("periodic", settings.periodic.to_string()),
+            (
+                "separation",
+                if settings.separate {
+                    format!(
+                        "{} over {} passes",
+                        settings.separation,
+                        settings.separation_iterations
+                    )
+                } else {
+                    "off".to_string()
+                },
+            ),
+            (
+                "noise",
+                if settings.noise_amplitude > 0.0 {
+                    format!(
+                        "{} correlated over {}",
+                        settings.noise_amplitude,
+                        settings.noise_correlation
+                    )
+                } else {
+                    "off".to_string()
+                },
+            ),
+            (
+                "tide",
+                if tide::is_active(settings) {
+                    format!(
+                        "{} and {} at {} degrees",
+                        settings.tide_major,
+                        settings.tide_minor,
+                        settings.tide_angle
+                    )
+                } else {
+                    "off".to_string()
+                },
+            ),
+        ];
+
+        Provenance {
+            entries: entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+
+    pub fn write_header(&self, out: &mut impl Write) -> io::Result<()> {
+        for (key, value) in &self.entries {
+            writeln!(out, "# {}: {}", key, value)?;
+        }
+        Ok(())
+    }
+
+    // reads the comment lines back, files from before provenance existed have none
+    pub fn read(path: &str) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let Some(comment) = line.strip_prefix("# ") else {
+                break;
+            };
+            if let Some((key, value)) = comment.split_once(": ") {
+                entries.push((key.to_string(), value.to_string()));
+            }
+        }
+        Ok(Provenance { entries })
+    }
+}
+
+// `threebody analyze info <file>`
+pub fn print_info(path: &str) {
+    match Provenance::read(path) {
+        Ok(provenance) if provenance.entries.is_empty() => {
+            println!("{}: no provenance recorded", path);
+        }
+        Ok(provenance) => {
+            let width = provenance.entries.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+            for (key, value) in provenance.entries {
+                println!("{:<width$}  {}", key, value, width = width);
+            }
+        }
+        Err(err) => eprintln!("{}: {}", path, err),
+    }
+}
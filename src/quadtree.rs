@@ -0,0 +1,121 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+// stops subdividing when bodies sit on top of each other
+const MAX_DEPTH: usize = 24;
+
+pub struct Node {
+    pub center: Vec2,
+    pub half_size: f32,
+    pub mass: f32,
+    pub center_of_mass: Vec2,
+    pub children: Vec<usize>,
+    pub bodies: Vec<usize>,
+}
+
+impl Node {
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        (position.x - self.center.x).abs() <= self.half_size &&
+            (position.y - self.center.y).abs() <= self.half_size
+    }
+
+    // far enough from `position` that the whole cell acts like a single mass at its center
+    pub fn is_far(&self, position: Vec2, theta: f32) -> bool {
+        let distance = position.distance(self.center_of_mass);
+        !self.contains(position) && (2.0 * self.half_size) / distance < theta
+    }
+}
+
+pub struct QuadTree {
+    // parents are always stored before their children, index 0 is the root
+    pub nodes: Vec<Node>,
+}
+
+impl QuadTree {
+    pub fn build(bodies: &Bodies, leaf_capacity: usize) -> Self {
+        let mut tree = QuadTree { nodes: Vec::new() };
+        if bodies.is_empty() {
+            return tree;
+        }
+
+        let (min, max) = bodies.positions
+            .iter()
+            .fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), &position| {
+                (min.min(position), max.max(position))
+            });
+        let center = (min + max) / 2.0;
+        let half_size = ((max - min).max_element() / 2.0).max(1.0);
+
+        tree.insert((0..bodies.len()).collect(), center, half_size, 0, bodies, leaf_capacity.max(1));
+        tree
+    }
+
+    fn insert(
+        &mut self,
+        indices: Vec<usize>,
+        center: Vec2,
+        half_size: f32,
+        depth: usize,
+        bodies: &Bodies,
+        leaf_capacity: usize
+    ) -> usize {
+        let (positions, masses) = (&bodies.positions, &bodies.masses);
+        let mass: f32 = indices.iter().map(|&i| masses[i]).sum();
+        let center_of_mass = if mass > 0.0 {
+            indices.iter().fold(Vec2::ZERO, |sum, &i| sum + positions[i] * masses[i]) / mass
+        } else {
+            center
+        };
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            center,
+            half_size,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            bodies: Vec::new(),
+        });
+
+        if indices.len() <= leaf_capacity || depth >= MAX_DEPTH {
+            self.nodes[index].bodies = indices;
+            return index;
+        }
+
+        let mut quadrants: [Vec<usize>; 4] = Default::default();
+        for i in indices {
+            let position = positions[i];
+            let quadrant =
+                (if position.x < center.x { 0 } else { 1 }) +
+                (if position.y < center.y { 0 } else { 2 });
+            quadrants[quadrant].push(i);
+        }
+
+        let quarter = half_size / 2.0;
+        for (quadrant, members) in quadrants.into_iter().enumerate() {
+            if members.is_empty() {
+                continue;
+            }
+            let offset = vec2(
+                if quadrant % 2 == 0 { -quarter } else { quarter },
+                if quadrant < 2 { -quarter } else { quarter }
+            );
+            let child = self.insert(
+                members,
+                center + offset,
+                quarter,
+                depth + 1,
+                bodies,
+                leaf_capacity
+            );
+            self.nodes[index].children.push(child);
+        }
+
+        index
+    }
+}
@@ -0,0 +1,303 @@
+use macroquad::prelude::*;
+
+use crate::metadata::{ Metadata, Value };
+use crate::Body;
+
+// selection expressions such as `mass > 1000 && speed < 2`, `group == "dust"` or
+// `within(400, 300, 100)`. Names that aren't built in are looked up in the body metadata.
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Name(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 16] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // an exponent, `1e3` or `2.5E-4`, only when digits follow
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let sign = usize::from(chars.get(i + 1).is_some_and(|&c| c == '+' || c == '-'));
+                if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text.parse().map_err(|_| format!("invalid number `{}`", text))?;
+            tokens.push(Token::Number(number));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&other| other == c)
+                .ok_or("unterminated string")?;
+            tokens.push(Token::Text(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let symbol = SYMBOLS.iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| format!("unexpected `{}`", c))?;
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Literal(Value),
+    Field(String),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_symbol(&self, symbols: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Symbol(symbol)) if symbols.contains(symbol) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), String> {
+        if self.peek_symbol(&[symbol]).is_some() {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", symbol))
+        }
+    }
+
+    // left associative chain of binary operators one precedence level down
+    fn binary(
+        &mut self,
+        symbols: &[&'static str],
+        next: fn(&mut Parser) -> Result<Expr, String>
+    ) -> Result<Expr, String> {
+        let mut left = next(self)?;
+        while let Some(symbol) = self.peek_symbol(symbols) {
+            self.position += 1;
+            left = Expr::Binary(symbol, Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.binary(&["||"], Parser::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.binary(&["&&"], Parser::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(&["==", "!=", "<=", ">=", "<", ">"], Parser::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&["+", "-"], Parser::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary(&["*", "/"], Parser::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek_symbol(&["!"]).is_some() {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek_symbol(&["-"]).is_some() {
+            self.position += 1;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("unexpected end of query")?;
+        self.position += 1;
+
+        match token {
+            Token::Number(number) => Ok(Expr::Literal(Value::Number(number))),
+            Token::Text(text) => Ok(Expr::Literal(Value::Text(text))),
+            Token::Name(name) if name == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Token::Name(name) if name == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Token::Name(name) => {
+                if self.peek_symbol(&["("]).is_none() {
+                    return Ok(Expr::Field(name));
+                }
+                self.position += 1;
+                let mut arguments = Vec::new();
+                if self.peek_symbol(&[")"]).is_none() {
+                    arguments.push(self.or()?);
+                    while self.peek_symbol(&[","]).is_some() {
+                        self.position += 1;
+                        arguments.push(self.or()?);
+                    }
+                }
+                self.expect(")")?;
+                Ok(Expr::Call(name, arguments))
+            }
+            Token::Symbol("(") => {
+                let inner = self.or()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Token::Symbol(symbol) => Err(format!("unexpected `{}`", symbol)),
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let expr = parser.or()?;
+    if parser.position != parser.tokens.len() {
+        return Err("unexpected input after the query".to_string());
+    }
+    Ok(expr)
+}
+
+fn number(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        Value::Bool(value) => Ok(if *value { 1.0 } else { 0.0 }),
+        Value::Text(text) => Err(format!("expected a number, got {:?}", text)),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Number(number) => *number != 0.0,
+        Value::Text(text) => !text.is_empty(),
+        Value::Bool(value) => *value,
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, body: &Body, metadata: &Metadata) -> Result<Value, String> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Field(name) => {
+                let number = |value: f32| Ok(Value::Number(value as f64));
+                match name.as_str() {
+                    "id" => Ok(Value::Number(body.id as f64)),
+                    "mass" => number(body.mass),
+                    "radius" => number(body.radius),
+                    "speed" => number(body.velocity.length()),
+                    "x" => number(body.position.x),
+                    "y" => number(body.position.y),
+                    "vx" => number(body.velocity.x),
+                    "vy" => number(body.velocity.y),
+                    "frozen" => Ok(Value::Bool(body.freezed)),
+                    "selected" => Ok(Value::Bool(body.selected)),
+                    "black_hole" => Ok(Value::Bool(body.black_hole)),
+                    // bodies without the key simply don't match
+                    _ => Ok(metadata.get(body.id, name).cloned().unwrap_or(Value::Bool(false))),
+                }
+            }
+            Expr::Call(name, arguments) => {
+                let values = arguments
+                    .iter()
+                    .map(|argument| argument.eval(body, metadata).and_then(|value| number(&value)))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                match (name.as_str(), values.as_slice()) {
+                    ("within", [x, y, radius]) => {
+                        let distance = body.position.distance(vec2(*x as f32, *y as f32));
+                        Ok(Value::Bool((distance as f64) <= *radius))
+                    }
+                    ("abs", [value]) => Ok(Value::Number(value.abs())),
+                    _ => Err(format!("unknown function {}/{}", name, values.len())),
+                }
+            }
+            Expr::Not(inner) => Ok(Value::Bool(!truthy(&inner.eval(body, metadata)?))),
+            Expr::Negate(inner) => Ok(Value::Number(-number(&inner.eval(body, metadata)?)?)),
+            Expr::Binary(symbol, left, right) => {
+                let left = left.eval(body, metadata)?;
+                // short circuit so `has_key && key > 1` works on bodies without the key
+                match *symbol {
+                    "&&" if !truthy(&left) => {
+                        return Ok(Value::Bool(false));
+                    }
+                    "||" if truthy(&left) => {
+                        return Ok(Value::Bool(true));
+                    }
+                    _ => {}
+                }
+                let right = right.eval(body, metadata)?;
+
+                match *symbol {
+                    "&&" | "||" => Ok(Value::Bool(truthy(&right))),
+                    "==" => Ok(Value::Bool(left == right)),
+                    "!=" => Ok(Value::Bool(left != right)),
+                    "<" | ">" | "<=" | ">=" => {
+                        let (Value::Number(a), Value::Number(b)) = (&left, &right) else {
+                            return Ok(Value::Bool(false));
+                        };
+                        Ok(
+                            Value::Bool(match *symbol {
+                                "<" => a < b,
+                                ">" => a > b,
+                                "<=" => a <= b,
+                                _ => a >= b,
+                            })
+                        )
+                    }
+                    _ => {
+                        let (a, b) = (number(&left)?, number(&right)?);
+                        Ok(
+                            Value::Number(match *symbol {
+                                "+" => a + b,
+                                "-" => a - b,
+                                "*" => a * b,
+                                _ => a / b,
+                            })
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn matches(&self, body: &Body, metadata: &Metadata) -> Result<bool, String> {
+        Ok(truthy(&self.eval(body, metadata)?))
+    }
+}
@@ -0,0 +1,127 @@
+use macroquad::prelude::*;
+use macroquad::ui::{ hash, root_ui, widgets };
+
+const PANEL_WIDTH: f32 = 320.0;
+const PANEL_HEIGHT: f32 = 200.0;
+// under the control panel, which shares the right edge
+const PANEL_TOP: f32 = 390.0;
+// characters per line of the panel's text, the ui font is about 7 pixels wide
+const WRAP: usize = 42;
+const NO_QUESTIONS: &str = "no questions, `quiz add` or a scenario's `questions` add them";
+
+pub struct Question {
+    pub prompt: String,
+    pub answer: String,
+    revealed: bool,
+}
+
+// guided questions for a self-paced lab, shipped in a scenario file's `questions` and worked
+// through one at a time in a side panel, answers hidden until asked for
+#[derive(Default)]
+pub struct Quiz {
+    questions: Vec<Question>,
+    current: usize,
+    pub visible: bool,
+}
+
+impl Quiz {
+    // shows the panel, a lab that adds questions wants them seen
+    pub fn add(&mut self, prompt: &str, answer: &str) {
+        self.questions.push(Question {
+            prompt: prompt.to_string(),
+            answer: answer.to_string(),
+            revealed: false,
+        });
+        self.visible = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Quiz::default();
+    }
+
+    pub fn current(&self) -> Result<&Question, String> {
+        self.questions.get(self.current).ok_or_else(|| NO_QUESTIONS.to_string())
+    }
+
+    // moves `by` questions on, or back when negative, stopping at either end
+    pub fn go(&mut self, by: isize) -> Result<&Question, String> {
+        self.current()?;
+        let last = (self.questions.len() - 1) as isize;
+        self.current = (self.current as isize + by).clamp(0, last) as usize;
+        self.visible = true;
+        self.current()
+    }
+
+    pub fn reveal(&mut self) -> Result<&Question, String> {
+        let question = self.questions
+            .get_mut(self.current)
+            .ok_or(NO_QUESTIONS)?;
+        question.revealed = true;
+        Ok(question)
+    }
+
+    // whether `position` is over the panel, so the mouse belongs to it
+    pub fn captures(&self, position: Vec2) -> bool {
+        self.visible && !self.questions.is_empty() && root_ui().is_mouse_over(position)
+    }
+
+    // lays the panel out for this frame, its buttons do what `quiz next` and the rest do
+    pub fn update(&mut self) {
+        if !self.visible || self.questions.is_empty() {
+            return;
+        }
+        let position = vec2(screen_width() - PANEL_WIDTH, PANEL_TOP);
+        let title = format!("question {} of {}", self.current + 1, self.questions.len());
+        let (mut back, mut next, mut reveal) = (false, false, false);
+        let question = &self.questions[self.current];
+        widgets::Window::new(hash!(), position, vec2(PANEL_WIDTH, PANEL_HEIGHT))
+            .label(&title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                for line in wrap(&question.prompt) {
+                    ui.label(None, &line);
+                }
+                ui.separator();
+                if question.revealed {
+                    for line in wrap(&question.answer) {
+                        ui.label(None, &line);
+                    }
+                } else {
+                    reveal = ui.button(None, "reveal answer");
+                }
+                ui.separator();
+                back = ui.button(None, "back");
+                ui.same_line(0.0);
+                next = ui.button(None, "next");
+            });
+        if reveal {
+            self.questions[self.current].revealed = true;
+        }
+        if back || next {
+            let _ = self.go(if next { 1 } else { -1 });
+        }
+    }
+}
+
+// breaks `text` between words into lines that fit the panel
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > WRAP {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader, BufWriter, Write };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::numbers::exact;
+use crate::provenance::Provenance;
+
+// a `.tbr` recording is the provenance header followed by one CSV row per body per recorded
+// step, `time,id,x,y,vx,vy,mass`. Rows with the same time make up a frame. A recording with
+// tracks has every body in a keyframe every `every`th step only, and the tracked bodies, named
+// by the `tracks` provenance entry, in a frame of their own on every step in between
+pub struct Recorder {
+    file: BufWriter<File>,
+    pub path: String,
+    every: usize,
+    tracks: HashSet<u64>,
+    steps: usize,
+    pub frames: usize,
+}
+
+impl Recorder {
+    // records every `every`th step from the next one on, and every step of the bodies in
+    // `tracks`
+    pub fn start(
+        path: &str,
+        every: usize,
+        tracks: &[u64],
+        provenance: &Provenance
+    ) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        provenance.write_header(&mut file)?;
+        writeln!(file, "time,id,x,y,vx,vy,mass")?;
+        Ok(Recorder {
+            file,
+            path: path.to_string(),
+            every: every.max(1),
+            tracks: tracks.iter().copied().collect(),
+            steps: 0,
+            frames: 0,
+        })
+    }
+
+    pub fn record(&mut self, time: f32, bodies: &Bodies) -> io::Result<()> {
+        self.steps += 1;
+        let keyframe = self.steps.is_multiple_of(self.every);
+        if !keyframe && self.tracks.is_empty() {
+            return Ok(());
+        }
+        for body in bodies.iter().filter(|body| keyframe || self.tracks.contains(&body.id)) {
+            writeln!(
+                self.file,
+                "{},{},{},{},{},{},{}",
+                exact(time),
+                body.id,
+                exact(body.position.x),
+                exact(body.position.y),
+                exact(body.velocity.x),
+                exact(body.velocity.y),
+                exact(body.mass)
+            )?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn stop(mut self) -> io::Result<usize> {
+        self.file.flush()?;
+        Ok(self.frames)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sample {
+    pub id: u64,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+}
+
+pub struct Frame {
+    pub time: f32,
+    // sorted by id
+    pub samples: Vec<Sample>,
+}
+
+impl Frame {
+    pub fn get(&self, id: u64) -> Option<&Sample> {
+        self.samples
+            .binary_search_by_key(&id, |sample| sample.id)
+            .ok()
+            .map(|index| &self.samples[index])
+    }
+}
+
+pub struct Recording {
+    pub provenance: Provenance,
+    pub frames: Vec<Frame>,
+    // the bodies in every frame of a recording with tracks, the others are only in keyframes
+    pub tracks: Vec<u64>,
+}
+
+impl Recording {
+    pub fn read(path: &str) -> Result<Self, String> {
+        let provenance = Provenance::read(path).map_err(|err| format!("{}: {}", path, err))?;
+        let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+        let mut lines = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.as_ref().is_ok_and(|line| line.starts_with('#')));
+
+        let header = match lines.next() {
+            Some((_, Ok(header))) => header,
+            Some((_, Err(err))) => {
+                return Err(format!("{}: {}", path, err));
+            }
+            None => {
+                return Err(format!("{}: empty file", path));
+            }
+        };
+        if header.trim() != "time,id,x,y,vx,vy,mass" {
+            return Err(format!("{}: not a recording, the header is `{}`", path, header));
+        }
+
+        let mut frames: Vec<Frame> = Vec::new();
+        for (number, line) in lines {
+            let line = line.map_err(|err| format!("{}: {}", path, err))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (time, sample) = parse_row(&line).ok_or_else(|| {
+                format!("{}:{}: expected `time,id,x,y,vx,vy,mass`", path, number + 1)
+            })?;
+            // rewinding while recording goes back in time, the frames after that never happened
+            while frames.last().is_some_and(|frame| frame.time > time) {
+                frames.pop();
+            }
+            match frames.last_mut() {
+                Some(frame) if frame.time == time => frame.samples.push(sample),
+                _ => frames.push(Frame { time, samples: vec![sample] }),
+            }
+        }
+        for frame in &mut frames {
+            frame.samples.sort_by_key(|sample| sample.id);
+        }
+        if frames.is_empty() {
+            return Err(format!("{}: no frames", path));
+        }
+        let tracks = provenance.entries
+            .iter()
+            .find(|(key, _)| key == "tracks")
+            .map(|(_, ids)| ids.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+            .unwrap_or_default();
+        Ok(Recording { provenance, frames, tracks })
+    }
+
+    // the first frame with every body in it, the first frame of a recording without tracks
+    pub fn first_keyframe(&self) -> &Frame {
+        self.frames
+            .iter()
+            .find(|frame| frame.samples.iter().any(|sample| !self.tracks.contains(&sample.id)))
+            .unwrap_or(&self.frames[0])
+    }
+
+    // the state of body `id` at `time`, linearly interpolated between the frames around it
+    // that have it, the nearest keyframes for a body that isn't tracked
+    pub fn at(&self, time: f32, id: u64) -> Option<Sample> {
+        let after = self.frames.partition_point(|frame| frame.time < time);
+        let (next_time, b) = self.frames[after..]
+            .iter()
+            .find_map(|frame| Some((frame.time, frame.get(id)?)))?;
+        if next_time == time {
+            return Some(*b);
+        }
+        let (previous_time, a) = self.frames[..after]
+            .iter()
+            .rev()
+            .find_map(|frame| Some((frame.time, frame.get(id)?)))?;
+        let t = (time - previous_time) / (next_time - previous_time);
+        Some(Sample {
+            id,
+            position: a.position.lerp(b.position, t),
+            velocity: a.velocity.lerp(b.velocity, t),
+            mass: a.mass + (b.mass - a.mass) * t,
+        })
+    }
+}
+
+fn parse_row(line: &str) -> Option<(f32, Sample)> {
+    let mut values = line.split(',').map(str::trim);
+    let time = values.next()?.parse().ok()?;
+    let id = values.next()?.parse().ok()?;
+    let mut number = || values.next()?.parse::<f32>().ok();
+    let (x, y, vx, vy, mass) = (number()?, number()?, number()?, number()?, number()?);
+    Some((time, Sample { id, position: vec2(x, y), velocity: vec2(vx, vy), mass }))
+}
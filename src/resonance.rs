@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::metadata::Metadata;
+use crate::view::View;
+
+// the heaviest body only counts as the one everything orbits when it outweighs the next
+// heaviest by this much
+const DOMINANCE: f32 = 10.0;
+// period ratios p:q with p and q up to this are looked for
+const MAX_ORDER: u32 = 5;
+// how close a ratio has to come, relative
+const TOLERANCE: f32 = 0.02;
+// weight of the newest estimate in the running average of each period
+const SMOOTHING: f32 = 0.05;
+// pairs are only searched among this many orbiting bodies, the heaviest ones
+const MAX_ORBITERS: usize = 64;
+const LISTED_PAIRS: usize = 5;
+
+pub struct Resonance {
+    pub ids: (u64, u64),
+    // the longer period over the shorter one, as p:q
+    pub ratio: (u32, u32),
+    // how far off the exact ratio, relative
+    pub error: f32,
+}
+
+// estimates orbital periods around the dominant body from the osculating orbits and flags
+// pairs whose periods are close to a ratio of small integers
+#[derive(Default)]
+pub struct ResonanceDetector {
+    pub visible: bool,
+    periods: HashMap<u64, f32>,
+    pub resonances: Vec<Resonance>,
+}
+
+impl ResonanceDetector {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.periods.clear();
+        self.resonances.clear();
+    }
+
+    pub fn update(&mut self, bodies: &Bodies, gravity: f32) {
+        if !self.visible {
+            return;
+        }
+        let Some(primary) = dominant(bodies) else {
+            self.periods.clear();
+            self.resonances.clear();
+            return;
+        };
+
+        // the semi-major axis from the vis-viva equation, unbound orbits have none
+        let mut orbiters = Vec::new();
+        for i in 0..bodies.len() {
+            if i == primary {
+                continue;
+            }
+            let mu = gravity * (bodies.masses[primary] + bodies.masses[i]);
+            let offset = bodies.positions[i] - bodies.positions[primary];
+            let velocity = bodies.velocities[i] - bodies.velocities[primary];
+            let inverse_axis = 2.0 / offset.length() - velocity.length_squared() / mu;
+            if inverse_axis <= 0.0 {
+                self.periods.remove(&bodies.ids[i]);
+                continue;
+            }
+            let period = TAU * (inverse_axis.powi(-3) / mu).sqrt();
+            let smoothed = self.periods
+                .entry(bodies.ids[i])
+                .and_modify(|average| {
+                    *average += (period - *average) * SMOOTHING;
+                })
+                .or_insert(period);
+            orbiters.push((i, *smoothed));
+        }
+        let alive: Vec<u64> = orbiters.iter().map(|&(i, _)| bodies.ids[i]).collect();
+        self.periods.retain(|id, _| alive.contains(id));
+
+        orbiters.sort_by(|&(a, _), &(b, _)| bodies.masses[b].total_cmp(&bodies.masses[a]));
+        orbiters.truncate(MAX_ORBITERS);
+
+        self.resonances.clear();
+        for (n, &(i, period)) in orbiters.iter().enumerate() {
+            for &(j, other_period) in &orbiters[n + 1..] {
+                let (ids, ratio) = if period > other_period {
+                    ((bodies.ids[i], bodies.ids[j]), period / other_period)
+                } else {
+                    ((bodies.ids[j], bodies.ids[i]), other_period / period)
+                };
+                if let Some((p, q, error)) = nearest_ratio(ratio) {
+                    self.resonances.push(Resonance { ids, ratio: (p, q), error });
+                }
+            }
+        }
+        self.resonances.sort_by(|a, b| a.error.total_cmp(&b.error));
+    }
+
+    // a line between each resonant pair, labelled with the ratio
+    pub fn draw(&self, bodies: &Bodies, view: &View) {
+        if !self.visible {
+            return;
+        }
+        for resonance in &self.resonances {
+            let (outer, inner) = resonance.ids;
+            let (Some(outer), Some(inner)) = (find(bodies, outer), find(bodies, inner)) else {
+                continue;
+            };
+            let (from, _) = view.project(&bodies.body(outer));
+            let (to, _) = view.project(&bodies.body(inner));
+            let color = Color::new(0.3, 0.9, 1.0, 0.6);
+            draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+            let middle = (from + to) / 2.0;
+            let (p, q) = resonance.ratio;
+            draw_text(format!("{}:{}", p, q), middle.x, middle.y, 18.0, color);
+        }
+    }
+
+    // the closest few resonances, named where the bodies have names
+    pub fn summary(&self, metadata: &Metadata) -> Vec<String> {
+        self.resonances
+            .iter()
+            .take(LISTED_PAIRS)
+            .map(|resonance| {
+                let (p, q) = resonance.ratio;
+                format!(
+                    "{}:{} {} / {} (off by {:.1}%)",
+                    p,
+                    q,
+                    metadata.label(resonance.ids.0),
+                    metadata.label(resonance.ids.1),
+                    resonance.error * 100.0
+                )
+            })
+            .collect()
+    }
+}
+
+// the heaviest body, if it clearly dominates
+fn dominant(bodies: &Bodies) -> Option<usize> {
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_by(|&a, &b| bodies.masses[b].total_cmp(&bodies.masses[a]));
+    match order[..] {
+        [heaviest] => Some(heaviest),
+        [heaviest, next, ..] if bodies.masses[heaviest] >= DOMINANCE * bodies.masses[next] => {
+            Some(heaviest)
+        }
+        _ => None,
+    }
+}
+
+// the simplest p:q within tolerance of `ratio` (which is at least 1), 1:1 doesn't count
+fn nearest_ratio(ratio: f32) -> Option<(u32, u32, f32)> {
+    for q in 1..=MAX_ORDER {
+        for p in q + 1..=MAX_ORDER {
+            let exact = (p as f32) / (q as f32);
+            let error = (ratio - exact).abs() / exact;
+            if error < TOLERANCE {
+                return Some((p, q, error));
+            }
+        }
+    }
+    None
+}
+
+fn find(bodies: &Bodies, id: u64) -> Option<usize> {
+    bodies.ids.iter().position(|&other| other == id)
+}
@@ -0,0 +1,42 @@
+use std::collections::VecDeque;
+
+use crate::bodies::Bodies;
+
+// a snapshot every other frame, so holding the key plays the past back at double speed
+pub const FRAMES_PER_SNAPSHOT: usize = 2;
+// about five seconds at 60 fps
+const MAX_SNAPSHOTS: usize = 150;
+
+// rolling buffer of recent states, independent of any recording
+#[derive(Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<(f32, Bodies)>,
+    frames: usize,
+}
+
+impl RewindBuffer {
+    pub fn record(&mut self, time: f32, bodies: &Bodies) {
+        self.frames += 1;
+        if !self.frames.is_multiple_of(FRAMES_PER_SNAPSHOT) {
+            return;
+        }
+        if self.snapshots.len() == MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((time, bodies.clone()));
+    }
+
+    // takes the newest snapshot out, the states after it are gone once the simulation resumes
+    pub fn step_back(&mut self) -> Option<(f32, Bodies)> {
+        self.snapshots.pop_back()
+    }
+
+    // how far back the buffer still reaches, in simulation time
+    pub fn span(&self, time: f32) -> f32 {
+        self.snapshots.front().map_or(0.0, |(oldest, _)| time - oldest)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::Mutex;
+
+use rand::distributions::uniform::SampleUniform;
+use rand::rngs::StdRng;
+use rand::{ Rng, SeedableRng };
+
+// the one random number generator everything draws from, so a seed reproduces a whole run.
+// Seeded with 0 until something reseeds it
+static RNG: Mutex<Option<StdRng>> = Mutex::new(None);
+
+pub fn seed(seed: u64) {
+    *RNG.lock().unwrap() = Some(StdRng::seed_from_u64(seed));
+}
+
+// uniform in [low, high)
+pub fn gen_range<T: SampleUniform + PartialOrd>(low: T, high: T) -> T {
+    let mut rng = RNG.lock().unwrap();
+    rng.get_or_insert_with(|| StdRng::seed_from_u64(0)).gen_range(low..high)
+}
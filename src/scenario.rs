@@ -0,0 +1,99 @@
+use crate::json::{ self, Json };
+
+// a scenario file is a JSON object turned into console commands, run in this order:
+//
+//   {
+//     "seed": 7,
+//     "preset": "figure8",
+//     "settings": { "G": 1.5, "dt": 0.2 },
+//     "commands": ["spawn 20 disk 400 300 60"],
+//     "schedules": [{ "select": "name == \"Earth\"", "file": "burn.csv" }],
+//     "questions": [{ "question": "What if the Sun were twice as heavy?", "answer": "..." }]
+//   }
+//
+// every key is optional, and anything the console can do a scenario can do too. Schedules
+// come last so they can pick out bodies the commands spawned, see `schedule::load`
+pub fn commands(text: &str) -> Result<Vec<String>, String> {
+    let scenario = json::parse(text)?;
+    if scenario.as_object().is_none() {
+        return Err("a scenario must be a JSON object".to_string());
+    }
+
+    let mut commands = Vec::new();
+    // the seed has to be in place before the preset draws its random numbers
+    if let Some(seed) = scenario.get("seed") {
+        commands.push(format!("set seed {}", seed_text(seed)?));
+    }
+    if let Some(preset) = scenario.get("preset") {
+        let preset = preset.as_str().ok_or("`preset` must be a string")?;
+        commands.push(format!("preset {}", preset));
+    }
+    if let Some(settings) = scenario.get("settings") {
+        let settings = settings.as_object().ok_or("`settings` must be an object")?;
+        for (name, value) in settings {
+            let value = match value {
+                Json::Number(number) => *number,
+                Json::Bool(on) => if *on { 1.0 } else { 0.0 }
+                _ => {
+                    return Err(format!("setting `{}` must be a number or a boolean", name));
+                }
+            };
+            commands.push(format!("set {} {}", name, value));
+        }
+    }
+    if let Some(lines) = scenario.get("commands") {
+        let lines = lines.as_array().ok_or("`commands` must be an array")?;
+        for line in lines {
+            commands.push(line.as_str().ok_or("`commands` must hold strings")?.to_string());
+        }
+    }
+    if let Some(schedules) = scenario.get("schedules") {
+        let schedules = schedules.as_array().ok_or("`schedules` must be an array")?;
+        for schedule in schedules {
+            let field = |name: &str| {
+                schedule
+                    .get(name)
+                    .and_then(Json::as_str)
+                    .ok_or(format!("every schedule needs a `{}` string", name))
+            };
+            commands.push(format!("select {}", field("select")?));
+            commands.push(format!("schedule {}", field("file")?));
+        }
+        // the selection was only a means to an end
+        commands.push("select none".to_string());
+    }
+    if let Some(questions) = scenario.get("questions") {
+        let questions = questions.as_array().ok_or("`questions` must be an array")?;
+        for question in questions {
+            let field = |name: &str| {
+                question
+                    .get(name)
+                    .and_then(Json::as_str)
+                    .ok_or(format!("every question needs a `{}` string", name))
+            };
+            let prompt = field("question")?;
+            // the console splits the two at the first bar
+            if prompt.contains('|') {
+                return Err(format!("question `{}` can't contain `|`", prompt));
+            }
+            commands.push(format!("quiz add {} | {}", prompt, field("answer")?));
+        }
+    }
+    Ok(commands)
+}
+
+// JSON numbers are doubles, exact only up to 2^53, so larger seeds have to be written as a
+// string of digits
+fn seed_text(seed: &Json) -> Result<String, String> {
+    const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+    match seed {
+        Json::Number(number) if number.fract() == 0.0 && (0.0..=MAX_EXACT).contains(number) => {
+            Ok(format!("{}", *number as u64))
+        }
+        Json::Number(_) => {
+            Err("`seed` must be a whole number up to 2^53, larger ones go in a string".to_string())
+        }
+        Json::String(digits) if digits.parse::<u64>().is_ok() => Ok(digits.clone()),
+        _ => Err("`seed` must be a whole number or a string of digits".to_string()),
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    // `time,ax,ay`: pushed on top of gravity, like a planned burn
+    Acceleration,
+    // `time,x,y`: moved along the path, whatever gravity says
+    Waypoints,
+}
+
+// a time series for one body, linearly interpolated between samples and doing nothing
+// outside them. Times in the file count from when the schedule was loaded
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schedule {
+    pub id: u64,
+    pub kind: Kind,
+    start: f32,
+    samples: Vec<(f32, Vec2)>,
+}
+
+impl Schedule {
+    // the value and its rate of change at `time`, if the schedule covers it
+    fn at(&self, time: f32) -> Option<(Vec2, Vec2)> {
+        let time = time - self.start;
+        let segment = self.samples.windows(2).find(|pair| pair[0].0 <= time && time <= pair[1].0);
+        match segment {
+            Some(&[(t0, v0), (t1, v1)]) => {
+                let slope = (v1 - v0) / (t1 - t0);
+                Some((v0 + slope * (time - t0), slope))
+            }
+            // a single sample only holds at its own time
+            _ => {
+                let &(t, value) = self.samples.first()?;
+                (self.samples.len() == 1 && t == time).then_some((value, Vec2::ZERO))
+            }
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) => last.0 - first.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// reads `time,ax,ay` or `time,x,y` with a header naming the columns, `#` lines are skipped
+pub fn load(path: &str) -> Result<(Kind, Vec<(f32, Vec2)>), String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#') && !line.trim().is_empty());
+
+    let (_, header) = lines.next().ok_or_else(|| format!("{}: empty file", path))?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let kind = match columns[..] {
+        ["time", "ax", "ay"] => Kind::Acceleration,
+        ["time", "x", "y"] => Kind::Waypoints,
+        _ => {
+            return Err(format!("{}: the header must be `time,ax,ay` or `time,x,y`", path));
+        }
+    };
+
+    let mut samples: Vec<(f32, Vec2)> = Vec::new();
+    for (number, line) in lines {
+        let values = line
+            .split(',')
+            .map(|value| value.trim().parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>();
+        let (time, value) = match values.as_deref() {
+            Ok(&[time, x, y]) => (time, vec2(x, y)),
+            _ => {
+                return Err(format!("{}:{}: expected three numbers", path, number + 1));
+            }
+        };
+        if samples.last().is_some_and(|&(last, _)| time <= last) {
+            return Err(format!("{}:{}: times have to increase", path, number + 1));
+        }
+        samples.push((time, value));
+    }
+    if samples.is_empty() {
+        return Err(format!("{}: no samples", path));
+    }
+    Ok((kind, samples))
+}
+
+#[derive(Default)]
+pub struct Schedules {
+    pub list: Vec<Schedule>,
+}
+
+impl Schedules {
+    pub fn add(&mut self, id: u64, kind: Kind, samples: Vec<(f32, Vec2)>, start: f32) {
+        // a body follows one schedule of each kind at a time
+        self.list.retain(|schedule| schedule.id != id || schedule.kind != kind);
+        self.list.push(Schedule { id, kind, start, samples });
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+    }
+
+    // adds the scheduled accelerations to `Bodies::external_forces`, before the forces of
+    // the step are summed
+    pub fn accelerate(&self, bodies: &mut Bodies, time: f32) {
+        for schedule in self.list.iter().filter(|schedule| schedule.kind == Kind::Acceleration) {
+            let Some(index) = find(bodies, schedule.id) else {
+                continue;
+            };
+            let Some((acceleration, _)) = schedule.at(time) else {
+                continue;
+            };
+            bodies.external_forces[index] += acceleration * bodies.masses[index];
+        }
+    }
+
+    // puts the bodies following waypoints where they should be, after the step
+    pub fn guide(&self, bodies: &mut Bodies, time: f32) {
+        for schedule in self.list.iter().filter(|schedule| schedule.kind == Kind::Waypoints) {
+            let Some(index) = find(bodies, schedule.id) else {
+                continue;
+            };
+            let Some((position, velocity)) = schedule.at(time) else {
+                continue;
+            };
+            bodies.positions[index] = position;
+            bodies.velocities[index] = velocity;
+        }
+    }
+}
+
+fn find(bodies: &Bodies, id: u64) -> Option<usize> {
+    bodies.ids.iter().position(|&other| other == id)
+}
@@ -0,0 +1,56 @@
+use std::fs::{ self, File };
+
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+const DIRECTORY: &str = "screenshots";
+
+// the window as drawn so far, as a PNG
+pub fn write(path: &str) -> Result<(), String> {
+    if screen_width() < 1.0 || screen_height() < 1.0 {
+        return Err(format!("no screenshot for {}, the window is minimized", path));
+    }
+    // `export_png` panics where it can't write, better to find out first
+    if let Err(err) = File::create(path) {
+        return Err(format!("failed to write {}: {}", path, err));
+    }
+    get_screen_data().export_png(path);
+    Ok(())
+}
+
+// into the screenshots directory, named after the time in UTC down to the millisecond so
+// they sort in the order they were taken. Returns the path
+pub fn save() -> Result<String, String> {
+    fs::create_dir_all(DIRECTORY).map_err(|err| format!("{}: {}", DIRECTORY, err))?;
+    let path = format!("{}/threebody-{}.png", DIRECTORY, timestamp(date::now()));
+    write(&path)?;
+    Ok(path)
+}
+
+// `20261015-093012-250` for seconds since the epoch, the dates from Howard Hinnant's
+// days-to-civil algorithm
+fn timestamp(seconds: f64) -> String {
+    let milliseconds = (seconds * 1000.0) as i64;
+    let days = milliseconds.div_euclid(86_400_000);
+    let of_day = milliseconds.rem_euclid(86_400_000);
+    // counted from 0000-03-01 in 400 year eras, so leap days come last in the year
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let era_day = shifted.rem_euclid(146_097);
+    let era_year = (era_day - era_day / 1460 + era_day / 36_524 - era_day / 146_096) / 365;
+    let year_day = era_day - (365 * era_year + era_year / 4 - era_year / 100);
+    let month_index = (5 * year_day + 2) / 153;
+    let day = year_day - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = era * 400 + era_year + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        of_day / 3_600_000,
+        (of_day / 60_000) % 60,
+        (of_day / 1000) % 60,
+        of_day % 1000
+    )
+}
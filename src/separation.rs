@@ -0,0 +1,72 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::broadphase;
+use crate::settings::Settings;
+
+// corrections smaller than this, in pixels, end the passes early
+const SETTLED: f32 = 1e-3;
+
+// pushes bodies apart until their surfaces are at least `settings.separation` apart, in
+// place of the collision response when only the look of it matters. Every pass moves both
+// bodies of each pair that is too close along the line between them, the lighter one
+// further, and the next pass fixes what that did to their other neighbours. Frozen bodies
+// don't move and black holes are left to swallow what they touch
+pub fn enforce(bodies: &mut Bodies, settings: &Settings) {
+    let gap = settings.separation.max(0.0);
+    // pushing a pair apart can push either body into a neighbour it wasn't near before, so
+    // every pass looks for pairs again
+    for _ in 0..settings.separation_iterations {
+        let mut largest = 0.0f32;
+        for (i, j) in pairs(bodies, gap) {
+            let offset = bodies.positions[j] - bodies.positions[i];
+            let depth = bodies.radii[i] + bodies.radii[j] + gap - offset.length();
+            if depth <= 0.0 {
+                continue;
+            }
+            // bodies right on top of each other part along x, any direction would do
+            let normal = offset.try_normalize().unwrap_or(Vec2::X);
+            let (wi, wj) = (inverse_mass(bodies, i), inverse_mass(bodies, j));
+            let correction = (normal * depth) / (wi + wj);
+            bodies.positions[i] -= correction * wi;
+            bodies.positions[j] += correction * wj;
+            largest = largest.max(depth);
+        }
+        if largest < SETTLED {
+            break;
+        }
+    }
+
+    // pairs held apart stop closing in, or gravity would keep speeding them up against each
+    // other until a step carries one through the other. What is taken out is shared so
+    // momentum is kept, like a perfectly inelastic collision along the normal
+    for (i, j) in pairs(bodies, gap + SETTLED) {
+        let offset = bodies.positions[j] - bodies.positions[i];
+        if offset.length() > bodies.radii[i] + bodies.radii[j] + gap + SETTLED {
+            continue;
+        }
+        let normal = offset.normalize_or_zero();
+        let closing = (bodies.velocities[i] - bodies.velocities[j]).dot(normal);
+        if closing <= 0.0 {
+            continue;
+        }
+        let (wi, wj) = (inverse_mass(bodies, i), inverse_mass(bodies, j));
+        let impulse = (normal * closing) / (wi + wj);
+        bodies.velocities[i] -= impulse * wi;
+        bodies.velocities[j] += impulse * wj;
+    }
+}
+
+// candidates closer than `gap`, less the ones the pass leaves alone
+fn pairs(bodies: &Bodies, gap: f32) -> Vec<(usize, usize)> {
+    let mut pairs = broadphase::pairs_within(bodies, gap);
+    let skipped = |i: usize, j: usize| {
+        bodies.black_holes[i] || bodies.black_holes[j] || (bodies.freezed[i] && bodies.freezed[j])
+    };
+    pairs.retain(|&(i, j)| !skipped(i, j));
+    pairs
+}
+
+fn inverse_mass(bodies: &Bodies, index: usize) -> f32 {
+    if bodies.freezed[index] { 0.0 } else { 1.0 / bodies.masses[index].max(f32::EPSILON) }
+}
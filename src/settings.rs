@@ -0,0 +1,302 @@
+use crate::broadphase::Broadphase;
+use crate::fmm;
+use crate::integrator::Integrator;
+use crate::numbers::Numbers;
+use crate::simd;
+use crate::solver::Solver;
+use crate::style::BodyStyle;
+use crate::theme::Theme;
+use crate::units::Scale;
+use crate::watchdog;
+use crate::{ DT, FRICTION, G, LIGHT_SPEED, MAX_VELOCITY, NUM_OF_BODIES };
+
+// the range of `Settings::time_scale`, and the stops the keys go through
+pub const MIN_TIME_SCALE: f32 = 0.1;
+pub const MAX_TIME_SCALE: f32 = 10.0;
+pub const TIME_SCALES: [f32; 7] = [0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0];
+// the range of `Settings::dt`, the watchdog halves it no further than the lower end
+pub const MIN_DT: f32 = DT / 64.0;
+pub const MAX_DT: f32 = 2.0;
+// more than this and a frame takes longer than it's worth
+pub const MAX_SUBSTEPS: usize = 16;
+// the range of `Settings::theta`, beyond the upper end hardly a cell gets opened and the
+// forces are mostly noise
+pub const MIN_THETA: f32 = 0.05;
+pub const MAX_THETA: f32 = 1.5;
+
+#[derive(Clone)]
+pub struct Settings {
+    pub gravity: f32,
+    pub dt: f32,
+    // every step is integrated in this many parts, each with fresh forces
+    pub substeps: usize,
+    // simulated time per frame as a multiple of `dt`, slow motion below 1 and fast forward
+    // above (see `Settings::scaled`)
+    pub time_scale: f32,
+    pub integrator: Integrator,
+    pub throw_strength: f32,
+    pub solver: Solver,
+    // the one presets switch back to before they load, whatever the last one asked for.
+    // Hardware detection's pick (see `Capabilities::choose`)
+    pub default_solver: Solver,
+    pub theta: f32,
+    // of the fmm local expansions, up to `fmm::MAX_ORDER`. Each order costs more per cell
+    // pair and takes the error down several times at the same theta
+    pub expansion_order: usize,
+    pub leaf_capacity: usize,
+    pub broadphase: Broadphase,
+    // sweeps fast bodies through the step so they can't pass through others
+    pub ccd: bool,
+    // vectorized brute force kernel, only where there is one (see `simd::KERNEL`)
+    pub simd: bool,
+    // real world units of the loaded scenario, if it has any
+    pub scale: Option<Scale>,
+    // random number seed, presets reseed with it so they come out the same every time
+    pub seed: u64,
+    // how many bodies the generated presets fill the world with
+    pub body_count: usize,
+    pub hacks: Hacks,
+    // of collisions between bodies while `Hacks::collision_friction` is on, elastic otherwise
+    pub restitution: f32,
+    // the share of its speed a body keeps bouncing off a wall while `Hacks::wall_loss` is on
+    pub wall_friction: f32,
+    // the speed `Hacks::velocity_clamp` caps bodies at
+    pub max_velocity: f32,
+    // first post-Newtonian correction to the force law, makes orbits precess
+    pub relativity: bool,
+    // in pixels per time unit, low enough that the precession shows within a few orbits
+    pub light_speed: f32,
+    // opposite walls joined into a torus, gravity reaches through them (see `ewald`). The
+    // energy diagnostics still count the nearest images only
+    pub periodic: bool,
+    // points kept per trail, and how quickly they fade with age (see `Trails::draw`)
+    pub trail_length: usize,
+    pub trail_fade: f32,
+    // pixels of velocity arrow per unit of speed, at a zoom of 1
+    pub velocity_scale: f32,
+    // pixels of acceleration arrow per unit of acceleration, at a zoom of 1
+    pub acceleration_scale: f32,
+    // a constraint pass keeping bodies `separation` apart, surface to surface, in place of
+    // the collision response (see `separation`)
+    pub separate: bool,
+    pub separation: f32,
+    pub separation_iterations: usize,
+    // random kicks on every body, see `noise::Noise`. The amplitude is an acceleration, 0
+    // turns them off, and the correlation how long a kick lasts in time units
+    pub noise_amplitude: f32,
+    pub noise_correlation: f32,
+    // an external tidal field, see `tide`: its two eigenvalues, 0 for none, and the angle of
+    // the major axis in degrees. The energy diagnostics leave its potential out
+    pub tide_major: f32,
+    pub tide_minor: f32,
+    pub tide_angle: f32,
+    // what happens when the simulation blows up, see `watchdog::Watchdog`
+    pub watchdog: watchdog::Mode,
+    // how quantities are written on screen
+    pub numbers: Numbers,
+    // how bodies without a `style` tag of their own are drawn
+    pub body_style: BodyStyle,
+    // seconds of running the energy plot covers
+    pub energy_window: f32,
+    // bodies smaller than this many pixels in radius on screen are drawn as dots, 0 for never
+    pub lod_radius: f32,
+    // the path the selected body is about to take, see `prediction::path`
+    pub predict_path: bool,
+    // the parallax stars behind the bodies, see `starfield::Starfield`
+    pub starfield: bool,
+    pub theme: Theme,
+    // how hard the velocity brush pushes, see `brush::Brush::apply`
+    pub brush_strength: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            gravity: G,
+            dt: DT,
+            substeps: 1,
+            time_scale: 1.0,
+            integrator: Integrator::Euler,
+            throw_strength: 1.0,
+            solver: Solver::BruteForce,
+            default_solver: Solver::BruteForce,
+            theta: 0.5,
+            expansion_order: fmm::DEFAULT_ORDER,
+            leaf_capacity: 8,
+            broadphase: Broadphase::SpatialHash,
+            ccd: true,
+            simd: simd::KERNEL,
+            scale: None,
+            seed: 0,
+            body_count: NUM_OF_BODIES,
+            hacks: Hacks::ALL,
+            restitution: FRICTION,
+            wall_friction: FRICTION,
+            max_velocity: MAX_VELOCITY,
+            relativity: false,
+            light_speed: LIGHT_SPEED,
+            periodic: false,
+            trail_length: 60,
+            trail_fade: 1.0,
+            velocity_scale: 10.0,
+            acceleration_scale: 500.0,
+            separate: false,
+            separation: 0.0,
+            separation_iterations: 8,
+            noise_amplitude: 0.0,
+            noise_correlation: 10.0,
+            tide_major: 0.0,
+            tide_minor: 0.0,
+            tide_angle: 0.0,
+            watchdog: watchdog::Mode::Tune,
+            numbers: Numbers::default(),
+            body_style: BodyStyle::Outline,
+            energy_window: 10.0,
+            lod_radius: 2.0,
+            predict_path: true,
+            starfield: true,
+            theme: Theme::default(),
+            brush_strength: 0.5,
+        }
+    }
+}
+
+impl Settings {
+    // what a frame steps with at the time scale. Slow motion takes a shorter step, fast
+    // forward a longer one in more substeps, so no substep is longer than without it and
+    // the orbits stay as accurate as at normal speed
+    pub fn scaled(&self) -> Settings {
+        let scale = self.time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        Settings {
+            dt: self.dt * scale,
+            substeps: ((self.substeps as f32) * scale.max(1.0)).ceil() as usize,
+            ..self.clone()
+        }
+    }
+
+    // the next stop of `TIME_SCALES` up or down from the current scale
+    pub fn step_time_scale(&mut self, faster: bool) {
+        let scale = self.time_scale;
+        let next = if faster {
+            TIME_SCALES.into_iter().find(|&stop| stop > scale + 1e-3)
+        } else {
+            TIME_SCALES.into_iter().rev().find(|&stop| stop < scale - 1e-3)
+        };
+        self.time_scale = next.unwrap_or(scale);
+    }
+}
+
+// the non-physical shortcuts that keep the sandbox tame, all of them on normally. The ideal
+// profile turns every one off so the engine runs as a conservative system that can be
+// checked against the conservation laws
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hacks {
+    // colliding bodies lose a bit of speed
+    pub collision_friction: bool,
+    // bounced bodies lose a bit of speed at the walls
+    pub wall_loss: bool,
+    // speeds are capped at `Settings::max_velocity`
+    pub velocity_clamp: bool,
+    // colliding bodies trade velocities whatever their masses, instead of an elastic impulse
+    pub velocity_swap: bool,
+    // overlapping bodies stop attracting, each judged by its own radius. Without it forces
+    // come from an exact pairwise sum that levels off inside contact distance
+    pub force_cutoff: bool,
+    // bodies that stay slow and weakly pulled fall asleep and stop being integrated
+    pub sleeping: bool,
+}
+
+impl Hacks {
+    pub const ALL: Hacks = Hacks {
+        collision_friction: true,
+        wall_loss: true,
+        velocity_clamp: true,
+        velocity_swap: true,
+        force_cutoff: true,
+        sleeping: true,
+    };
+
+    pub const NONE: Hacks = Hacks {
+        collision_friction: false,
+        wall_loss: false,
+        velocity_clamp: false,
+        velocity_swap: false,
+        force_cutoff: false,
+        sleeping: false,
+    };
+
+    pub fn is_ideal(&self) -> bool {
+        *self == Hacks::NONE
+    }
+
+    pub fn list(&self) -> [(&'static str, &'static str, bool); 6] {
+        [
+            (
+                "collision_friction",
+                "collisions take a little speed off both bodies",
+                self.collision_friction,
+            ),
+            ("wall_loss", "wall bounces take a little speed off", self.wall_loss),
+            ("velocity_clamp", "speeds are capped at the speed limit", self.velocity_clamp),
+            (
+                "velocity_swap",
+                "colliding bodies swap velocities regardless of mass",
+                self.velocity_swap,
+            ),
+            (
+                "force_cutoff",
+                "overlapping bodies ignore each other's gravity, one-sidedly for unequal radii, \
+                 and the tree and GPU solvers approximate",
+                self.force_cutoff,
+            ),
+            (
+                "sleeping",
+                "bodies that stay still stop moving until hit or pulled hard",
+                self.sleeping,
+            ),
+        ]
+    }
+
+    // turns a single hack on or off by name, false if there is no such hack
+    pub fn set(&mut self, name: &str, active: bool) -> bool {
+        let hack = match name {
+            "collision_friction" => &mut self.collision_friction,
+            "wall_loss" => &mut self.wall_loss,
+            "velocity_clamp" => &mut self.velocity_clamp,
+            "velocity_swap" => &mut self.velocity_swap,
+            "force_cutoff" => &mut self.force_cutoff,
+            "sleeping" => &mut self.sleeping,
+            _ => {
+                return false;
+            }
+        };
+        *hack = active;
+        true
+    }
+
+    // one line per hack, for the console and the provenance of exports
+    pub fn report(&self) -> String {
+        let mut report = if self.is_ideal() {
+            "ideal physics, no hacks active".to_string()
+        } else {
+            "active hacks:".to_string()
+        };
+        for (name, description, active) in self.list() {
+            if active {
+                report.push_str(&format!("\n  {}: {}", name, description));
+            }
+        }
+        report
+    }
+
+    // names of the active hacks, comma separated
+    pub fn names(&self) -> String {
+        let active: Vec<&str> = self
+            .list()
+            .into_iter()
+            .filter(|&(_, _, active)| active)
+            .map(|(name, _, _)| name)
+            .collect();
+        if active.is_empty() { "none".to_string() } else { active.join(",") }
+    }
+}
@@ -0,0 +1,108 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+
+const LANES: usize = 4;
+// only x86_64 has a hand written SSE kernel, elsewhere `brute_force` is a plain loop the
+// compiler may or may not vectorize
+pub const KERNEL: bool = cfg!(target_arch = "x86_64");
+
+// positions split into x and y and padded so four bodies load as one vector
+struct Lanes {
+    x: Vec<f32>,
+    y: Vec<f32>,
+    mass: Vec<f32>,
+}
+
+impl Lanes {
+    fn new(bodies: &Bodies) -> Self {
+        let padded = bodies.len().div_ceil(LANES) * LANES;
+        // padding bodies are massless, so they never contribute
+        let mut lanes = Lanes {
+            x: vec![0.0; padded],
+            y: vec![0.0; padded],
+            mass: vec![0.0; padded],
+        };
+        for (i, position) in bodies.positions.iter().enumerate() {
+            lanes.x[i] = position.x;
+            lanes.y[i] = position.y;
+        }
+        lanes.mass[..bodies.len()].copy_from_slice(&bodies.masses);
+        lanes
+    }
+}
+
+// same sum as `solver::brute_force`, four other bodies per iteration
+pub fn brute_force(bodies: &Bodies, gravity: f32) -> Vec<Vec2> {
+    let lanes = Lanes::new(bodies);
+    (0..bodies.len()).map(|i| force_on(&lanes, bodies, i, gravity)).collect()
+}
+
+#[cfg(target_arch = "x86_64")]
+fn force_on(lanes: &Lanes, bodies: &Bodies, i: usize, gravity: f32) -> Vec2 {
+    use std::arch::x86_64::*;
+
+    // SSE2 is part of the x86_64 baseline, so these are always available
+    unsafe {
+        let px = _mm_set1_ps(bodies.positions[i].x);
+        let py = _mm_set1_ps(bodies.positions[i].y);
+        let cutoff = _mm_set1_ps(2.0 * bodies.radii[i]);
+        let strength = _mm_set1_ps(gravity * bodies.masses[i]);
+        let zero = _mm_setzero_ps();
+        let mut fx = zero;
+        let mut fy = zero;
+
+        for j in (0..lanes.x.len()).step_by(LANES) {
+            let dx = _mm_sub_ps(_mm_loadu_ps(lanes.x.as_ptr().add(j)), px);
+            let dy = _mm_sub_ps(_mm_loadu_ps(lanes.y.as_ptr().add(j)), py);
+            let distance_squared = _mm_add_ps(_mm_mul_ps(dx, dx), _mm_mul_ps(dy, dy));
+            let distance = _mm_sqrt_ps(distance_squared);
+
+            // overlapping pairs (and the body itself) are skipped like in `Bodies::force_towards`
+            let mask = _mm_and_ps(
+                _mm_cmpge_ps(distance, cutoff),
+                _mm_cmpgt_ps(distance_squared, zero)
+            );
+            let magnitude = _mm_div_ps(
+                _mm_mul_ps(strength, _mm_loadu_ps(lanes.mass.as_ptr().add(j))),
+                _mm_mul_ps(distance_squared, distance)
+            );
+            let magnitude = _mm_and_ps(mask, magnitude);
+
+            fx = _mm_add_ps(fx, _mm_mul_ps(magnitude, dx));
+            fy = _mm_add_ps(fy, _mm_mul_ps(magnitude, dy));
+        }
+
+        let mut x = [0.0f32; LANES];
+        let mut y = [0.0f32; LANES];
+        _mm_storeu_ps(x.as_mut_ptr(), fx);
+        _mm_storeu_ps(y.as_mut_ptr(), fy);
+        vec2(x.iter().sum(), y.iter().sum())
+    }
+}
+
+// portable version of the same kernel, written lane-wise so the compiler can vectorize it
+#[cfg(not(target_arch = "x86_64"))]
+fn force_on(lanes: &Lanes, bodies: &Bodies, i: usize, gravity: f32) -> Vec2 {
+    let position = bodies.positions[i];
+    let cutoff = 2.0 * bodies.radii[i];
+    let strength = gravity * bodies.masses[i];
+    let mut fx = [0.0f32; LANES];
+    let mut fy = [0.0f32; LANES];
+
+    for j in (0..lanes.x.len()).step_by(LANES) {
+        for lane in 0..LANES {
+            let dx = lanes.x[j + lane] - position.x;
+            let dy = lanes.y[j + lane] - position.y;
+            let distance_squared = dx * dx + dy * dy;
+            let distance = distance_squared.sqrt();
+            if distance >= cutoff && distance_squared > 0.0 {
+                let magnitude = (strength * lanes.mass[j + lane]) / (distance_squared * distance);
+                fx[lane] += magnitude * dx;
+                fy[lane] += magnitude * dy;
+            }
+        }
+    }
+
+    vec2(fx.iter().sum(), fy.iter().sum())
+}
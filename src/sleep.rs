@@ -0,0 +1,36 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::settings::Hacks;
+
+// a body this slow and this weakly pulled on is a candidate for sleeping
+const SLEEP_SPEED: f32 = 0.05;
+const SLEEP_ACCELERATION: f32 = 0.005;
+// twice the pull it fell asleep under wakes it, so it doesn't flicker at the threshold
+const WAKE_ACCELERATION: f32 = 2.0 * SLEEP_ACCELERATION;
+// steps a body has to stay still before it falls asleep
+pub const SLEEP_STEPS: u32 = 60;
+
+// counts how long every body has been still and puts the ones that stayed still long enough
+// to sleep: they stop moving until something hits them (the collision response gives them
+// a velocity) or they get pulled on hard enough, but their gravity still counts
+pub fn update(bodies: &mut Bodies, hacks: &Hacks) {
+    if !hacks.sleeping {
+        bodies.still_steps.fill(0);
+        return;
+    }
+    for i in 0..bodies.len() {
+        let acceleration = bodies.forces[i].length() / bodies.masses[i];
+        let speed = bodies.velocities[i].length();
+        let asleep = bodies.still_steps[i] >= SLEEP_STEPS;
+        let threshold = if asleep { WAKE_ACCELERATION } else { SLEEP_ACCELERATION };
+        if speed > SLEEP_SPEED || acceleration > threshold {
+            bodies.still_steps[i] = 0;
+        } else if !asleep {
+            bodies.still_steps[i] += 1;
+            if bodies.still_steps[i] == SLEEP_STEPS {
+                bodies.velocities[i] = Vec2::ZERO;
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{ self, BufWriter, Write };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::provenance::Provenance;
+use crate::settings::Settings;
+use crate::{ diagnostics, export, rng, solver, Body, MAX_VELOCITY, SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// few enough that some steps pass without any collision or wall contact
+const SOAK_BODIES: usize = 10;
+// the body count stays between these, spawning or merging pushes it back in range
+const MIN_BODIES: usize = 5;
+const MAX_BODIES: usize = 20;
+// chance per step of poking the system, spread over the interactions below
+const INTERACTION_CHANCE: f32 = 0.01;
+// how far a step without collisions, wall contact or clamping may move the invariants,
+// relative to the sum of |m v| and to K + |U| respectively. Large close encounters cost
+// the integrator a lot of energy, only a blow-up counts
+const MOMENTUM_TOLERANCE: f64 = 1e-3;
+const ENERGY_TOLERANCE: f64 = 0.5;
+const SPEED_ROUNDING: f32 = 1e-3;
+const REPORTED_INTERACTIONS: usize = 20;
+const PROGRESS_INTERVAL: usize = 10_000;
+
+// `threebody soak [steps] [seed]`: runs the simulation headless while spawning, throwing,
+// merging and deleting bodies at random, and stops at the first step that breaks an
+// invariant. The state before that step is shrunk to the fewest bodies that still break it
+// and written out next to a report, so the failure can be reproduced and filed
+pub fn run(steps: usize, seed: u64) {
+    rng::seed(seed);
+    let settings = Settings { seed, ..Settings::default() };
+    let mut bodies: Bodies = (0..SOAK_BODIES).map(|_| Body::random(None)).collect();
+    let mut interactions = VecDeque::new();
+    let mut closed_steps = 0;
+    println!("soaking {} steps with seed {}", steps, seed);
+
+    for step in 0..steps {
+        if rng::gen_range(0.0, 1.0) < INTERACTION_CHANCE {
+            let interaction = interact(&mut bodies);
+            if interactions.len() == REPORTED_INTERACTIONS {
+                interactions.pop_front();
+            }
+            interactions.push_back(format!("step {}: {}", step, interaction));
+        }
+
+        let before = bodies.clone();
+        let (violation, closed) = advance(&mut bodies, &settings);
+        closed_steps += closed as usize;
+        if let Some(violation) = violation {
+            println!("step {}: {}", step, violation);
+            let minimal = shrink(before.clone(), &settings);
+            println!("shrunk {} bodies down to {}", before.len(), minimal.len());
+            match report(step, &violation, &minimal, &interactions, &settings) {
+                Ok(()) => println!("wrote soak-failure.txt and soak-failure.csv"),
+                Err(err) => eprintln!("failed to write the failure report: {}", err),
+            }
+            return;
+        }
+
+        if (step + 1) % PROGRESS_INTERVAL == 0 {
+            println!(
+                "step {}: {} bodies, {} closed steps so far, all invariants hold",
+                step + 1,
+                bodies.len(),
+                closed_steps
+            );
+        }
+    }
+    println!("no violations in {} steps, {} of them closed", steps, closed_steps);
+}
+
+// one step of forces, collisions and integration, checked against the invariants. Also
+// says whether the step was closed, only those can be held to conservation laws
+fn advance(bodies: &mut Bodies, settings: &Settings) -> (Option<String>, bool) {
+    let momentum = diagnostics::linear_momentum(bodies);
+    let energy = energy(bodies, settings.gravity);
+    solver::compute_forces(bodies, settings);
+    let collisions = crate::step(bodies, settings);
+
+    for body in bodies.iter() {
+        let broken = if !is_finite(&body) {
+            "is no longer finite"
+        } else if !inside_world(&body) {
+            "left the world"
+        } else if body.velocity.length() > MAX_VELOCITY * (1.0 + SPEED_ROUNDING) {
+            "is faster than the speed limit"
+        } else {
+            continue;
+        };
+        return (Some(format!("body {} {}", body.id, broken)), false);
+    }
+
+    // walls, collisions and the speed limit all take energy and momentum out on purpose,
+    // only a step without any of them is a closed system
+    let closed =
+        collisions.is_empty() &&
+        bodies.iter().all(|body| !touches_wall(&body) && !at_speed_limit(&body));
+    if !closed {
+        return (None, false);
+    }
+
+    let scale: f64 = bodies
+        .iter()
+        .map(|body| (body.mass as f64) * (body.velocity.length() as f64))
+        .sum();
+    let drift = (diagnostics::linear_momentum(bodies) - momentum).length() as f64;
+    if scale > 0.0 && drift > MOMENTUM_TOLERANCE * scale {
+        return (Some(format!("momentum changed by {:.3e} in a closed step", drift)), true);
+    }
+
+    let (total, magnitude) = energy;
+    let growth = self::energy(bodies, settings.gravity).0 - total;
+    if magnitude > 0.0 && growth > ENERGY_TOLERANCE * magnitude {
+        return (Some(format!("energy grew by {:.3e} in a closed step", growth)), true);
+    }
+    (None, true)
+}
+
+// total energy and K + |U|, the size it's measured against
+fn energy(bodies: &Bodies, gravity: f32) -> (f64, f64) {
+    let kinetic = diagnostics::kinetic_energy(bodies);
+    let potential = diagnostics::potential_energy(bodies, gravity);
+    (kinetic + potential, kinetic + potential.abs())
+}
+
+fn is_finite(body: &Body) -> bool {
+    body.position.is_finite() && body.velocity.is_finite() && body.mass.is_finite()
+}
+
+fn inside_world(body: &Body) -> bool {
+    (0.0..=SCREEN_WIDTH).contains(&body.position.x) &&
+        (0.0..=SCREEN_HEIGHT).contains(&body.position.y)
+}
+
+// a clamped velocity comes out a rounding error either side of the limit
+fn at_speed_limit(body: &Body) -> bool {
+    body.velocity.length() >= MAX_VELOCITY * (1.0 - SPEED_ROUNDING)
+}
+
+fn touches_wall(body: &Body) -> bool {
+    body.position.x <= body.radius ||
+        body.position.x >= SCREEN_WIDTH - body.radius ||
+        body.position.y <= body.radius ||
+        body.position.y >= SCREEN_HEIGHT - body.radius
+}
+
+// does what a user might do with the mouse or the console, and says what it did
+fn interact(bodies: &mut Bodies) -> String {
+    let choice = if bodies.len() < MIN_BODIES {
+        0
+    } else if bodies.len() > MAX_BODIES {
+        3
+    } else {
+        // spawning twice as often as merging or deleting keeps the count steady
+        rng::gen_range(0, 5)
+    };
+    let random_index = |bodies: &Bodies| rng::gen_range(0, bodies.len());
+
+    match choice {
+        0 | 1 => {
+            let body = Body::random(None);
+            bodies.push(body);
+            format!("spawned body {} at {}", body.id, body.position)
+        }
+        2 => {
+            // picked up, moved and thrown like a right mouse drag
+            let index = random_index(bodies);
+            let mut body = bodies.body(index);
+            body.position = vec2(
+                rng::gen_range(0.0, SCREEN_WIDTH),
+                rng::gen_range(0.0, SCREEN_HEIGHT)
+            );
+            body.velocity = vec2(rng::gen_range(-30.0, 30.0), rng::gen_range(-30.0, 30.0));
+            bodies.set(index, body);
+            format!("threw body {} from {} at {}", body.id, body.position, body.velocity)
+        }
+        3 => {
+            // two bodies become one, keeping mass and momentum
+            let (i, j) = (random_index(bodies), random_index(bodies));
+            if i == j {
+                return "merged nothing".to_string();
+            }
+            let (mut body, other) = (bodies.body(i), bodies.body(j));
+            let mass = body.mass + other.mass;
+            body.position = (body.position * body.mass + other.position * other.mass) / mass;
+            body.velocity = (body.velocity * body.mass + other.velocity * other.mass) / mass;
+            body.mass = mass;
+            body.radius = body.radius.max(other.radius);
+            bodies.set(i, body);
+            bodies.retain(|body| body.id != other.id);
+            format!("merged body {} into body {}", other.id, body.id)
+        }
+        _ => {
+            let index = random_index(bodies);
+            let id = bodies.ids[index];
+            bodies.retain(|body| body.id != id);
+            format!("deleted body {}", id)
+        }
+    }
+}
+
+// drops ever smaller runs of bodies as long as the step still breaks an invariant without them
+fn shrink(mut bodies: Bodies, settings: &Settings) -> Bodies {
+    let fails = |bodies: &Bodies| advance(&mut bodies.clone(), settings).0.is_some();
+    let mut chunk = bodies.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < bodies.len() {
+            let end = (start + chunk).min(bodies.len());
+            let ids: Vec<u64> = bodies.ids[start..end].to_vec();
+            let mut candidate = bodies.clone();
+            candidate.retain(|body| !ids.contains(&body.id));
+            if fails(&candidate) {
+                bodies = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+    bodies
+}
+
+fn report(
+    step: usize,
+    violation: &str,
+    bodies: &Bodies,
+    interactions: &VecDeque<String>,
+    settings: &Settings
+) -> io::Result<()> {
+    let provenance = Provenance::new(settings);
+    export::bodies_csv(bodies, "soak-failure.csv", &provenance)?;
+
+    let mut file = BufWriter::new(File::create("soak-failure.txt")?);
+    provenance.write_header(&mut file)?;
+    writeln!(file, "violation at step {}: {}", step, violation)?;
+    writeln!(
+        file,
+        "soak-failure.csv holds the {} bodies that still break it in one step",
+        bodies.len()
+    )?;
+    writeln!(file, "rerun with `threebody soak {} {}`", step + 1, settings.seed)?;
+    writeln!(file)?;
+    writeln!(file, "last interactions:")?;
+    for interaction in interactions {
+        writeln!(file, "  {}", interaction)?;
+    }
+    file.flush()
+}
@@ -0,0 +1,205 @@
+use macroquad::miniquad::date;
+use macroquad::prelude::*;
+
+use crate::quadtree::QuadTree;
+use crate::settings::Settings;
+use crate::bodies::Bodies;
+use crate::{ ewald, fmm, perf, simd };
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Solver {
+    BruteForce,
+    BarnesHut,
+    Fmm,
+    // needs a GL context, see `gpu::GpuSolver`
+    Gpu,
+}
+
+impl Solver {
+    pub const CPU: [Solver; 3] = [Solver::BruteForce, Solver::BarnesHut, Solver::Fmm];
+
+    pub fn next(self) -> Self {
+        match self {
+            Solver::BruteForce => Solver::BarnesHut,
+            Solver::BarnesHut => Solver::Fmm,
+            Solver::Fmm => Solver::Gpu,
+            Solver::Gpu => Solver::BruteForce,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Solver::BruteForce => "brute force",
+            Solver::BarnesHut => "barnes-hut",
+            Solver::Fmm => "fmm",
+            Solver::Gpu => "gpu",
+        }
+    }
+}
+
+// sums the gravitational force on every body into `Bodies::forces`
+pub fn compute_forces(bodies: &mut Bodies, settings: &Settings) {
+    let started = date::now();
+    let forces = match settings.solver {
+        _ if settings.periodic => ewald::forces(bodies, settings.gravity),
+        _ if !settings.hacks.force_cutoff => pairwise(bodies, settings.gravity),
+        // without a GPU context the same pairwise sum runs on the CPU
+        Solver::BruteForce | Solver::Gpu if settings.simd => {
+            simd::brute_force(bodies, settings.gravity)
+        }
+        Solver::BruteForce | Solver::Gpu => brute_force(bodies, settings.gravity),
+        Solver::BarnesHut => {
+            barnes_hut(bodies, settings.theta, settings.leaf_capacity, settings.gravity)
+        }
+        Solver::Fmm => {
+            fmm::forces(
+                bodies,
+                settings.theta,
+                settings.expansion_order,
+                settings.leaf_capacity,
+                settings.gravity
+            )
+        }
+    };
+
+    bodies.forces = forces;
+    add_external_forces(bodies);
+    if settings.relativity {
+        let corrections = post_newtonian(bodies, settings.gravity, settings.light_speed);
+        for (force, correction) in bodies.forces.iter_mut().zip(corrections) {
+            *force += correction;
+        }
+    }
+    perf::add_force_time(date::now() - started);
+}
+
+// bodies the force error is estimated from, spread evenly over the list
+const ERROR_SAMPLES: usize = 32;
+
+// how far the forces the solver came up with are from the exact sum, as the RMS of the
+// difference over the RMS of the exact forces on a sample of bodies. Only meaningful right
+// after `compute_forces` for plain gravity, without the relativistic correction
+pub fn force_error(bodies: &Bodies, gravity: f32) -> f32 {
+    let stride = bodies.len().div_ceil(ERROR_SAMPLES).max(1);
+    let (mut difference, mut magnitude) = (0.0, 0.0);
+    for i in (0..bodies.len()).step_by(stride) {
+        let exact = brute_force_on(bodies, i, gravity);
+        let approximate = bodies.forces[i] - bodies.external_forces[i];
+        difference += (approximate - exact).length_squared();
+        magnitude += exact.length_squared();
+    }
+    if magnitude > 0.0 { (difference / magnitude).sqrt() } else { 0.0 }
+}
+
+// whatever co-simulation or scripts push on the bodies, on top of gravity
+pub fn add_external_forces(bodies: &mut Bodies) {
+    for (force, external) in bodies.forces.iter_mut().zip(&bodies.external_forces) {
+        *force += *external;
+    }
+}
+
+pub fn brute_force(bodies: &Bodies, gravity: f32) -> Vec<Vec2> {
+    (0..bodies.len()).map(|i| brute_force_on(bodies, i, gravity)).collect()
+}
+
+// exact sum over every pair with a symmetric law that levels off inside contact distance
+// instead of vanishing, so the forces cancel pairwise and derive from a potential (see
+// `diagnostics::potential_energy`). Tree codes would break both, whatever the solver
+pub fn pairwise(bodies: &Bodies, gravity: f32) -> Vec<Vec2> {
+    let (positions, masses, radii) = (&bodies.positions, &bodies.masses, &bodies.radii);
+    let mut forces = vec![Vec2::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let offset = positions[j] - positions[i];
+            let distance = offset.length();
+            if distance == 0.0 {
+                continue;
+            }
+            let reach = distance.max(radii[i] + radii[j]);
+            let force = (offset / distance) * ((gravity * masses[i] * masses[j]) / reach.powi(2));
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+    forces
+}
+
+// the first post-Newtonian correction for every pair, in the limit where one body is much
+// heavier than the other (Schwarzschild in harmonic coordinates):
+//
+//   a = G M / (c^2 r^2) * ((4 G M / r - v^2) n + 4 (n . v) v)
+//
+// with M the pair's total mass and r, n, v the separation, its direction and the relative
+// velocity. The pair shares it through the reduced mass, so momentum is kept, and an orbit
+// around a heavy body advances its periapsis by 6 pi G M / (c^2 a (1 - e^2)) per turn.
+// Always a sum over every pair, none of the solvers approximate velocity dependent forces
+pub fn post_newtonian(bodies: &Bodies, gravity: f32, light_speed: f32) -> Vec<Vec2> {
+    let (positions, velocities) = (&bodies.positions, &bodies.velocities);
+    let (masses, radii) = (&bodies.masses, &bodies.radii);
+    let c2 = light_speed.powi(2);
+    let mut forces = vec![Vec2::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let offset = positions[i] - positions[j];
+            let distance = offset.length();
+            if distance == 0.0 {
+                continue;
+            }
+            // like the Newtonian force, it stops growing once the bodies touch
+            let reach = distance.max(radii[i] + radii[j]);
+            let direction = offset / distance;
+            let velocity = velocities[i] - velocities[j];
+            let mu = gravity * (masses[i] + masses[j]);
+            let acceleration =
+                (mu / (c2 * reach.powi(2))) *
+                ((4.0 * mu / reach - velocity.length_squared()) * direction +
+                    4.0 * direction.dot(velocity) * velocity);
+            let reduced_mass = (masses[i] * masses[j]) / (masses[i] + masses[j]);
+            forces[i] += acceleration * reduced_mass;
+            forces[j] -= acceleration * reduced_mass;
+        }
+    }
+    forces
+}
+
+pub fn brute_force_on(bodies: &Bodies, i: usize, gravity: f32) -> Vec2 {
+    let mut force = Vec2::ZERO;
+    for j in 0..bodies.len() {
+        if i != j {
+            force += bodies.force_between(i, j, gravity);
+        }
+    }
+    force
+}
+
+pub fn barnes_hut(bodies: &Bodies, theta: f32, leaf_capacity: usize, gravity: f32) -> Vec<Vec2> {
+    let tree = QuadTree::build(bodies, leaf_capacity);
+    (0..bodies.len()).map(|i| barnes_hut_on(&tree, 0, bodies, i, theta, gravity)).collect()
+}
+
+fn barnes_hut_on(
+    tree: &QuadTree,
+    node: usize,
+    bodies: &Bodies,
+    i: usize,
+    theta: f32,
+    gravity: f32
+) -> Vec2 {
+    let position = bodies.positions[i];
+    let node = &tree.nodes[node];
+
+    if node.is_leaf() {
+        return node.bodies
+            .iter()
+            .filter(|&&j| j != i)
+            .fold(Vec2::ZERO, |sum, &j| sum + bodies.force_between(i, j, gravity));
+    }
+
+    if node.is_far(position, theta) {
+        return bodies.force_towards(i, node.center_of_mass, node.mass, gravity);
+    }
+
+    node.children.iter().fold(Vec2::ZERO, |sum, &child| {
+        sum + barnes_hut_on(tree, child, bodies, i, theta, gravity)
+    })
+}
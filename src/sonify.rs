@@ -0,0 +1,211 @@
+use std::f32::consts::{ FRAC_PI_2, PI, TAU };
+
+use macroquad::audio::{
+    load_sound_from_bytes,
+    play_sound,
+    set_sound_volume,
+    stop_sound,
+    PlaySoundParams,
+    Sound,
+};
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::broadphase;
+use crate::diagnostics;
+
+const SAMPLE_RATE: u32 = 22050;
+// major pentatonic over a few octaves, so any two notes played together sound fine
+const PENTATONIC: [f32; 5] = [0.0, 2.0, 4.0, 7.0, 9.0];
+const OCTAVES: i32 = 3;
+const BASE_FREQUENCY: f32 = 220.0;
+const NOTE_SECONDS: f32 = 0.3;
+// a whole number of cycles per second so the drone loops without clicking
+const DRONE_FREQUENCIES: [f32; 2] = [110.0, 165.0];
+// the closest pair is sounded this often, in seconds of wall time
+const PAIR_INTERVAL: f64 = 0.2;
+// pairs at least this far apart get the lowest note
+const PAIR_RANGE: f32 = 200.0;
+// orbits with a larger radius than this tick on the lowest note
+const ORBIT_RANGE: f32 = 400.0;
+
+struct Orbit {
+    id: u64,
+    angle: f32,
+    swept: f32,
+}
+
+// maps the dynamics to sound: the drone follows the total kinetic energy, a note sounds the
+// closest pair (higher when closer) and the selected body ticks every quarter orbit
+pub struct Sonifier {
+    pub enabled: bool,
+    notes: Vec<Sound>,
+    drone: Sound,
+    drone_playing: bool,
+    peak_kinetic: f64,
+    last_pair_note: f64,
+    orbit: Option<Orbit>,
+}
+
+impl Sonifier {
+    // None when the generated samples can't be loaded
+    pub async fn new() -> Option<Self> {
+        let mut notes = Vec::new();
+        for octave in 0..OCTAVES {
+            for step in PENTATONIC {
+                let semitones = (octave as f32) * 12.0 + step;
+                let frequency = BASE_FREQUENCY * (2.0f32).powf(semitones / 12.0);
+                notes.push(load(&tone(&[frequency], NOTE_SECONDS, true)).await?);
+            }
+        }
+        let drone = load(&tone(&DRONE_FREQUENCIES, 1.0, false)).await?;
+
+        Some(Sonifier {
+            enabled: false,
+            notes,
+            drone,
+            drone_playing: false,
+            peak_kinetic: 0.0,
+            last_pair_note: 0.0,
+            orbit: None,
+        })
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if !self.enabled && self.drone_playing {
+            stop_sound(&self.drone);
+            self.drone_playing = false;
+        }
+        self.orbit = None;
+    }
+
+    pub fn update(&mut self, bodies: &Bodies, kinetic: f64) {
+        if !self.enabled {
+            return;
+        }
+
+        if !self.drone_playing {
+            play_sound(&self.drone, PlaySoundParams { looped: true, volume: 0.0 });
+            self.drone_playing = true;
+        }
+        // relative to the most energetic moment so far, quiet systems still get a drone
+        self.peak_kinetic = self.peak_kinetic.max(kinetic);
+        let loudness = if self.peak_kinetic > 0.0 { kinetic / self.peak_kinetic } else { 0.0 };
+        set_sound_volume(&self.drone, 0.3 * (loudness as f32));
+
+        let now = get_time();
+        if now - self.last_pair_note >= PAIR_INTERVAL {
+            self.last_pair_note = now;
+            if let Some(distance) = closest_pair(bodies) {
+                self.play(1.0 - (distance / PAIR_RANGE).clamp(0.0, 1.0), 0.4);
+            }
+        }
+
+        self.track_orbit(bodies);
+    }
+
+    // `level` in [0, 1] picks the note from lowest to highest
+    fn play(&self, level: f32, volume: f32) {
+        let index = (level * ((self.notes.len() - 1) as f32)).round() as usize;
+        play_sound(&self.notes[index], PlaySoundParams { looped: false, volume });
+    }
+
+    fn track_orbit(&mut self, bodies: &Bodies) {
+        let Some(index) = bodies.selected.iter().position(|&selected| selected) else {
+            self.orbit = None;
+            return;
+        };
+        let offset = bodies.positions[index] - diagnostics::center_of_mass(bodies);
+        let angle = offset.y.atan2(offset.x);
+        let id = bodies.ids[index];
+
+        match self.orbit.as_mut() {
+            Some(orbit) if orbit.id == id => {
+                // shortest way round, the angle jumps by 2pi when crossing the negative x axis
+                let delta = (angle - orbit.angle + PI).rem_euclid(TAU) - PI;
+                orbit.angle = angle;
+                orbit.swept += delta.abs();
+                if orbit.swept >= FRAC_PI_2 {
+                    orbit.swept = 0.0;
+                    self.play(1.0 - (offset.length() / ORBIT_RANGE).clamp(0.0, 1.0), 0.6);
+                }
+            }
+            _ => {
+                self.orbit = Some(Orbit { id, angle, swept: 0.0 });
+            }
+        }
+    }
+}
+
+// the distance between the two closest bodies, `PAIR_RANGE` if none are closer than that as
+// they all sound the same. Asks the broadphase for pairs within a small margin first and
+// widens it until the closest pair found is inside it, as any pair left out is further apart
+// than the margin, so crowded scenes never measure more than their near neighbours
+fn closest_pair(bodies: &Bodies) -> Option<f32> {
+    if bodies.len() < 2 {
+        return None;
+    }
+    let positions = &bodies.positions;
+    let mut margin = PAIR_RANGE / 64.0;
+    loop {
+        let closest = broadphase::pairs_within(bodies, margin)
+            .into_iter()
+            .map(|(i, j)| positions[i].distance(positions[j]))
+            .fold(PAIR_RANGE, f32::min);
+        if closest <= margin || margin >= PAIR_RANGE {
+            return Some(closest);
+        }
+        margin *= 2.0;
+    }
+}
+
+async fn load(samples: &[f32]) -> Option<Sound> {
+    match load_sound_from_bytes(&wav(samples)).await {
+        Ok(sound) => Some(sound),
+        Err(err) => {
+            eprintln!("sonification unavailable: {:?}", err);
+            None
+        }
+    }
+}
+
+// sum of sines, optionally with a short attack and an exponential decay
+fn tone(frequencies: &[f32], seconds: f32, envelope: bool) -> Vec<f32> {
+    let count = ((SAMPLE_RATE as f32) * seconds) as usize;
+    (0..count)
+        .map(|i| {
+            let t = (i as f32) / (SAMPLE_RATE as f32);
+            let wave: f32 =
+                frequencies
+                    .iter()
+                    .map(|frequency| (TAU * frequency * t).sin())
+                    .sum::<f32>() / (frequencies.len() as f32);
+            let gain = if envelope { (t / 0.01).min(1.0) * (-t * 12.0).exp() } else { 1.0 };
+            wave * gain
+        })
+        .collect()
+}
+
+// 16 bit mono PCM, the simplest thing every audio backend can decode
+fn wav(samples: &[f32]) -> Vec<u8> {
+    let data_size = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + (data_size as usize));
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // bytes per second
+    bytes.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+    bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * (i16::MAX as f32)) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
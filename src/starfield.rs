@@ -0,0 +1,94 @@
+use macroquad::prelude::*;
+use ::rand::rngs::StdRng;
+use ::rand::{ Rng, SeedableRng };
+
+use crate::view::View;
+
+// the stars repeat in tiles this many pixels across, far more than shows at once
+const TILE: f32 = 512.0;
+// its own seed, the simulation's random numbers stay the same with or without the stars
+const SEED: u64 = 0x5747;
+
+// how far away a layer is: how much of the camera's movement it follows, stars in it per
+// tile, their size in pixels and how bright the brightest of them is
+struct LayerKind {
+    depth: f32,
+    stars: usize,
+    size: f32,
+    brightness: f32,
+}
+
+// farthest first, so nearer stars are drawn over them
+const LAYERS: [LayerKind; 3] = [
+    LayerKind { depth: 0.1, stars: 60, size: 1.0, brightness: 0.35 },
+    LayerKind { depth: 0.25, stars: 30, size: 1.0, brightness: 0.55 },
+    LayerKind { depth: 0.5, stars: 12, size: 2.0, brightness: 0.8 },
+];
+
+struct Star {
+    // inside the tile
+    position: Vec2,
+    brightness: f32,
+    // from a little blue to a little yellow
+    warmth: f32,
+}
+
+// a few layers of faint stars behind everything, each scrolling and zooming a fraction as
+// much as the bodies do, the farther the less, so panning and zooming show which way the
+// camera went even with nothing else on screen
+pub struct Starfield {
+    layers: Vec<Vec<Star>>,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let layers = LAYERS
+            .iter()
+            .map(|kind| {
+                (0..kind.stars)
+                    .map(|_| {
+                        let position = vec2(rng.gen_range(0.0..TILE), rng.gen_range(0.0..TILE));
+                        let brightness = kind.brightness * rng.gen_range(0.3..1.0);
+                        let warmth = rng.gen_range(-0.15..0.15);
+                        Star { position, brightness, warmth }
+                    })
+                    .collect()
+            })
+            .collect();
+        Starfield { layers }
+    }
+}
+
+impl Starfield {
+    // in shades of `color`, the theme's, fading into the background
+    pub fn draw(&self, view: &View, color: Color) {
+        let center = vec2(screen_width(), screen_height()) / 2.0;
+        let rotation = Vec2::from_angle(-view.angle());
+        for (kind, stars) in LAYERS.iter().zip(&self.layers) {
+            // the layer zooms around the middle of the screen by this much, and moves `depth`
+            // as far on screen as the bodies do
+            let magnification = view.zoom.powf(kind.depth);
+            let shift = view.camera() * view.scale() * kind.depth / magnification;
+            // every tile that reaches the screen, whichever way it's turned
+            let reach = center.length() / magnification;
+            let first = ((shift - reach) / TILE).floor();
+            let last = ((shift + reach) / TILE).floor();
+            for row in (first.y as i32)..=(last.y as i32) {
+                for column in (first.x as i32)..=(last.x as i32) {
+                    let corner = vec2(column as f32, row as f32) * TILE - shift;
+                    for star in stars {
+                        let at = center + rotation.rotate(corner + star.position) * magnification;
+                        let color = Color::new(
+                            color.r * (1.0 + star.warmth).min(1.0),
+                            color.g,
+                            color.b * (1.0 - star.warmth).min(1.0),
+                            color.a * star.brightness
+                        );
+                        draw_rectangle(at.x, at.y, kind.size, kind.size, color);
+                    }
+                }
+            }
+        }
+    }
+}
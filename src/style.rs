@@ -0,0 +1,50 @@
+use macroquad::prelude::*;
+
+use crate::metadata::{ Metadata, Value };
+
+// ring width at a zoom of 1, thinner on small bodies so crowds don't smear into a blob
+const OUTLINE_WIDTH: f32 = 4.0;
+
+// how bodies are drawn. `Settings::body_style` for all of them, a `style` tag for one
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BodyStyle {
+    Outline,
+    Filled,
+    // a shaded disc, bright in the middle and darker towards the edge like a star
+    Sprite,
+}
+
+impl BodyStyle {
+    pub const ALL: [BodyStyle; 3] = [BodyStyle::Outline, BodyStyle::Filled, BodyStyle::Sprite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyStyle::Outline => "outline",
+            BodyStyle::Filled => "filled",
+            BodyStyle::Sprite => "sprite",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        BodyStyle::ALL.into_iter().find(|style| style.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let index = BodyStyle::ALL.iter().position(|&style| style == self).unwrap_or(0);
+        BodyStyle::ALL[(index + 1) % BodyStyle::ALL.len()]
+    }
+
+    // the body's own `style` tag when it names one, `default` otherwise
+    pub fn of(metadata: &Metadata, id: u64, default: BodyStyle) -> BodyStyle {
+        match metadata.get(id, "style") {
+            Some(Value::Text(name)) => BodyStyle::parse(name).unwrap_or(default),
+            _ => default,
+        }
+    }
+}
+
+// of the ring `BodyStyle::Outline` draws around a body `radius` big on screen, `scale` being
+// how much the view magnifies it
+pub fn outline_width(radius: f32, scale: f32) -> f32 {
+    (OUTLINE_WIDTH * scale).min(radius / 2.0)
+}
@@ -0,0 +1,178 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::diagnostics;
+use crate::numbers::Numbers;
+use crate::view::View;
+
+// the unbinding is quadratic in the selection, beyond this it's left alone
+const MAX_MEMBERS: usize = 512;
+// rounds of dropping the unbound members, at most one in this many of them each round
+const MAX_PASSES: usize = 32;
+const DROPPED_SHARE: usize = 4;
+const COLOR: Color = Color::new(0.4, 0.9, 0.6, 0.8);
+
+pub struct Stats {
+    pub selected: usize,
+    pub bound: usize,
+    pub bound_mass: f32,
+    // of the bound members, or of the whole selection when none are bound
+    pub center: Vec2,
+    pub velocity: Vec2,
+    // along one axis, mass weighted
+    pub dispersion: f32,
+    // the Jacobi radius in the pull of everything not selected, None when there's nothing
+    pub tidal_radius: Option<f32>,
+}
+
+// a selected group of bodies watched as one cluster: how much of it is still bound, where
+// it is and how hot it is, and how far out the rest of the system can pull its members away
+pub struct Subcluster {
+    pub visible: bool,
+    pub stats: Option<Stats>,
+    // more bodies selected than `MAX_MEMBERS`
+    too_many: bool,
+}
+
+impl Default for Subcluster {
+    fn default() -> Self {
+        Subcluster { visible: true, stats: None, too_many: false }
+    }
+}
+
+impl Subcluster {
+    pub fn update(&mut self, bodies: &Bodies, gravity: f32) {
+        self.stats = None;
+        self.too_many = false;
+        if !self.visible {
+            return;
+        }
+        let members: Vec<usize> = (0..bodies.len()).filter(|&i| bodies.selected[i]).collect();
+        if members.len() < 2 {
+            return;
+        }
+        if members.len() > MAX_MEMBERS {
+            self.too_many = true;
+            return;
+        }
+
+        // a member is bound when its kinetic energy in the frame of the bound ones is less
+        // than their pull on it. Dropping the ones that aren't moves that frame, so again
+        // until nothing changes. A fast heavy member drags the frame along with it, so only
+        // the worst few go each round rather than everything it makes look unbound
+        let mut bound = members.clone();
+        for _ in 0..MAX_PASSES {
+            let (_, _, velocity) = moments(bodies, &bound);
+            let mut unbound: Vec<(f64, usize)> = bound
+                .iter()
+                .map(|&i| {
+                    let kinetic =
+                        0.5 *
+                        (bodies.masses[i] as f64) *
+                        ((bodies.velocities[i] - velocity).length_squared() as f64);
+                    let potential: f64 = bound
+                        .iter()
+                        .filter(|&&j| j != i)
+                        .map(|&j| diagnostics::pair_potential(bodies, i, j, gravity))
+                        .sum();
+                    (kinetic + potential, i)
+                })
+                .filter(|&(energy, _)| energy >= 0.0)
+                .collect();
+            if unbound.is_empty() {
+                break;
+            }
+            unbound.sort_by(|a, b| b.0.total_cmp(&a.0));
+            unbound.truncate((bound.len() / DROPPED_SHARE).max(1));
+            bound.retain(|i| !unbound.iter().any(|&(_, j)| j == *i));
+            if bound.len() < 2 {
+                break;
+            }
+        }
+        // a single body isn't bound to anything
+        if bound.len() < 2 {
+            bound.clear();
+        }
+
+        let group = if bound.is_empty() { &members } else { &bound };
+        let (mass, center, velocity) = moments(bodies, group);
+        let spread: f32 = group
+            .iter()
+            .map(|&i| bodies.masses[i] * (bodies.velocities[i] - velocity).length_squared())
+            .sum();
+        let bound_mass = bound.iter().fold(0.0, |sum, &i| sum + bodies.masses[i]);
+
+        // r_t = R (m / 3M)^(1/3) for a cluster of mass m a distance R from a mass M
+        let outside: Vec<usize> = (0..bodies.len()).filter(|&i| !bodies.selected[i]).collect();
+        let (host_mass, host_center, _) = moments(bodies, &outside);
+        let tidal_radius = (host_mass > 0.0 && bound_mass > 0.0).then(|| {
+            center.distance(host_center) * (bound_mass / (3.0 * host_mass)).cbrt()
+        });
+
+        self.stats = Some(Stats {
+            selected: members.len(),
+            bound: bound.len(),
+            bound_mass,
+            center,
+            velocity,
+            dispersion: (spread / (2.0 * mass.max(f32::MIN_POSITIVE))).sqrt(),
+            tidal_radius,
+        });
+    }
+
+    // the center and the tidal radius around it
+    pub fn draw(&self, view: &View) {
+        let Some(stats) = &self.stats else {
+            return;
+        };
+        let center = view.world_to_screen(stats.center);
+        draw_line(center.x - 5.0, center.y, center.x + 5.0, center.y, 1.0, COLOR);
+        draw_line(center.x, center.y - 5.0, center.x, center.y + 5.0, 1.0, COLOR);
+        if let Some(radius) = stats.tidal_radius {
+            draw_circle_lines(center.x, center.y, radius * view.scale(), 1.0, COLOR);
+        }
+    }
+
+    pub fn summary(&self, numbers: Numbers) -> Vec<String> {
+        if self.too_many {
+            return vec![format!("subcluster: over {} bodies selected", MAX_MEMBERS)];
+        }
+        let Some(stats) = &self.stats else {
+            return Vec::new();
+        };
+        let pair = |vector: Vec2, decimals: usize| {
+            let (x, y) = (numbers.format(vector.x, decimals), numbers.format(vector.y, decimals));
+            format!("({}, {})", x, y)
+        };
+        let tidal_radius = match stats.tidal_radius {
+            Some(radius) => numbers.format(radius, 1),
+            None => "none".to_string(),
+        };
+        vec![
+            format!(
+                "subcluster: {} of {} bound, mass {}",
+                stats.bound,
+                stats.selected,
+                numbers.format(stats.bound_mass, 1)
+            ),
+            format!("center {}, moving {}", pair(stats.center, 1), pair(stats.velocity, 3)),
+            format!(
+                "dispersion {}, tidal radius {}",
+                numbers.format(stats.dispersion, 3),
+                tidal_radius
+            )
+        ]
+    }
+}
+
+// total mass, center of mass and mean velocity of `indices`
+fn moments(bodies: &Bodies, indices: &[usize]) -> (f32, Vec2, Vec2) {
+    let mass: f32 = indices.iter().map(|&i| bodies.masses[i]).sum();
+    if mass <= 0.0 {
+        return (0.0, Vec2::ZERO, Vec2::ZERO);
+    }
+    let weighted = |values: &[Vec2]| {
+        indices.iter().fold(Vec2::ZERO, |sum, &i| sum + values[i] * bodies.masses[i]) / mass
+    };
+    (mass, weighted(&bodies.positions), weighted(&bodies.velocities))
+}
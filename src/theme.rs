@@ -0,0 +1,163 @@
+use std::fs;
+
+use macroquad::prelude::*;
+
+use crate::json::{ self, Json };
+
+// the colors everything not colored by meaning is drawn in: the background and the stars
+// behind it, bodies, their trails and the HUD. `theme <name | file.json>` in the console
+// switches between them
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub stars: Color,
+    pub body: Color,
+    // bodies the sleep check has set aside, see `sleep`
+    pub sleeping: Color,
+    // selected bodies, their labels and the selection rectangle
+    pub accent: Color,
+    // the newest end of a trail, it fades from there
+    pub trail: Color,
+    pub text: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub const BUILT_IN: [&'static str; 3] = ["dark", "light", "blueprint"];
+
+    pub fn dark() -> Self {
+        Theme {
+            name: "dark".to_string(),
+            background: BLACK,
+            stars: WHITE,
+            body: RED,
+            sleeping: Color::new(0.5, 0.15, 0.15, 1.0),
+            accent: YELLOW,
+            trail: Color::new(1.0, 0.3, 0.3, 0.6),
+            text: WHITE,
+        }
+    }
+
+    // for projectors and printing
+    pub fn light() -> Self {
+        Theme {
+            name: "light".to_string(),
+            background: Color::new(0.96, 0.95, 0.92, 1.0),
+            stars: Color::new(0.35, 0.35, 0.45, 1.0),
+            body: Color::new(0.75, 0.1, 0.1, 1.0),
+            sleeping: Color::new(0.65, 0.5, 0.5, 1.0),
+            accent: Color::new(0.85, 0.5, 0.0, 1.0),
+            trail: Color::new(0.8, 0.2, 0.2, 0.6),
+            text: Color::new(0.1, 0.1, 0.12, 1.0),
+        }
+    }
+
+    pub fn blueprint() -> Self {
+        Theme {
+            name: "blueprint".to_string(),
+            background: Color::new(0.05, 0.16, 0.35, 1.0),
+            stars: Color::new(0.7, 0.8, 1.0, 1.0),
+            body: Color::new(0.95, 0.97, 1.0, 1.0),
+            sleeping: Color::new(0.5, 0.6, 0.8, 1.0),
+            accent: Color::new(1.0, 0.85, 0.3, 1.0),
+            trail: Color::new(0.6, 0.8, 1.0, 0.5),
+            text: Color::new(0.9, 0.95, 1.0, 1.0),
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "blueprint" => Some(Theme::blueprint()),
+            _ => None,
+        }
+    }
+
+    // a built in theme by name, or a theme file otherwise
+    pub fn find(name: &str) -> Result<Self, String> {
+        match Theme::built_in(name) {
+            Some(theme) => Ok(theme),
+            None => Theme::load(name),
+        }
+    }
+
+    // a JSON object of colors as `#rrggbb` or `#rrggbbaa`, starting from the theme `base`
+    // names, dark unless it names another, and named after the file unless it has a `name`:
+    //
+    //   { "base": "light", "background": "#fdf6e3", "body": "#dc322f", "text": "#586e75" }
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Theme::parse(&text).map_err(|err| format!("{}: {}", path, err)).map(|mut theme| {
+            if theme.name.is_empty() {
+                let stem = std::path::Path::new(path).file_stem().and_then(|stem| stem.to_str());
+                theme.name = stem.unwrap_or(path).to_string();
+            }
+            theme
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let file = json::parse(text)?;
+        let fields = file.as_object().ok_or("a theme must be a JSON object")?;
+        let mut theme = match file.get("base") {
+            Some(base) => {
+                let base = base.as_str().ok_or("`base` must be a string")?;
+                Theme::built_in(base).ok_or_else(|| {
+                    format!("unknown base theme `{}`, {}", base, Theme::BUILT_IN.join(", "))
+                })?
+            }
+            None => Theme::dark(),
+        };
+        theme.name = String::new();
+        for (key, value) in fields {
+            if key == "base" {
+                continue;
+            }
+            if key == "name" {
+                theme.name = value.as_str().ok_or("`name` must be a string")?.to_string();
+                continue;
+            }
+            let color = match value {
+                Json::String(hex) => parse_color(hex)?,
+                _ => {
+                    return Err(format!("`{}` must be a color like \"#ff8800\"", key));
+                }
+            };
+            match key.as_str() {
+                "background" => theme.background = color,
+                "stars" => theme.stars = color,
+                "body" => theme.body = color,
+                "sleeping" => theme.sleeping = color,
+                "accent" => theme.accent = color,
+                "trail" => theme.trail = color,
+                "text" => theme.text = color,
+                _ => {
+                    return Err(format!("unknown theme color `{}`", key));
+                }
+            }
+        }
+        Ok(theme)
+    }
+}
+
+// `#rrggbb` or `#rrggbbaa`
+fn parse_color(hex: &str) -> Result<Color, String> {
+    let invalid = || format!("`{}` isn't a color, it takes #rrggbb or #rrggbbaa", hex);
+    let digits = hex.strip_prefix('#').ok_or_else(invalid)?;
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let channels = (0..digits.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&digits[at..at + 2], 16).map(|channel| channel as f32 / 255.0))
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| invalid())?;
+    Ok(Color::new(channels[0], channels[1], channels[2], channels.get(3).copied().unwrap_or(1.0)))
+}
@@ -0,0 +1,34 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::settings::Settings;
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// the pull of something much bigger than the system, a host galaxy or the walls of a
+// container, as a tidal tensor: an acceleration of -T (x - c) about the middle of the world c.
+// T has `Settings::tide_major` along `Settings::tide_angle` and `Settings::tide_minor`
+// across it. A positive eigenvalue squeezes along its axis and a negative one stretches, so
+// a cluster in a galaxy's tide (stretched towards the galaxy, squeezed across) sheds stars
+// in two tails along the major axis
+pub fn accelerate(bodies: &mut Bodies, settings: &Settings) {
+    if !is_active(settings) {
+        return;
+    }
+    let center = vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+    let axis = Vec2::from_angle(settings.tide_angle.to_radians());
+    for index in 0..bodies.len() {
+        if bodies.freezed[index] {
+            continue;
+        }
+        let offset = bodies.positions[index] - center;
+        // the offset in the tensor's own axes, where it's diagonal, and back
+        let along = offset.dot(axis) * settings.tide_major;
+        let across = offset.dot(axis.perp()) * settings.tide_minor;
+        let acceleration = -(axis * along + axis.perp() * across);
+        bodies.external_forces[index] += acceleration * bodies.masses[index];
+    }
+}
+
+pub fn is_active(settings: &Settings) -> bool {
+    settings.tide_major != 0.0 || settings.tide_minor != 0.0
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::io;
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::{ Body, SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// header: time, then the counts of all particles, dimensions, gas, dark matter and stars,
+// and padding to 32 bytes
+const HEADER_SIZE: usize = 32;
+// mass, position, velocity, softening and potential
+const DARK_FLOATS: usize = 9;
+// mass, position, velocity, density, temperature, smoothing length, metals and potential
+const GAS_FLOATS: usize = 12;
+// mass, position, velocity, metals, formation time, softening and potential
+const STAR_FLOATS: usize = 11;
+// an imported snapshot fills this much of the smaller side of the world with this share of
+// its particles, and takes this long to collapse in time units
+const FIT: f32 = 0.8;
+const FIT_QUANTILE: f32 = 0.9;
+const DYNAMICAL_TIME: f32 = 100.0;
+// what the softening lengths become, collisions go by the radius
+const MIN_RADIUS: f32 = 2.0;
+const MAX_RADIUS: f32 = 10.0;
+
+// a Tipsy snapshot as pynbody, yt and the original tipsy tools read it: big endian, with the
+// plane at z = 0. Bodies are dark matter particles and black holes stars, so they load as
+// two families. Masses are multiplied by `gravity` because the format's tools take G = 1,
+// which leaves every acceleration the same. Softening is the body's radius
+pub fn write(bodies: &Bodies, path: &str, time: f32, gravity: f32) -> io::Result<()> {
+    let dark: Vec<usize> = (0..bodies.len()).filter(|&i| !bodies.black_holes[i]).collect();
+    let stars: Vec<usize> = (0..bodies.len()).filter(|&i| bodies.black_holes[i]).collect();
+    let size = HEADER_SIZE + 4 * (DARK_FLOATS * dark.len() + STAR_FLOATS * stars.len());
+    let mut bytes = Vec::with_capacity(size);
+    bytes.extend_from_slice(&(time as f64).to_be_bytes());
+    for count in [bodies.len(), 3, 0, dark.len(), stars.len(), 0] {
+        bytes.extend_from_slice(&(count as i32).to_be_bytes());
+    }
+
+    let particle = |bytes: &mut Vec<u8>, index: usize, star: bool| {
+        let (position, velocity) = (bodies.positions[index], bodies.velocities[index]);
+        let mut floats = vec![
+            bodies.masses[index] * gravity,
+            position.x,
+            position.y,
+            0.0,
+            velocity.x,
+            velocity.y,
+            0.0,
+        ];
+        if star {
+            // no metals, formed at the start
+            floats.extend_from_slice(&[0.0, 0.0]);
+        }
+        // the potential is left for the tools to work out
+        floats.extend_from_slice(&[bodies.radii[index], 0.0]);
+        for value in floats {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+    };
+    for &index in &dark {
+        particle(&mut bytes, index, false);
+    }
+    for &index in &stars {
+        particle(&mut bytes, index, true);
+    }
+    fs::write(path, bytes)
+}
+
+// which two of the snapshot's axes become the plane, the third goes into the depth of the
+// 2.5D view
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Plane {
+    Xy,
+    Xz,
+    Yz,
+}
+
+impl Plane {
+    pub const ALL: [Plane; 3] = [Plane::Xy, Plane::Xz, Plane::Yz];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Plane::Xy => "xy",
+            Plane::Xz => "xz",
+            Plane::Yz => "yz",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Plane::ALL.into_iter().find(|plane| plane.name() == name)
+    }
+
+    // the two in-plane components first and the one left out last
+    fn project(self, vector: Vec3) -> Vec3 {
+        match self {
+            Plane::Xy => vector,
+            Plane::Xz => vec3(vector.x, vector.z, vector.y),
+            Plane::Yz => vec3(vector.y, vector.z, vector.x),
+        }
+    }
+}
+
+pub struct Particle {
+    pub family: &'static str,
+    pub mass: f32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub softening: f32,
+}
+
+pub struct Snapshot {
+    pub time: f64,
+    pub particles: Vec<Particle>,
+}
+
+// both byte orders, and headers with or without the padding to 32 bytes, all of which are
+// around. The file's size tells which one it is
+pub fn read(path: &str) -> Result<Snapshot, String> {
+    let bytes = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    for big_endian in [true, false] {
+        let int = |offset: usize| {
+            let word: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+            let value = if big_endian {
+                i32::from_be_bytes(word)
+            } else {
+                i32::from_le_bytes(word)
+            };
+            usize::try_from(value).ok()
+        };
+        // the total, dimensions, gas, dark matter and stars
+        let counts = (int(8), int(12), int(16), int(20), int(24));
+        let (Some(count), Some(3), Some(gas), Some(dark), Some(stars)) = counts else {
+            continue;
+        };
+        if gas + dark + stars != count {
+            continue;
+        }
+        let particles = 4 * (GAS_FLOATS * gas + DARK_FLOATS * dark + STAR_FLOATS * stars);
+        let headers = [HEADER_SIZE, HEADER_SIZE - 4];
+        let Some(header) = headers.into_iter().find(|header| header + particles == bytes.len())
+        else {
+            continue;
+        };
+
+        let float = |offset: usize| {
+            let word: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+            if big_endian { f32::from_be_bytes(word) } else { f32::from_le_bytes(word) }
+        };
+        let time: [u8; 8] = bytes[0..8].try_into().unwrap();
+        let time = if big_endian { f64::from_be_bytes(time) } else { f64::from_le_bytes(time) };
+        let mut snapshot = Snapshot { time, particles: Vec::with_capacity(count) };
+        let mut offset = header;
+        // where the softening is in each family's record
+        for (family, amount, floats, softening) in [
+            ("gas", gas, GAS_FLOATS, GAS_FLOATS - 3),
+            ("dark", dark, DARK_FLOATS, DARK_FLOATS - 2),
+            ("star", stars, STAR_FLOATS, STAR_FLOATS - 2),
+        ] {
+            for _ in 0..amount {
+                let at = |field: usize| float(offset + 4 * field);
+                snapshot.particles.push(Particle {
+                    family,
+                    mass: at(0),
+                    position: vec3(at(1), at(2), at(3)),
+                    velocity: vec3(at(4), at(5), at(6)),
+                    softening: at(softening),
+                });
+                offset += 4 * floats;
+            }
+        }
+        return Ok(snapshot);
+    }
+    Err(format!("{}: not a Tipsy snapshot", path))
+}
+
+// the snapshot moved into the world: centered on its center of mass and at rest, sized so
+// most of it fills the middle of the screen, and with the clock set so it takes
+// `DYNAMICAL_TIME` to fall together. Masses follow from the two so the orbits come out the
+// same under `gravity`. Returns the bodies and the length and time scales used
+pub fn place(
+    snapshot: &Snapshot,
+    plane: Plane,
+    gravity: f32
+) -> Result<(Vec<Body>, f32, f32), String> {
+    let particles = &snapshot.particles;
+    let total: f32 = particles.iter().map(|particle| particle.mass).sum();
+    if particles.is_empty() || total <= 0.0 || !total.is_finite() {
+        return Err("the snapshot has no mass in it".to_string());
+    }
+    let weighted = |value: &dyn Fn(&Particle) -> Vec3| {
+        let sum = particles
+            .iter()
+            .fold(Vec3::ZERO, |sum, particle| sum + value(particle) * particle.mass);
+        sum / total
+    };
+    let center = plane.project(weighted(&|particle| particle.position));
+    let drift = plane.project(weighted(&|particle| particle.velocity));
+
+    // a quantile rather than the farthest one, a few strays would shrink everything else
+    let mut distances: Vec<f32> = particles
+        .iter()
+        .map(|particle| (plane.project(particle.position) - center).truncate().length())
+        .collect();
+    distances.sort_by(f32::total_cmp);
+    let extent = distances[((distances.len() - 1) as f32 * FIT_QUANTILE) as usize];
+    if extent <= 0.0 || !extent.is_finite() {
+        return Err("the snapshot's particles are all in one place".to_string());
+    }
+    let length = (FIT * SCREEN_WIDTH.min(SCREEN_HEIGHT)) / 2.0 / extent;
+    // G = 1 in the snapshot's units
+    let falling = (extent.powi(3) / total).sqrt();
+    let time = DYNAMICAL_TIME / falling;
+    let mass = length.powi(3) / (time.powi(2) * gravity);
+
+    let middle = vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+    let bodies = particles
+        .iter()
+        .map(|particle| {
+            let position = plane.project(particle.position) - center;
+            let velocity = plane.project(particle.velocity) - drift;
+            Body {
+                position: middle + position.truncate() * length,
+                velocity: velocity.truncate() * (length / time),
+                mass: particle.mass * mass,
+                radius: (particle.softening * length).clamp(MIN_RADIUS, MAX_RADIUS),
+                depth: (position.z / extent).clamp(-1.0, 1.0),
+                ..Body::new(Vec2::ZERO)
+            }
+        })
+        .collect();
+    Ok((bodies, length, time))
+}
@@ -0,0 +1,96 @@
+use std::collections::{ HashMap, HashSet, VecDeque };
+
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::ewald;
+use crate::view::View;
+
+// the most points a trail can hold, whatever the setting
+pub const MAX_TRAIL_LENGTH: usize = 1000;
+
+// the last `length` positions of every body, oldest first. Nothing is kept while hidden
+#[derive(Default)]
+pub struct Trails {
+    pub visible: bool,
+    points: HashMap<u64, VecDeque<Vec2>>,
+}
+
+impl Trails {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        if !self.visible {
+            self.points.clear();
+        }
+    }
+
+    pub fn record(&mut self, bodies: &Bodies, length: usize) {
+        if !self.visible {
+            return;
+        }
+        for (&id, &position) in bodies.ids.iter().zip(&bodies.positions) {
+            // the buffers are reused once full, so a running trail doesn't allocate
+            let trail = self.points.entry(id).or_insert_with(|| VecDeque::with_capacity(length));
+            while trail.len() >= length.max(1) {
+                trail.pop_front();
+            }
+            trail.push_back(position);
+        }
+        // bodies that are gone take their trails with them
+        if self.points.len() > bodies.len() {
+            let alive: HashSet<u64> = bodies.ids.iter().copied().collect();
+            self.points.retain(|id, _| alive.contains(id));
+        }
+    }
+
+    // rewinding takes the trails back with the bodies
+    pub fn step_back(&mut self, steps: usize) {
+        for trail in self.points.values_mut() {
+            trail.truncate(trail.len().saturating_sub(steps));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    // `fade` shapes how quickly a trail thins out with age: 0 keeps it solid, 1 fades it
+    // linearly and higher values leave only the most recent part visible. `color` is the
+    // newest end's
+    pub fn draw(&self, view: &View, fade: f32, color: Color) {
+        if !self.visible {
+            return;
+        }
+        // co-moving, each point moves along by as far as the pinned body has gone since, so
+        // the trails are paths relative to it. Points older than its own trail are left out
+        let anchor = view.comoving().and_then(|id| self.points.get(&id));
+        let shift = |from_newest: usize| -> Option<Vec2> {
+            match anchor {
+                Some(anchor) => {
+                    let then = anchor.len().checked_sub(from_newest + 1)?;
+                    Some(*anchor.back()? - anchor[then])
+                }
+                None => Some(Vec2::ZERO),
+            }
+        };
+        for trail in self.points.values() {
+            let count = trail.len();
+            for (age, (from, to)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+                // jumps across the seam of a periodic world aren't part of the path
+                if ewald::crosses_seam(*from, *to) {
+                    continue;
+                }
+                // counted from the newest point, `to` being the newer of the two
+                let newer = count - 2 - age;
+                let (Some(from_shift), Some(to_shift)) = (shift(newer + 1), shift(newer)) else {
+                    continue;
+                };
+                let (from, to) = (*from + from_shift, *to + to_shift);
+                let recency = (age + 1) as f32 / count as f32;
+                let color = Color { a: color.a * recency.powf(fade), ..color };
+                let (from, to) = (view.world_to_screen(from), view.world_to_screen(to));
+                draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+            }
+        }
+    }
+}
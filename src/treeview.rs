@@ -0,0 +1,96 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::quadtree::QuadTree;
+use crate::settings::Settings;
+use crate::view::View;
+
+const CELL_COLOR: Color = Color::new(0.5, 0.5, 0.5, 0.25);
+const FAR_COLOR: Color = Color::new(0.0, 0.89, 0.19, 0.15);
+
+// the Barnes-Hut tree over the bodies as it's built for this frame's forces. For the
+// selected body the cells that stood in for their bodies as a single mass are filled, the
+// leaves whose bodies pulled on it one by one outlined, and what the walk came to written
+// next to it
+#[derive(Default)]
+pub struct TreeOverlay {
+    pub visible: bool,
+}
+
+#[derive(Default)]
+struct Walk {
+    opened: usize,
+    far: usize,
+    summed: usize,
+}
+
+impl TreeOverlay {
+    pub fn draw(&self, bodies: &Bodies, settings: &Settings, view: &View) {
+        if !self.visible || bodies.is_empty() {
+            return;
+        }
+        let tree = QuadTree::build(bodies, settings.leaf_capacity);
+        for node in &tree.nodes {
+            draw_cell(view, node.center, node.half_size, CELL_COLOR, None);
+        }
+
+        let Some(index) = bodies.selected.iter().position(|&selected| selected) else {
+            return;
+        };
+        let mut walk = Walk::default();
+        visit(&tree, 0, bodies, index, settings.theta, view, &mut walk);
+        let (position, scale) = view.project(&bodies.body(index));
+        let line = format!(
+            "{} cells opened, {} far enough for one mass, {} leaves summed",
+            walk.opened,
+            walk.far,
+            walk.summed
+        );
+        let x = position.x + bodies.radii[index] * scale + 6.0;
+        draw_text(&line, x, position.y, 16.0, YELLOW);
+    }
+}
+
+// the same walk as `solver::barnes_hut`, drawing instead of adding up forces
+fn visit(
+    tree: &QuadTree,
+    node: usize,
+    bodies: &Bodies,
+    index: usize,
+    theta: f32,
+    view: &View,
+    walk: &mut Walk
+) {
+    let node = &tree.nodes[node];
+    if node.is_leaf() {
+        if node.bodies.iter().any(|&other| other != index) {
+            walk.summed += 1;
+            draw_cell(view, node.center, node.half_size, ORANGE, None);
+        }
+        return;
+    }
+    if node.is_far(bodies.positions[index], theta) {
+        walk.far += 1;
+        draw_cell(view, node.center, node.half_size, GREEN, Some(FAR_COLOR));
+        let mass = view.world_to_screen(node.center_of_mass);
+        draw_circle(mass.x, mass.y, 2.0, GREEN);
+        return;
+    }
+    walk.opened += 1;
+    for &child in &node.children {
+        visit(tree, child, bodies, index, theta, view, walk);
+    }
+}
+
+fn draw_cell(view: &View, center: Vec2, half_size: f32, color: Color, fill: Option<Color>) {
+    let corners = [vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0)]
+        .map(|corner| view.world_to_screen(center + corner * half_size));
+    if let Some(fill) = fill {
+        draw_triangle(corners[0], corners[1], corners[2], fill);
+        draw_triangle(corners[0], corners[2], corners[3], fill);
+    }
+    for (i, &from) in corners.iter().enumerate() {
+        let to = corners[(i + 1) % 4];
+        draw_line(from.x, from.y, to.x, to.y, 1.0, color);
+    }
+}
@@ -0,0 +1,40 @@
+use std::f32::consts::TAU;
+
+// maps astronomical units onto the screen. Distances are compressed with a power law so
+// Mercury and Neptune both fit, and the central mass is picked so a body at 1 AU goes
+// around once per year. Periods elsewhere follow the compressed radii, not the real ones.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scale {
+    // pixels for 1 AU
+    pub au: f32,
+    // 1 keeps distances linear, smaller values pull the outer orbits in
+    pub exponent: f32,
+    // simulation time units per year
+    pub year: f32,
+    // simulation time when the scale came into use, the clock starts there
+    pub epoch: f32,
+}
+
+impl Scale {
+    pub fn distance(&self, au: f32) -> f32 {
+        self.au * au.powf(self.exponent)
+    }
+
+    pub fn au_from_pixels(&self, pixels: f32) -> f32 {
+        (pixels / self.au).powf(1.0 / self.exponent)
+    }
+
+    pub fn years(&self, time: f32) -> f32 {
+        (time - self.epoch) / self.year
+    }
+
+    // Kepler's third law at 1 AU: G M = 4 pi^2 a^3 / T^2
+    pub fn central_mass(&self, gravity: f32) -> f32 {
+        TAU.powi(2) * self.au.powi(3) / (gravity * self.year.powi(2))
+    }
+
+    // speed of a circular orbit at the given on-screen radius
+    pub fn circular_speed(&self, gravity: f32, central_mass: f32, pixels: f32) -> f32 {
+        (gravity * central_mass / pixels).sqrt()
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+
+use macroquad::prelude::*;
+
+use crate::gif::GifWriter;
+use crate::screenshot;
+
+// GIFs get big quickly, wider windows are scaled down to this
+const MAX_GIF_WIDTH: u32 = 640;
+
+enum Output {
+    // opened on the first frame, once the size is known
+    Gif(Option<GifWriter>),
+    // numbered PNGs in a directory, for ffmpeg and the like
+    Frames,
+}
+
+// the window, captured every `every`th frame as drawn, without the console, into a looping
+// GIF or a directory of numbered PNGs
+pub struct Video {
+    pub path: String,
+    output: Output,
+    every: usize,
+    drawn: usize,
+    pub frames: usize,
+    // since the last captured frame, for the GIF delays
+    elapsed: f32,
+}
+
+impl Video {
+    // a path ending in `.gif` makes a GIF, anything else names the directory for the PNGs
+    pub fn start(path: &str, every: usize) -> Result<Self, String> {
+        let output = if path.to_lowercase().ends_with(".gif") {
+            Output::Gif(None)
+        } else {
+            fs::create_dir_all(path).map_err(|err| format!("{}: {}", path, err))?;
+            Output::Frames
+        };
+        Ok(Video {
+            path: path.to_string(),
+            output,
+            every: every.max(1),
+            drawn: 0,
+            frames: 0,
+            elapsed: 0.0,
+        })
+    }
+
+    // every frame once it's drawn, `frame_time` being how long the last one took
+    pub fn capture(&mut self, frame_time: f32) -> Result<(), String> {
+        self.elapsed += frame_time;
+        self.drawn += 1;
+        if !(self.drawn - 1).is_multiple_of(self.every) {
+            return Ok(());
+        }
+        match &mut self.output {
+            Output::Frames => {
+                let path = format!("{}/frame-{:05}.png", self.path, self.frames);
+                screenshot::write(&path)?;
+            }
+            Output::Gif(writer) => {
+                let screen = get_screen_data();
+                let (width, height) = (screen.width as u32, screen.height as u32);
+                if width == 0 || height == 0 {
+                    return Ok(());
+                }
+                let writer = match writer {
+                    Some(writer) => writer,
+                    None => {
+                        let scaled_width = width.min(MAX_GIF_WIDTH);
+                        let scaled_height = (height * scaled_width / width).max(1);
+                        let created =
+                            GifWriter::create(&self.path, scaled_width as u16, scaled_height as u16)
+                                .map_err(|err| format!("failed to write {}: {}", self.path, err))?;
+                        writer.insert(created)
+                    }
+                };
+                // the screen comes bottom row first, and in whatever size the window has now
+                let (to_width, to_height) = (writer.width as u32, writer.height as u32);
+                let mut pixels = Vec::with_capacity((to_width * to_height) as usize);
+                for y in 0..to_height {
+                    let from_y = height - 1 - (y * height / to_height).min(height - 1);
+                    for x in 0..to_width {
+                        let from_x = (x * width / to_width).min(width - 1);
+                        let at = 4 * (from_y * width + from_x) as usize;
+                        pixels.push(screen.bytes[at..at + 4].try_into().unwrap());
+                    }
+                }
+                // GIF delays are hundredths of a second and most viewers won't go below 2
+                let delay = ((self.elapsed * 100.0).round() as u16).max(2);
+                writer
+                    .frame(&pixels, delay)
+                    .map_err(|err| format!("failed to write {}: {}", self.path, err))?;
+            }
+        }
+        self.elapsed = 0.0;
+        self.frames += 1;
+        Ok(())
+    }
+
+    // how many frames it wrote
+    pub fn stop(self) -> Result<usize, String> {
+        if let Output::Gif(Some(writer)) = self.output {
+            writer.finish().map_err(|err| format!("failed to write {}: {}", self.path, err))?;
+        }
+        Ok(self.frames)
+    }
+}
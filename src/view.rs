@@ -0,0 +1,450 @@
+use macroquad::prelude::*;
+
+use crate::bodies::Bodies;
+use crate::broadphase;
+use crate::coloring::ColorMode;
+use crate::density::RenderMode;
+use crate::{ diagnostics, Body, SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// pixels drawn per unit of fictitious acceleration
+const FICTITIOUS_FORCE_SCALE: f32 = 500.0;
+// how strongly depth shrinks and shifts bodies in the 2.5D view
+const DEPTH_PERSPECTIVE: f32 = 0.35;
+// how far in and out the camera goes, and how much one notch of the wheel zooms
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.0;
+const ZOOM_STEP: f32 = 1.1;
+// longest arrow head in pixels, shorter arrows get a head half their length
+const ARROW_HEAD: f32 = 8.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReferenceFrame {
+    Inertial,
+    CenterOfMass,
+    // follows a single body by id, so it stays on that body whatever else is added or removed.
+    // Co-moving, trails and velocity arrows are drawn relative to it too. Only the drawing
+    // changes, the bodies keep their velocities
+    Pinned {
+        id: u64,
+        match_velocity: bool,
+    },
+}
+
+impl ReferenceFrame {
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceFrame::Inertial => "inertial",
+            ReferenceFrame::CenterOfMass => "center of mass",
+            ReferenceFrame::Pinned { match_velocity: true, .. } => "pinned and co-moving",
+            ReferenceFrame::Pinned { .. } => "pinned",
+        }
+    }
+}
+
+// view that turns around the origin, stacked on top of the reference frame
+pub struct Rotation {
+    pub angle: f32,
+    pub angular_velocity: f32,
+    // follows the mean motion of two bodies, by id, and keeps their barycenter in the middle
+    pub locked_pair: Option<(u64, u64)>,
+}
+
+pub struct View {
+    pub frame: ReferenceFrame,
+    // world position shown at the middle of the screen
+    pub origin: Vec2,
+    pub rotation: Option<Rotation>,
+    pub show_fictitious_forces: bool,
+    // an arrow along every body's velocity, `Settings::velocity_scale` long per unit of speed
+    pub show_velocities: bool,
+    // an arrow along every body's net acceleration as the solver last worked it out,
+    // `Settings::acceleration_scale` long per unit
+    pub show_accelerations: bool,
+    pub depth_mode: bool,
+    pub color_mode: ColorMode,
+    pub render_mode: RenderMode,
+    // camera on top of the frame: screen pixels per world unit, and how far the middle of the
+    // screen has been dragged from the frame's origin, in world units
+    pub zoom: f32,
+    pub pan: Vec2,
+    // id of the body the camera keeps in the middle of the screen. Unlike the pinned frame it
+    // only moves the camera, trails and arrows stay as they are
+    pub follow: Option<u64>,
+}
+
+fn world_center() -> Vec2 {
+    vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0)
+}
+
+// the middle of the window, whatever size it has been resized to
+fn screen_center() -> Vec2 {
+    vec2(screen_width(), screen_height()) / 2.0
+}
+
+// pixels per world unit that fit the whole world in the window at a zoom of 1, 1 at the
+// window's starting size. The world keeps its size, so resizing only moves the camera
+fn fit() -> f32 {
+    (screen_width() / SCREEN_WIDTH).min(screen_height() / SCREEN_HEIGHT).max(f32::EPSILON)
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            frame: ReferenceFrame::Inertial,
+            origin: world_center(),
+            rotation: None,
+            show_fictitious_forces: false,
+            show_velocities: false,
+            show_accelerations: false,
+            depth_mode: false,
+            color_mode: ColorMode::Plain,
+            render_mode: RenderMode::Bodies,
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            follow: None,
+        }
+    }
+}
+
+impl View {
+    pub fn toggle_center_of_mass(&mut self) {
+        self.frame = match self.frame {
+            ReferenceFrame::CenterOfMass => ReferenceFrame::Inertial,
+            _ => ReferenceFrame::CenterOfMass,
+        };
+        if self.frame == ReferenceFrame::Inertial {
+            self.origin = View::default().origin;
+        }
+    }
+
+    pub fn toggle_pinned(&mut self, bodies: &Bodies, match_velocity: bool) {
+        if let ReferenceFrame::Pinned { .. } = self.frame {
+            self.frame = ReferenceFrame::Inertial;
+            self.origin = View::default().origin;
+            return;
+        }
+
+        if let Some(index) = bodies.selected.iter().position(|&selected| selected) {
+            self.frame = ReferenceFrame::Pinned { id: bodies.ids[index], match_velocity };
+        }
+    }
+
+    // locks onto the first two selected bodies, or spins freely at the given rate
+    pub fn toggle_rotation(&mut self, bodies: &Bodies, angular_velocity: f32) {
+        if self.rotation.take().is_some() {
+            return;
+        }
+
+        let mut selected = bodies.selected
+            .iter()
+            .zip(&bodies.ids)
+            .filter(|(&selected, _)| selected)
+            .map(|(_, &id)| id);
+        let locked_pair = match (selected.next(), selected.next()) {
+            (Some(a), Some(b)) => Some((a, b)),
+            _ => None,
+        };
+
+        self.rotation = Some(Rotation {
+            angle: 0.0,
+            angular_velocity,
+            locked_pair,
+        });
+    }
+
+    pub fn adjust_rotation(&mut self, amount: f32) {
+        if let Some(rotation) = self.rotation.as_mut() {
+            rotation.locked_pair = None;
+            rotation.angular_velocity += amount;
+        }
+    }
+
+    pub fn apply_frame(&mut self, bodies: &mut Bodies, dt: f32) {
+        self.apply_translation(bodies);
+
+        let Some(rotation) = self.rotation.as_mut() else {
+            return;
+        };
+
+        if let Some((a, b)) = rotation.locked_pair {
+            // swallowed or deleted, the view goes on turning at the rate it had
+            let find = |id: u64| bodies.ids.iter().position(|&other| other == id);
+            match (find(a).and_then(|a| bodies.get(a)), find(b).and_then(|b| bodies.get(b))) {
+                (Some(a), Some(b)) => {
+                    let offset = b.position - a.position;
+                    let relative_velocity = b.velocity - a.velocity;
+                    if offset.length_squared() > 0.0 {
+                        rotation.angular_velocity =
+                            offset.perp_dot(relative_velocity) / offset.length_squared();
+                    }
+                    self.origin = (a.position * a.mass + b.position * b.mass) / (a.mass + b.mass);
+                }
+                _ => {
+                    rotation.locked_pair = None;
+                }
+            }
+        }
+
+        rotation.angle += rotation.angular_velocity * dt;
+    }
+
+    fn apply_translation(&mut self, bodies: &mut Bodies) {
+        match self.frame {
+            ReferenceFrame::Inertial => {}
+            // keeps the system's center of mass at rest and in the middle of the view
+            ReferenceFrame::CenterOfMass => {
+                // over the bodies it can change the velocity of, frozen ones would keep
+                // pulling the frame along with whatever velocity they were frozen with
+                let (mut mass, mut momentum) = (0.0, Vec2::ZERO);
+                for i in 0..bodies.len() {
+                    if !bodies.freezed[i] {
+                        mass += bodies.masses[i];
+                        momentum += bodies.velocities[i] * bodies.masses[i];
+                    }
+                }
+                if mass == 0.0 {
+                    return;
+                }
+
+                let center_velocity = momentum / mass;
+                for i in 0..bodies.len() {
+                    if !bodies.freezed[i] {
+                        bodies.velocities[i] -= center_velocity;
+                    }
+                }
+                self.origin = diagnostics::center_of_mass(bodies);
+            }
+            ReferenceFrame::Pinned { id, .. } => {
+                let Some(index) = bodies.ids.iter().position(|&other| other == id) else {
+                    // the pinned body is gone, fall back to the plain view
+                    self.frame = ReferenceFrame::Inertial;
+                    self.origin = View::default().origin;
+                    return;
+                };
+                self.origin = bodies.positions[index];
+            }
+        }
+    }
+
+    // the id of the body trails and velocities are drawn relative to, if any
+    pub fn comoving(&self) -> Option<u64> {
+        match self.frame {
+            ReferenceFrame::Pinned { id, match_velocity: true } => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn angle(&self) -> f32 {
+        self.rotation.as_ref().map_or(0.0, |rotation| rotation.angle)
+    }
+
+    // the world position at the middle of the screen
+    pub fn camera(&self) -> Vec2 {
+        self.origin + self.pan
+    }
+
+    // screen pixels per world unit, the zoom on top of fitting the world in the window
+    pub fn scale(&self) -> f32 {
+        self.zoom * fit()
+    }
+
+    pub fn world_to_screen(&self, position: Vec2) -> Vec2 {
+        let offset = position - self.origin - self.pan;
+        Vec2::from_angle(-self.angle()).rotate(offset) * self.scale() + screen_center()
+    }
+
+    pub fn screen_to_world(&self, position: Vec2) -> Vec2 {
+        let offset = (position - screen_center()) / self.scale();
+        Vec2::from_angle(self.angle()).rotate(offset) + self.origin + self.pan
+    }
+
+    // one notch per call, in for positive `notches`. The world point under `anchor` stays
+    // put, so the wheel zooms towards the mouse
+    pub fn zoom_at(&mut self, anchor: Vec2, notches: f32) {
+        let before = self.screen_to_world(anchor);
+        self.zoom = (self.zoom * ZOOM_STEP.powf(notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan += before - self.screen_to_world(anchor);
+    }
+
+    // drags the world along with the mouse, `delta` in screen pixels. Lets go of a followed body
+    pub fn pan_by(&mut self, delta: Vec2) {
+        self.follow = None;
+        self.pan -= Vec2::from_angle(self.angle()).rotate(delta) / self.scale();
+    }
+
+    // moves the camera so `position` is in the middle of the screen, letting go of a
+    // followed body
+    pub fn center_on(&mut self, position: Vec2) {
+        self.follow = None;
+        self.pan += position - self.screen_to_world(screen_center());
+    }
+
+    pub fn reset_camera(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+        self.follow = None;
+    }
+
+    // follows the first selected body, or stops following
+    pub fn toggle_follow(&mut self, bodies: &Bodies) {
+        if self.follow.take().is_some() {
+            return;
+        }
+        let selected = bodies.selected.iter().position(|&selected| selected);
+        self.follow = selected.map(|index| bodies.ids[index]);
+        self.track(bodies);
+    }
+
+    // recenters on the followed body, every frame it's drawn
+    pub fn track(&mut self, bodies: &Bodies) {
+        let Some(id) = self.follow else {
+            return;
+        };
+        match bodies.ids.iter().position(|&other| other == id) {
+            Some(index) => {
+                self.pan = bodies.positions[index] - self.origin;
+            }
+            // swallowed or deleted, the camera stays where it was
+            None => {
+                self.follow = None;
+            }
+        }
+    }
+
+    fn depth_scale(&self, body: &Body) -> f32 {
+        if self.depth_mode { 1.0 / (1.0 + body.depth * DEPTH_PERSPECTIVE) } else { 1.0 }
+    }
+
+    // screen position and size factor, with the zoom and perspective when the 2.5D view is on
+    pub fn project(&self, body: &Body) -> (Vec2, f32) {
+        let scale = self.depth_scale(body);
+        let position = (self.world_to_screen(body.position) - screen_center()) * scale;
+        (position + screen_center(), scale * self.scale())
+    }
+
+    // whether a click at `screen` lands on the body where it's drawn, within twice its radius
+    pub fn hits(&self, body: &Body, screen: Vec2) -> bool {
+        let (position, scale) = self.project(body);
+        position.distance(screen) < 2.0 * body.radius * scale
+    }
+
+    // back to front so nearer bodies are drawn on top
+    pub fn draw_order(&self, bodies: &Bodies) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..bodies.len()).collect();
+        if self.depth_mode {
+            let depths = &bodies.depths;
+            order.sort_by(|&a, &b| depths[b].total_cmp(&depths[a]));
+        }
+        order
+    }
+
+    pub fn depth_tint(&self, body: &Body, color: Color) -> Color {
+        if !self.depth_mode {
+            return color;
+        }
+        let brightness = 1.0 - ((body.depth + 1.0) / 2.0) * 0.6;
+        Color::new(color.r * brightness, color.g * brightness, color.b * brightness, color.a)
+    }
+
+    pub fn draw_bounds(&self) {
+        let camera_moved = self.zoom != 1.0 || self.pan != Vec2::ZERO || self.follow.is_some();
+        // a window of another shape than the world leaves room beside it
+        let fills_window =
+            (screen_width() - SCREEN_WIDTH * fit()).abs() < 1.0 &&
+            (screen_height() - SCREEN_HEIGHT * fit()).abs() < 1.0;
+        let still = self.frame == ReferenceFrame::Inertial && self.rotation.is_none();
+        if still && !camera_moved && fills_window {
+            return;
+        }
+        let corners = [
+            vec2(0.0, 0.0),
+            vec2(SCREEN_WIDTH, 0.0),
+            vec2(SCREEN_WIDTH, SCREEN_HEIGHT),
+            vec2(0.0, SCREEN_HEIGHT),
+        ].map(|corner| self.world_to_screen(corner));
+        for i in 0..corners.len() {
+            let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+            draw_line(a.x, a.y, b.x, b.y, 1.0, DARKGRAY);
+        }
+    }
+
+    // centrifugal and coriolis accelerations as seen from the rotating view
+    pub fn draw_fictitious_forces(&self, bodies: &Bodies) {
+        let Some(rotation) = self.rotation.as_ref() else {
+            return;
+        };
+        if !self.show_fictitious_forces {
+            return;
+        }
+
+        let omega = rotation.angular_velocity;
+        for body in bodies.iter().filter(|body| !body.freezed) {
+            let offset = body.position - self.origin;
+            let rotating_velocity = body.velocity - omega * offset.perp();
+            let centrifugal = omega.powi(2) * offset;
+            let coriolis = -2.0 * omega * rotating_velocity.perp();
+
+            let start = self.world_to_screen(body.position);
+            for (acceleration, color) in [(centrifugal, ORANGE), (coriolis, VIOLET)] {
+                let tip = body.position + acceleration * FICTITIOUS_FORCE_SCALE;
+                let end = self.world_to_screen(tip);
+                draw_line(start.x, start.y, end.x, end.y, 1.0, color);
+            }
+        }
+    }
+
+    pub fn draw_velocities(&self, bodies: &Bodies, scale: f32) {
+        if !self.show_velocities {
+            return;
+        }
+        let frame_velocity = self
+            .comoving()
+            .and_then(|id| bodies.ids.iter().position(|&other| other == id))
+            .map_or(Vec2::ZERO, |index| bodies.velocities[index]);
+        for body in bodies.iter().filter(|body| body.velocity != frame_velocity) {
+            let start = self.world_to_screen(body.position);
+            let velocity = body.velocity - frame_velocity;
+            let end = self.world_to_screen(body.position + velocity * scale);
+            draw_arrow(start, end, 1.0, LIME);
+        }
+    }
+
+    // bodies closer to another than twice their radius, whose pull between the two the force
+    // cutoff has switched off, get a red ring so a short arrow near a collision isn't a
+    // surprise
+    pub fn draw_accelerations(&self, bodies: &Bodies, scale: f32, cutoff: bool) {
+        if !self.show_accelerations {
+            return;
+        }
+        for (index, body) in bodies.iter().enumerate() {
+            let acceleration = bodies.forces[index] / body.mass.max(f32::EPSILON);
+            let start = self.world_to_screen(body.position);
+            let end = self.world_to_screen(body.position + acceleration * scale);
+            draw_arrow(start, end, 1.0, MAGENTA);
+        }
+        if !cutoff {
+            return;
+        }
+        let max_radius = bodies.radii.iter().fold(0.0f32, |max, &radius| max.max(radius));
+        let mut switched_off = vec![false; bodies.len()];
+        for (i, j) in broadphase::pairs_within(bodies, max_radius) {
+            let distance = bodies.positions[i].distance(bodies.positions[j]);
+            switched_off[i] |= distance < 2.0 * bodies.radii[i];
+            switched_off[j] |= distance < 2.0 * bodies.radii[j];
+        }
+        for index in (0..bodies.len()).filter(|&index| switched_off[index]) {
+            let (position, scale) = self.project(&bodies.body(index));
+            let radius = bodies.radii[index] * scale + 3.0;
+            draw_circle_lines(position.x, position.y, radius, 1.0, RED);
+        }
+    }
+}
+
+pub fn draw_arrow(from: Vec2, to: Vec2, thickness: f32, color: Color) {
+    draw_line(from.x, from.y, to.x, to.y, thickness, color);
+    let length = from.distance(to);
+    let back = (from - to).normalize_or_zero() * ARROW_HEAD.min(length / 2.0);
+    for side in [back.rotate(Vec2::from_angle(0.4)), back.rotate(Vec2::from_angle(-0.4))] {
+        draw_line(to.x, to.y, to.x + side.x, to.y + side.y, thickness, color);
+    }
+}
@@ -0,0 +1,219 @@
+use std::mem;
+
+use crate::bodies::Bodies;
+use crate::diagnostics::{ self, EnergyTracker };
+use crate::settings::{ Settings, MIN_DT };
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// a step that adds this much energy, as a fraction of K + |U| before it, blew up. Close
+// encounters cost the integrator a lot less (the soak test draws the line at the same place)
+const ENERGY_JUMP: f64 = 0.5;
+// with the velocity clamp off, speeds past this many times its limit are running away
+const RUNAWAY: f32 = 10.0;
+// rounding the clamp may leave
+const CLAMP_SLACK: f32 = 1e-3;
+// bodies whose surface is within this many pixels of a corner, and how many make a pile: at
+// least this many, and this many times as many as would be there if they were spread evenly
+const CORNER_REACH: f32 = 30.0;
+const CORNER_PILE: usize = 10;
+const CORNER_CROWDING: f32 = 5.0;
+// frames after stepping in before looking again, so a smaller step gets to show its effect
+const COOLDOWN: usize = 60;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Off,
+    // halves the time step where that helps and pauses where it doesn't
+    Tune,
+    Pause,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Off, Mode::Tune, Mode::Pause];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Tune => "tune",
+            Mode::Pause => "pause",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Mode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+enum Problem {
+    NotFinite(usize),
+    Runaway {
+        count: usize,
+        fastest: f32,
+        limit: f32,
+    },
+    EnergyJump(f64),
+    CornerPile(usize),
+}
+
+impl Problem {
+    fn describe(&self) -> String {
+        match self {
+            Problem::NotFinite(count) => {
+                format!("{} bodies have no finite position or velocity left", count)
+            }
+            Problem::Runaway { count, fastest, limit } => {
+                format!("{} bodies are faster than {}, the fastest at {:.1}", count, limit, fastest)
+            }
+            Problem::EnergyJump(fraction) => {
+                format!("the last step added {:.0}% of K + |U| to the energy", fraction * 100.0)
+            }
+            Problem::CornerPile(count) => format!("{} bodies are piled up in a corner", count),
+        }
+    }
+
+    // what to do about it when a smaller step won't do
+    fn advice(&self) -> &'static str {
+        match self {
+            Problem::NotFinite(_) => "hold backspace to rewind or press L to reload",
+            Problem::Runaway { .. } | Problem::EnergyJump(_) => {
+                "try a smaller dt, more substeps or another integrator"
+            }
+            Problem::CornerPile(_) => {
+                "the wall hacks trap them there, `set wall_loss 0` or space to clear"
+            }
+        }
+    }
+
+    // a smaller time step is the cure for integration errors only
+    fn tunable(&self) -> bool {
+        matches!(self, Problem::Runaway { .. } | Problem::EnergyJump(_))
+    }
+}
+
+pub enum Verdict {
+    Tuned(String),
+    Paused {
+        problem: String,
+        advice: &'static str,
+    },
+}
+
+// looks at every step for states the simulation can't recover from on its own, so they are
+// fixed or stopped rather than drawn as if nothing happened
+#[derive(Default)]
+pub struct Watchdog {
+    // kinetic energy and body count right before the step
+    before: Option<(f64, usize)>,
+    // potential energy after the last step, and the gravity it was worked out with
+    potential: Option<(f64, f32)>,
+    cooldown: usize,
+    // the kind of problem it last paused for, not paused for again until it has cleared
+    reported: Option<mem::Discriminant<Problem>>,
+}
+
+impl Watchdog {
+    pub fn before_step(&mut self, bodies: &Bodies) {
+        self.before = Some((diagnostics::kinetic_energy(bodies), bodies.len()));
+    }
+
+    // after the step, once `energy` has caught up with it. `disturbed` is a frame where
+    // something besides the step moved bodies or changed their energy, the mouse or a
+    // command, so the energy can't be held against the step
+    pub fn check(
+        &mut self,
+        bodies: &Bodies,
+        energy: &EnergyTracker,
+        disturbed: bool,
+        settings: &mut Settings
+    ) -> Option<Verdict> {
+        let before = self.before.take();
+        let potential = self.potential.replace((energy.potential, settings.gravity));
+        if settings.watchdog == Mode::Off {
+            return None;
+        }
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return None;
+        }
+        let energy_before = match (before, potential) {
+            (Some((kinetic, count)), Some((potential, gravity)))
+                if count == bodies.len() && gravity == settings.gravity && !disturbed => {
+                Some((kinetic + potential, kinetic + potential.abs()))
+            }
+            _ => None,
+        };
+        let Some(problem) = diagnose(bodies, energy, energy_before, settings) else {
+            self.reported = None;
+            return None;
+        };
+        let kind = mem::discriminant(&problem);
+        let tune = settings.watchdog == Mode::Tune && problem.tunable();
+        if tune && settings.dt / 2.0 >= MIN_DT {
+            settings.dt /= 2.0;
+            self.cooldown = COOLDOWN;
+            let verdict = format!("{}, halved dt to {}", problem.describe(), settings.dt);
+            return Some(Verdict::Tuned(verdict));
+        }
+        if self.reported == Some(kind) {
+            return None;
+        }
+        self.reported = Some(kind);
+        self.cooldown = COOLDOWN;
+        Some(Verdict::Paused { problem: problem.describe(), advice: problem.advice() })
+    }
+}
+
+fn diagnose(
+    bodies: &Bodies,
+    energy: &EnergyTracker,
+    before: Option<(f64, f64)>,
+    settings: &Settings
+) -> Option<Problem> {
+    let broken = (0..bodies.len())
+        .filter(|&i| !bodies.positions[i].is_finite() || !bodies.velocities[i].is_finite())
+        .count();
+    if broken > 0 {
+        return Some(Problem::NotFinite(broken));
+    }
+
+    let limit = if settings.hacks.velocity_clamp {
+        settings.max_velocity * (1.0 + CLAMP_SLACK)
+    } else {
+        settings.max_velocity * RUNAWAY
+    };
+    let speeds = (0..bodies.len())
+        .filter(|&i| !bodies.freezed[i] && !bodies.black_holes[i])
+        .map(|i| bodies.velocities[i].length());
+    let (count, fastest) = speeds
+        .filter(|&speed| speed > limit)
+        .fold((0, 0.0f32), |(count, fastest), speed| (count + 1, fastest.max(speed)));
+    if count > 0 {
+        return Some(Problem::Runaway { count, fastest, limit: limit.round() });
+    }
+
+    if let Some((total, scale)) = before {
+        let fraction = (energy.total() - total) / scale.max(f64::EPSILON);
+        if fraction > ENERGY_JUMP {
+            return Some(Problem::EnergyJump(fraction));
+        }
+    }
+
+    if !settings.periodic {
+        let share = CORNER_REACH.powi(2) / (SCREEN_WIDTH * SCREEN_HEIGHT);
+        let pile = CORNER_PILE.max((CORNER_CROWDING * share * (bodies.len() as f32)) as usize);
+        let (right, bottom) = (SCREEN_WIDTH, SCREEN_HEIGHT);
+        for (x, y) in [(0.0, 0.0), (right, 0.0), (0.0, bottom), (right, bottom)] {
+            let piled = (0..bodies.len())
+                .filter(|&i| {
+                    let position = bodies.positions[i];
+                    let reach = CORNER_REACH + bodies.radii[i];
+                    (position.x - x).abs() < reach && (position.y - y).abs() < reach
+                })
+                .count();
+            if piled >= pile {
+                return Some(Problem::CornerPile(piled));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,68 @@
+use macroquad::window::Conf;
+
+use crate::{ SCREEN_HEIGHT, SCREEN_WIDTH };
+
+// `--window <width>x<height>`, `--fullscreen`, `--msaa <samples>` and `--low-dpi`: how the
+// window opens. The world keeps its size whatever the window's, the view fits it in
+pub struct WindowConfig {
+    pub width: i32,
+    pub height: i32,
+    pub fullscreen: bool,
+    // drawing at the display's full resolution. Sizes, positions and the mouse stay in
+    // points either way, macroquad scales them, so only the sharpness changes
+    pub high_dpi: bool,
+    // multisampling, 1 for none
+    pub samples: i32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: SCREEN_WIDTH as i32,
+            height: SCREEN_HEIGHT as i32,
+            fullscreen: false,
+            high_dpi: true,
+            samples: 1,
+        }
+    }
+}
+
+impl WindowConfig {
+    // `1280x720`
+    pub fn set_size(&mut self, size: &str) -> Result<(), String> {
+        let parsed = size
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+        match parsed {
+            Some((width, height)) if width > 0 && height > 0 => {
+                self.width = width;
+                self.height = height;
+                Ok(())
+            }
+            _ => Err(format!("`{}` isn't a window size, it takes <width>x<height>", size)),
+        }
+    }
+
+    pub fn set_samples(&mut self, samples: &str) -> Result<(), String> {
+        match samples.parse() {
+            Ok(samples) if [1, 2, 4, 8, 16].contains(&samples) => {
+                self.samples = samples;
+                Ok(())
+            }
+            _ => Err(format!("`{}` samples, it takes 1, 2, 4, 8 or 16", samples)),
+        }
+    }
+
+    pub fn conf(&self) -> Conf {
+        Conf {
+            window_title: "threebody".to_string(),
+            window_width: self.width,
+            window_height: self.height,
+            fullscreen: self.fullscreen,
+            high_dpi: self.high_dpi,
+            sample_count: self.samples,
+            window_resizable: true,
+            ..Default::default()
+        }
+    }
+}
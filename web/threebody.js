@@ -0,0 +1,76 @@
+// embeds the simulator in a page. Needs macroquad's mq_js_bundle.js loaded first, which
+// renders into the canvas with id "glcanvas", and after it sapp_jsutils.js and quad-url.js
+// for the egui control panel
+//
+//   threebody.onEvent(event => console.log(event.type, event));
+//   threebody.init(document.getElementById("glcanvas"), { preset: "figure8" });
+//   threebody.setParam("G", 2);
+//
+// events are plain objects: { type: "message", text } for every state change the app
+// announces, { type: "collisions", count } per step with collisions and { type: "error",
+// message } for a scenario that doesn't parse
+const threebody = (function () {
+    const encoder = new TextEncoder();
+    const decoder = new TextDecoder();
+    const listeners = [];
+    // calls made before the module is running, replayed once it is
+    let pending = [];
+
+    function ready(call) {
+        if (wasm_exports) {
+            call();
+        } else {
+            pending.push(call);
+        }
+    }
+
+    // copies the string into memory the Rust side allocated and takes ownership of
+    function send(name, text, ...rest) {
+        const bytes = encoder.encode(text);
+        const ptr = wasm_exports.threebody_alloc(bytes.length);
+        new Uint8Array(wasm_memory.buffer, ptr, bytes.length).set(bytes);
+        wasm_exports[name](ptr, bytes.length, ...rest);
+    }
+
+    miniquad_add_plugin({
+        name: "threebody",
+        version: "0.1.0",
+        register_plugin: function (importObject) {
+            importObject.env.threebody_event = function (ptr, len) {
+                const text = decoder.decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+                const event = JSON.parse(text);
+                listeners.forEach(listener => listener(event));
+            };
+        },
+        // runs right before the simulation's main, so a scenario is in place for frame one
+        on_init: function () {
+            pending.forEach(call => call());
+            pending = [];
+        },
+    });
+
+    return {
+        // `scenario` is a scenario object or its JSON text, see src/scenario.rs
+        init: function (canvas, scenario, wasmPath) {
+            if (canvas.id !== "glcanvas") {
+                throw new Error("threebody renders into the canvas with id \"glcanvas\"");
+            }
+            if (scenario !== undefined && scenario !== null) {
+                const json = typeof scenario === "string" ? scenario : JSON.stringify(scenario);
+                ready(() => send("threebody_scenario", json));
+            }
+            load(wasmPath || "threebody.wasm");
+        },
+        // any name the console's `set` takes: G, dt, theta, throw_strength, ...
+        setParam: function (name, value) {
+            ready(() => send("threebody_set_param", name, value));
+        },
+        // a line of the in-app console, for everything `setParam` doesn't cover
+        command: function (line) {
+            ready(() => send("threebody_command", line));
+        },
+        onEvent: function (listener) {
+            listeners.push(listener);
+        },
+    };
+})();